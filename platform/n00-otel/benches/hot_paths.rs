@@ -0,0 +1,186 @@
+//! Microbenchmarks for the layer's hot paths: span creation/close under
+//! sampled and unsampled providers, in-span event recording (with and
+//! without limits), late field recording, context access, header
+//! injection, and ID hex formatting. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use n00_otel::OpenTelemetrySpanExt;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::hint::black_box;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+/// A provider that samples everything but exports into the void, so the
+/// benchmarks measure the layer rather than an exporter.
+fn provider() -> SdkTracerProvider {
+    SdkTracerProvider::builder().build()
+}
+
+fn bench_span_lifecycle(c: &mut Criterion) {
+    let provider = provider();
+    let subscriber =
+        Registry::default().with(n00_otel::layer().with_tracer(provider.tracer("bench")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    c.bench_function("span_create_enter_close_sampled", |b| {
+        b.iter(|| {
+            tracing::info_span!("bench_span", answer = 42).in_scope(|| black_box(1));
+        })
+    });
+}
+
+fn bench_span_lifecycle_unsampled(c: &mut Criterion) {
+    // Head sampler drops everything: measures the cost of spans the
+    // backend never sees, which dominates at low sampling ratios.
+    let provider = SdkTracerProvider::builder()
+        .with_sampler(opentelemetry_sdk::trace::Sampler::AlwaysOff)
+        .build();
+    let subscriber =
+        Registry::default().with(n00_otel::layer().with_tracer(provider.tracer("bench")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    c.bench_function("span_create_enter_close_unsampled", |b| {
+        b.iter(|| {
+            tracing::info_span!("bench_span", answer = 42).in_scope(|| black_box(1));
+        })
+    });
+}
+
+fn bench_dropped_span_pooling(c: &mut Criterion) {
+    // Every span is rate-limited away: the close path recycles attribute
+    // and event buffers through the pool. Compare against
+    // span_create_enter_close_sampled for the exporting path.
+    let provider = provider();
+    let subscriber = Registry::default().with(
+        n00_otel::layer()
+            .with_span_rate_limit(0.0, 1)
+            .with_tracer(provider.tracer("bench")),
+    );
+    let _guard = tracing::subscriber::set_default(subscriber);
+    tracing::info_span!("warmup").in_scope(|| {}); // consume the burst token
+
+    c.bench_function("span_close_dropped_pooled", |b| {
+        b.iter(|| {
+            tracing::info_span!("bench_span", answer = 42).in_scope(|| {
+                tracing::info!("buffered then recycled");
+            });
+        })
+    });
+}
+
+fn bench_on_record(c: &mut Criterion) {
+    let provider = provider();
+    let subscriber =
+        Registry::default().with(n00_otel::layer().with_tracer(provider.tracer("bench")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("record_sink", late = tracing::field::Empty);
+    c.bench_function("on_record_late_field", |b| {
+        b.iter(|| span.record("late", black_box(42)));
+    });
+}
+
+fn bench_header_injection(c: &mut Criterion) {
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::TraceContextExt as _;
+
+    let cx = opentelemetry::Context::new().with_remote_span_context(
+        opentelemetry::trace::SpanContext::new(
+            opentelemetry::trace::TraceId::from_bytes(
+                0x0af7651916cd43dd8448eb211c80319c_u128.to_be_bytes(),
+            ),
+            opentelemetry::trace::SpanId::from_bytes(0xb7ad6b7169203331_u64.to_be_bytes()),
+            opentelemetry::trace::TraceFlags::SAMPLED,
+            true,
+            Default::default(),
+        ),
+    );
+
+    let b3 = n00_otel::propagation::B3Propagator::new();
+    c.bench_function("inject_b3_single", |b| {
+        b.iter(|| {
+            let mut carrier: std::collections::HashMap<String, String> =
+                std::collections::HashMap::with_capacity(1);
+            b3.inject_context(black_box(&cx), &mut carrier);
+            black_box(carrier)
+        })
+    });
+
+    let w3c = opentelemetry_sdk::propagation::TraceContextPropagator::new();
+    c.bench_function("inject_traceparent", |b| {
+        b.iter(|| {
+            let mut carrier: std::collections::HashMap<String, String> =
+                std::collections::HashMap::with_capacity(2);
+            w3c.inject_context(black_box(&cx), &mut carrier);
+            black_box(carrier)
+        })
+    });
+}
+
+fn bench_event_recording(c: &mut Criterion) {
+    let provider = provider();
+    let subscriber =
+        Registry::default().with(n00_otel::layer().with_tracer(provider.tracer("bench")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("event_sink");
+    let _entered = span.enter();
+    c.bench_function("event_unlimited", |b| {
+        b.iter(|| tracing::info!(step = 7, "tick"));
+    });
+}
+
+fn bench_event_recording_with_tail_limit(c: &mut Criterion) {
+    let provider = provider();
+    let subscriber = Registry::default().with(
+        n00_otel::layer()
+            .with_tail_events(64)
+            .with_tracer(provider.tracer("bench")),
+    );
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("event_sink");
+    let _entered = span.enter();
+    c.bench_function("event_tail_ring", |b| {
+        b.iter(|| tracing::info!(step = 7, "tick"));
+    });
+}
+
+fn bench_context_access(c: &mut Criterion) {
+    let provider = provider();
+    let subscriber =
+        Registry::default().with(n00_otel::layer().with_tracer(provider.tracer("bench")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("ctx");
+    c.bench_function("span_context", |b| {
+        b.iter(|| black_box(span.context()));
+    });
+}
+
+fn bench_hex(c: &mut Criterion) {
+    let trace_id = opentelemetry::trace::TraceId::from_bytes(
+        0x0af7651916cd43dd8448eb211c80319c_u128.to_be_bytes(),
+    );
+    c.bench_function("trace_id_hex", |b| {
+        b.iter(|| black_box(n00_otel::hex::trace_id_hex(black_box(trace_id))));
+    });
+    c.bench_function("trace_id_to_string", |b| {
+        b.iter(|| black_box(black_box(trace_id).to_string()));
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_span_lifecycle,
+    bench_span_lifecycle_unsampled,
+    bench_dropped_span_pooling,
+    bench_on_record,
+    bench_event_recording,
+    bench_event_recording_with_tail_limit,
+    bench_context_access,
+    bench_header_injection,
+    bench_hex
+);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+fn main() {
+    // Capture the compiler version for the process.runtime.version
+    // resource attribute; "unknown" keeps builds working without rustc on
+    // PATH (distcc-style wrappers).
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|v| v.trim().trim_start_matches("rustc ").to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=N00_OTEL_RUSTC_VERSION={version}");
+}
@@ -0,0 +1,231 @@
+//! Adaptive head sampling driven by recent error rates.
+//!
+//! Head sampling at a flat ratio treats the healthy 99% and the failing 1%
+//! of operations identically. [`AdaptiveSampler`] keeps a short sliding
+//! window of outcomes per span name — fed back by the layer at span close —
+//! and boosts the sampling rate for names whose recent error rate crosses a
+//! threshold.
+//!
+//! Install the same handle in both places:
+//!
+//! ```
+//! use opentelemetry_sdk::trace::SdkTracerProvider;
+//!
+//! let sampler = n00_otel::AdaptiveSampler::builder()
+//!     .with_base_ratio(0.01)
+//!     .with_boost_ratio(1.0)
+//!     .with_error_rate_threshold(0.05)
+//!     .build();
+//! let provider = SdkTracerProvider::builder()
+//!     .with_sampler(sampler.clone())
+//!     .build();
+//! let layer = n00_otel::layer::<tracing_subscriber::Registry>()
+//!     .with_adaptive_feedback(sampler);
+//! # drop((provider, layer));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+/// Builder for [`AdaptiveSampler`].
+pub struct AdaptiveSamplerBuilder {
+    base_ratio: f64,
+    boost_ratio: f64,
+    error_rate_threshold: f64,
+    window: Duration,
+    min_samples: u64,
+}
+
+impl AdaptiveSamplerBuilder {
+    /// Sampling ratio for names with a healthy recent error rate.
+    /// Defaults to `0.01`.
+    pub fn with_base_ratio(mut self, ratio: f64) -> Self {
+        self.base_ratio = ratio;
+        self
+    }
+
+    /// Sampling ratio for names whose error rate crossed the threshold.
+    /// Defaults to `1.0`.
+    pub fn with_boost_ratio(mut self, ratio: f64) -> Self {
+        self.boost_ratio = ratio;
+        self
+    }
+
+    /// Error rate (errors / outcomes over the window) at which the boost
+    /// kicks in. Defaults to `0.05`.
+    pub fn with_error_rate_threshold(mut self, threshold: f64) -> Self {
+        self.error_rate_threshold = threshold;
+        self
+    }
+
+    /// Length of one sliding-window half; rates are computed over the
+    /// current and previous half. Defaults to 30 seconds.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Outcomes required in the window before the rate is trusted;
+    /// below it the base ratio applies. Defaults to 10.
+    pub fn with_min_samples(mut self, min_samples: u64) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Build the sampler.
+    pub fn build(self) -> AdaptiveSampler {
+        AdaptiveSampler {
+            shared: Arc::new(Shared {
+                windows: Mutex::new(HashMap::new()),
+                base_ratio: self.base_ratio,
+                boost_ratio: self.boost_ratio,
+                error_rate_threshold: self.error_rate_threshold,
+                window: self.window,
+                min_samples: self.min_samples,
+            }),
+        }
+    }
+}
+
+/// A [`ShouldSample`] whose per-name ratio adapts to recent error rates.
+#[derive(Clone)]
+pub struct AdaptiveSampler {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for AdaptiveSampler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdaptiveSampler").finish_non_exhaustive()
+    }
+}
+
+struct Shared {
+    windows: Mutex<HashMap<String, Window>>,
+    base_ratio: f64,
+    boost_ratio: f64,
+    error_rate_threshold: f64,
+    window: Duration,
+    min_samples: u64,
+}
+
+#[derive(Default)]
+struct Window {
+    rotated_at: Option<Instant>,
+    current: (u64, u64),
+    previous: (u64, u64),
+}
+
+impl AdaptiveSampler {
+    /// Start building a sampler with the defaults.
+    pub fn builder() -> AdaptiveSamplerBuilder {
+        AdaptiveSamplerBuilder {
+            base_ratio: 0.01,
+            boost_ratio: 1.0,
+            error_rate_threshold: 0.05,
+            window: Duration::from_secs(30),
+            min_samples: 10,
+        }
+    }
+
+    /// Record a span outcome; called by the layer at span close when
+    /// installed via
+    /// [`with_adaptive_feedback`](crate::OpenTelemetryLayer::with_adaptive_feedback).
+    pub fn record_outcome(&self, span_name: &str, is_error: bool) {
+        let mut windows = self.shared.windows.lock().unwrap();
+        let window = match windows.get_mut(span_name) {
+            Some(window) => window,
+            None => windows.entry(span_name.to_string()).or_default(),
+        };
+        let now = Instant::now();
+        let rotated_at = *window.rotated_at.get_or_insert(now);
+        if now.duration_since(rotated_at) >= self.shared.window {
+            window.previous = window.current;
+            window.current = (0, 0);
+            window.rotated_at = Some(now);
+        }
+        window.current.0 += 1;
+        if is_error {
+            window.current.1 += 1;
+        }
+    }
+
+    fn ratio_for(&self, span_name: &str) -> f64 {
+        let windows = self.shared.windows.lock().unwrap();
+        let Some(window) = windows.get(span_name) else {
+            return self.shared.base_ratio;
+        };
+        let total = window.current.0 + window.previous.0;
+        let errors = window.current.1 + window.previous.1;
+        if total < self.shared.min_samples {
+            return self.shared.base_ratio;
+        }
+        if errors as f64 / total as f64 >= self.shared.error_rate_threshold {
+            self.shared.boost_ratio
+        } else {
+            self.shared.base_ratio
+        }
+    }
+}
+
+impl ShouldSample for AdaptiveSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        // ParentBased semantics for child spans, adaptive ratio at the root.
+        Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(self.ratio_for(name))))
+            .should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::SamplingDecision;
+
+    fn decide(sampler: &AdaptiveSampler, name: &str, trace_id: u128) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from_bytes(trace_id.to_be_bytes()),
+                name,
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn boosts_names_with_recent_errors() {
+        let sampler = AdaptiveSampler::builder()
+            .with_base_ratio(0.0)
+            .with_boost_ratio(1.0)
+            .with_error_rate_threshold(0.5)
+            .with_min_samples(4)
+            .build();
+
+        // Below min samples: base ratio applies.
+        assert_eq!(decide(&sampler, "checkout", 7), SamplingDecision::Drop);
+
+        for _ in 0..4 {
+            sampler.record_outcome("checkout", true);
+            sampler.record_outcome("browse", false);
+        }
+        assert_eq!(
+            decide(&sampler, "checkout", 7),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(decide(&sampler, "browse", 7), SamplingDecision::Drop);
+    }
+}
@@ -0,0 +1,129 @@
+//! Typed attribute helpers for durations and byte sizes.
+//!
+//! Attributes like `elapsed = format!("{:?}", d)` export as strings that no
+//! backend can aggregate. These helpers pin the unit in the key and the type
+//! in the value, so dashboards get numbers:
+//!
+//! ```
+//! use std::time::Duration;
+//! use n00_otel::attrs;
+//!
+//! let kv = attrs::duration_ms("db.elapsed", Duration::from_micros(2500));
+//! assert_eq!(kv.key.as_str(), "db.elapsed_ms");
+//! assert_eq!(kv.value, opentelemetry::Value::F64(2.5));
+//! ```
+
+use std::time::Duration;
+
+use opentelemetry::{Key, KeyValue, Value};
+
+/// A duration attribute in (fractional) milliseconds; the key gains a
+/// `_ms` suffix unless it already carries one.
+pub fn duration_ms(key: impl Into<Key>, duration: Duration) -> KeyValue {
+    KeyValue::new(
+        suffixed(key.into(), "_ms"),
+        Value::F64(duration.as_secs_f64() * 1_000.0),
+    )
+}
+
+/// A duration attribute in whole nanoseconds (`_ns` suffix), for timings
+/// where sub-millisecond resolution matters. Saturates at `i64::MAX`.
+pub fn duration_ns(key: impl Into<Key>, duration: Duration) -> KeyValue {
+    KeyValue::new(
+        suffixed(key.into(), "_ns"),
+        Value::I64(i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX)),
+    )
+}
+
+/// A byte-size attribute (`_bytes` suffix). Saturates at `i64::MAX`.
+pub fn byte_size(key: impl Into<Key>, bytes: u64) -> KeyValue {
+    KeyValue::new(
+        suffixed(key.into(), "_bytes"),
+        Value::I64(i64::try_from(bytes).unwrap_or(i64::MAX)),
+    )
+}
+
+fn suffixed(key: Key, suffix: &str) -> Key {
+    let s = key.as_str();
+    if s.ends_with(suffix) {
+        key
+    } else {
+        Key::new(format!("{s}{suffix}"))
+    }
+}
+
+/// Parse a human-readable duration like `"250ms"`, `"1.5s"`, `"2m"`,
+/// `"3h"`, or a bare number of seconds.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "ns" => number / 1_000_000_000.0,
+        "us" | "µs" => number / 1_000_000.0,
+        "ms" => number / 1_000.0,
+        "" | "s" => number,
+        "m" | "min" => number * 60.0,
+        "h" => number * 3_600.0,
+        "d" => number * 86_400.0,
+        _ => return None,
+    };
+    (seconds >= 0.0 && seconds.is_finite()).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Parse a human-readable byte size like `"512"`, `"4KiB"`, `"1.5 MB"`.
+/// Binary suffixes (`KiB`) are 1024-based, decimal (`KB`) 1000-based.
+pub fn parse_byte_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let factor: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "kib" => 1 << 10,
+        "mb" => 1_000_000,
+        "mib" => 1 << 20,
+        "gb" => 1_000_000_000,
+        "gib" => 1 << 30,
+        "tb" => 1_000_000_000_000,
+        "tib" => 1 << 40,
+        _ => return None,
+    };
+    let bytes = number * factor as f64;
+    (bytes >= 0.0 && bytes.is_finite()).then_some(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_and_byte_attrs_are_typed_and_suffixed() {
+        let kv = duration_ns("span.busy", Duration::from_nanos(42));
+        assert_eq!(kv.key.as_str(), "span.busy_ns");
+        assert_eq!(kv.value, Value::I64(42));
+
+        let kv = byte_size("payload_bytes", 1024);
+        assert_eq!(kv.key.as_str(), "payload_bytes");
+        assert_eq!(kv.value, Value::I64(1024));
+    }
+
+    #[test]
+    fn parses_durations_and_sizes() {
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("nope"), None);
+
+        assert_eq!(parse_byte_size("512"), Some(512));
+        assert_eq!(parse_byte_size("4KiB"), Some(4096));
+        assert_eq!(parse_byte_size("1.5 MB"), Some(1_500_000));
+        assert_eq!(parse_byte_size("many"), None);
+    }
+}
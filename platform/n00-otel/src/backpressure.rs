@@ -0,0 +1,140 @@
+//! Exporter back-pressure surfaced to the layer.
+//!
+//! When the export path falls behind (slow collector, network stall), the
+//! healthy failure mode is to shed detail at the source instead of queueing
+//! without bound. [`BackpressureSignal`] is the shared gauge: an exporter
+//! wrapper ([`BackpressureExporter`]) tracks in-flight spans, and a layer
+//! configured with [`with_backpressure`] stops buffering span events while
+//! the signal reports overload (events are counted instead, via the usual
+//! `otel.dropped_event_count` accounting).
+//!
+//! ```
+//! use opentelemetry_sdk::trace::SdkTracerProvider;
+//!
+//! let signal = n00_otel::BackpressureSignal::with_threshold(10_000);
+//! # let exporter = opentelemetry_sdk::trace::InMemorySpanExporter::default();
+//! let provider = SdkTracerProvider::builder()
+//!     .with_simple_exporter(n00_otel::BackpressureExporter::new(exporter, signal.clone()))
+//!     .build();
+//! let layer = n00_otel::layer::<tracing_subscriber::Registry>()
+//!     .with_backpressure(signal);
+//! # drop((provider, layer));
+//! ```
+//!
+//! [`with_backpressure`]: crate::OpenTelemetryLayer::with_backpressure
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+/// Shared gauge of spans currently in flight towards the exporter.
+#[derive(Clone, Debug, Default)]
+pub struct BackpressureSignal {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    in_flight: AtomicU64,
+    threshold: AtomicU64,
+    span_shed_threshold: AtomicU64,
+}
+
+impl BackpressureSignal {
+    /// A signal that reports overload once more than `threshold` spans are
+    /// in flight.
+    pub fn with_threshold(threshold: u64) -> Self {
+        let signal = BackpressureSignal::default();
+        signal.inner.threshold.store(threshold, Ordering::Relaxed);
+        signal
+    }
+
+    /// A signal with the full escalation policy: above `shed_events` in
+    /// flight the layer stops buffering span events; above `shed_spans`
+    /// it additionally stops exporting non-error spans, so a saturated
+    /// exporter only receives the spans worth keeping.
+    pub fn with_thresholds(shed_events: u64, shed_spans: u64) -> Self {
+        let signal = Self::with_threshold(shed_events);
+        signal
+            .inner
+            .span_shed_threshold
+            .store(shed_spans, Ordering::Relaxed);
+        signal
+    }
+
+    /// Spans currently being exported.
+    pub fn in_flight(&self) -> u64 {
+        self.inner.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Whether the export path is currently considered overloaded (the
+    /// event-shedding stage).
+    pub fn is_overloaded(&self) -> bool {
+        let threshold = self.inner.threshold.load(Ordering::Relaxed);
+        threshold > 0 && self.in_flight() > threshold
+    }
+
+    /// Whether overload has escalated far enough to shed non-error spans
+    /// (the second stage of [`with_thresholds`](Self::with_thresholds)).
+    pub fn should_shed_spans(&self) -> bool {
+        let threshold = self.inner.span_shed_threshold.load(Ordering::Relaxed);
+        threshold > 0 && self.in_flight() > threshold
+    }
+
+    /// Record `n` more spans in flight. Public so custom export paths
+    /// (anything other than [`BackpressureExporter`]) can drive the signal.
+    pub fn add(&self, n: u64) {
+        self.inner.in_flight.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Record `n` spans as no longer in flight.
+    pub fn sub(&self, n: u64) {
+        self.inner.in_flight.fetch_sub(n, Ordering::Relaxed);
+    }
+}
+
+/// A [`SpanExporter`] wrapper feeding a [`BackpressureSignal`].
+#[derive(Debug)]
+pub struct BackpressureExporter<E> {
+    inner: E,
+    signal: BackpressureSignal,
+}
+
+impl<E> BackpressureExporter<E> {
+    /// Wrap `inner`, tracking its in-flight spans in `signal`.
+    pub fn new(inner: E, signal: BackpressureSignal) -> Self {
+        BackpressureExporter { inner, signal }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for BackpressureExporter<E> {
+    fn export(
+        &self,
+        batch: Vec<SpanData>,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let count = batch.len() as u64;
+        self.signal.add(count);
+        let signal = self.signal.clone();
+        let inner = self.inner.export(batch);
+        async move {
+            let result = inner.await;
+            signal.sub(count);
+            result
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: std::time::Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
@@ -0,0 +1,139 @@
+//! Export spans in the Chrome trace-event format.
+//!
+//! The produced file loads into `about://tracing`, Perfetto and speedscope:
+//! each span becomes one complete (`"ph": "X"`) event with microsecond
+//! timestamps, the trace/span IDs and attributes as `args`, and the span's
+//! thread attributes mapped to `tid`. The writer emits a JSON array
+//! incrementally; Chrome's loader accepts a missing closing bracket, and
+//! [`shutdown`] writes it when the provider shuts down cleanly.
+//!
+//! ```no_run
+//! use opentelemetry_sdk::trace::SdkTracerProvider;
+//!
+//! let file = std::fs::File::create("trace.json").unwrap();
+//! let provider = SdkTracerProvider::builder()
+//!     .with_simple_exporter(n00_otel::ChromeTraceExporter::new(file))
+//!     .build();
+//! # drop(provider);
+//! ```
+//!
+//! [`shutdown`]: opentelemetry_sdk::trace::SpanExporter::shutdown_with_timeout
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+
+/// A [`SpanExporter`] writing the Chrome trace-event JSON array format.
+#[derive(Debug)]
+pub struct ChromeTraceExporter<W> {
+    state: Mutex<State<W>>,
+}
+
+#[derive(Debug)]
+struct State<W> {
+    writer: W,
+    events_written: bool,
+}
+
+impl<W: Write + Send + Sync + std::fmt::Debug> ChromeTraceExporter<W> {
+    /// An exporter writing a trace-event array to `writer`.
+    pub fn new(writer: W) -> Self {
+        ChromeTraceExporter {
+            state: Mutex::new(State {
+                writer,
+                events_written: false,
+            }),
+        }
+    }
+}
+
+fn micros(time: std::time::SystemTime) -> u128 {
+    time.duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_micros())
+        .unwrap_or(0)
+}
+
+fn span_event(span: &SpanData) -> serde_json::Value {
+    let ts = micros(span.start_time);
+    let dur = micros(span.end_time).saturating_sub(ts);
+    let mut args = serde_json::Map::new();
+    args.insert(
+        "trace_id".into(),
+        serde_json::Value::String(crate::hex::trace_id_hex(span.span_context.trace_id()).to_string()),
+    );
+    args.insert(
+        "span_id".into(),
+        serde_json::Value::String(crate::hex::span_id_hex(span.span_context.span_id()).to_string()),
+    );
+    let mut tid = 0u64;
+    for kv in &span.attributes {
+        if kv.key.as_str() == "thread.id" {
+            if let opentelemetry::Value::I64(id) = kv.value {
+                tid = id as u64;
+            }
+        }
+        args.insert(
+            kv.key.as_str().to_string(),
+            serde_json::Value::String(kv.value.to_string()),
+        );
+    }
+    serde_json::json!({
+        "name": span.name.as_ref(),
+        "cat": span.instrumentation_scope.name(),
+        "ph": "X",
+        "ts": ts,
+        "dur": dur,
+        "pid": std::process::id(),
+        "tid": tid,
+        "args": args,
+    })
+}
+
+impl<W: Write + Send + Sync + std::fmt::Debug> SpanExporter for ChromeTraceExporter<W> {
+    fn export(
+        &self,
+        batch: Vec<SpanData>,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let result = (|| {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|_| OTelSdkError::InternalFailure("writer poisoned".into()))?;
+            for span in &batch {
+                let prefix = if state.events_written { ",\n" } else { "[\n" };
+                state.events_written = true;
+                let event = span_event(span);
+                let io = state
+                    .writer
+                    .write_all(prefix.as_bytes())
+                    .and_then(|()| {
+                        serde_json::to_writer(&mut state.writer, &event)
+                            .map_err(std::io::Error::other)
+                    });
+                io.map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+            }
+            state
+                .writer
+                .flush()
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+        })();
+        std::future::ready(result)
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: std::time::Duration) -> OTelSdkResult {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| OTelSdkError::InternalFailure("writer poisoned".into()))?;
+        if state.events_written {
+            state
+                .writer
+                .write_all(b"\n]\n")
+                .and_then(|()| state.writer.flush())
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+//! Pluggable wall-clock source for deterministic tests and simulation.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of wall-clock timestamps for span start, end and event times.
+///
+/// Installed via [`OpenTelemetryLayer::with_clock`]; the default is the
+/// system clock. A simulation or test injects a [`ManualClock`] and drives
+/// time explicitly, making exported durations exact instead of "roughly
+/// the sleep we did".
+///
+/// [`OpenTelemetryLayer::with_clock`]: crate::OpenTelemetryLayer::with_clock
+pub trait Clock: Send + Sync {
+    /// The current wall-clock time.
+    fn now(&self) -> SystemTime;
+}
+
+/// The real system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        crate::time::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl ManualClock {
+    /// A manual clock starting at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        ManualClock {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Move the clock forward by `delta`.
+    pub fn advance(&self, delta: Duration) {
+        *self.now.lock().unwrap() += delta;
+    }
+
+    /// Jump the clock to an absolute time.
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,108 @@
+//! Vendor conventions mapping applied to spans at export time.
+
+use opentelemetry::trace::{SpanBuilder, SpanKind, Status};
+use opentelemetry::KeyValue;
+
+/// Which attribute conventions exported spans follow.
+///
+/// Configured via [`OpenTelemetryLayer::with_conventions`]; the default is
+/// plain OpenTelemetry semantics with no extra mapping.
+///
+/// [`OpenTelemetryLayer::with_conventions`]: crate::OpenTelemetryLayer::with_conventions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConventionsMode {
+    /// Export spans as recorded, OpenTelemetry semantics only.
+    #[default]
+    OpenTelemetry,
+
+    /// Add the attributes Datadog's OTLP ingestion maps onto its trace
+    /// model:
+    ///
+    /// * `operation.name` — the `tracing` target (low cardinality), which
+    ///   Datadog uses as the metric-worthy operation
+    /// * `resource.name` — the span name (what the operation acted on)
+    /// * `span.type` — derived from the span kind (`web`, `http`, `queue`)
+    /// * `error.msg` — the status description of failed spans
+    ///
+    /// Attributes already present are not overwritten, so explicit values
+    /// win over the mapping.
+    Datadog,
+
+    /// Map fields recorded with the `xray.annotation.` prefix onto AWS
+    /// X-Ray annotations: the prefix is stripped from the attribute key
+    /// and the key names are listed in the `aws.xray.annotations` string
+    /// array, which the ADOT/X-Ray exporters promote to indexed,
+    /// filterable annotations (everything else stays metadata). Pairs with
+    /// [`XrayIdGenerator`] and [`XrayPropagator`].
+    ///
+    /// [`XrayIdGenerator`]: crate::XrayIdGenerator
+    /// [`XrayPropagator`]: crate::propagation::XrayPropagator
+    Xray,
+}
+
+/// Apply the conventions mapping to a finished builder. `target` is the
+/// span's `tracing` target.
+pub(crate) fn apply(mode: ConventionsMode, builder: &mut SpanBuilder, target: &str) {
+    match mode {
+        ConventionsMode::OpenTelemetry => {}
+        ConventionsMode::Datadog => apply_datadog(builder, target),
+        ConventionsMode::Xray => apply_xray(builder),
+    }
+}
+
+const XRAY_ANNOTATION_PREFIX: &str = "xray.annotation.";
+
+fn apply_xray(builder: &mut SpanBuilder) {
+    let Some(attributes) = builder.attributes.as_mut() else {
+        return;
+    };
+    let mut annotation_keys = Vec::new();
+    for kv in attributes.iter_mut() {
+        if let Some(stripped) = kv.key.as_str().strip_prefix(XRAY_ANNOTATION_PREFIX) {
+            let stripped = stripped.to_string();
+            kv.key = opentelemetry::Key::new(stripped.clone());
+            annotation_keys.push(opentelemetry::StringValue::from(stripped));
+        }
+    }
+    if !annotation_keys.is_empty() {
+        attributes.push(KeyValue::new(
+            "aws.xray.annotations",
+            opentelemetry::Value::Array(opentelemetry::Array::String(annotation_keys)),
+        ));
+    }
+}
+
+fn apply_datadog(builder: &mut SpanBuilder, target: &str) {
+    let span_type = match builder.span_kind {
+        Some(SpanKind::Server) => "web",
+        Some(SpanKind::Client) => "http",
+        Some(SpanKind::Producer) | Some(SpanKind::Consumer) => "queue",
+        _ => "custom",
+    };
+    let resource = builder.name.to_string();
+    let error_msg = match &builder.status {
+        Status::Error { description } if !description.is_empty() => {
+            Some(description.to_string())
+        }
+        _ => None,
+    };
+
+    let attributes = builder.attributes.get_or_insert_with(Vec::new);
+    let missing = |attributes: &[KeyValue], key: &str| {
+        !attributes.iter().any(|kv| kv.key.as_str() == key)
+    };
+    if missing(attributes, "operation.name") {
+        attributes.push(KeyValue::new("operation.name", target.to_string()));
+    }
+    if missing(attributes, "resource.name") {
+        attributes.push(KeyValue::new("resource.name", resource));
+    }
+    if missing(attributes, "span.type") {
+        attributes.push(KeyValue::new("span.type", span_type));
+    }
+    if let Some(error_msg) = error_msg {
+        if missing(attributes, "error.msg") {
+            attributes.push(KeyValue::new("error.msg", error_msg));
+        }
+    }
+}
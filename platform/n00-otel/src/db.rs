@@ -0,0 +1,157 @@
+//! Database client span helpers.
+//!
+//! Builds `Client`-kind spans following the `db.*` semantic conventions,
+//! with statement capture that is bounded (multi-megabyte INSERTs have no
+//! place in a trace) and composable with the layer's
+//! [statement sanitizer](crate::OpenTelemetryLayer::with_db_statement_sanitizer).
+
+use opentelemetry::KeyValue;
+
+use crate::semconv;
+
+/// Default cap on captured statement text.
+pub const DEFAULT_STATEMENT_CAPTURE_LIMIT: usize = 2048;
+
+/// Truncate a statement for capture, appending a marker with the elided
+/// length. Respects char boundaries.
+pub fn capture_statement(statement: &str, max_len: usize) -> String {
+    if statement.len() <= max_len {
+        return statement.to_string();
+    }
+    let mut cut = max_len;
+    while cut > 0 && !statement.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!(
+        "{}… [{} chars truncated]",
+        &statement[..cut],
+        statement.len() - cut
+    )
+}
+
+/// Builder for a database client span.
+///
+/// ```
+/// let span = n00_otel::db::DbSpan::new("postgresql", "SELECT")
+///     .with_namespace("orders")
+///     .with_statement("SELECT * FROM orders WHERE id = $1")
+///     .span();
+/// span.in_scope(|| { /* run the query */ });
+/// ```
+#[derive(Clone, Debug)]
+pub struct DbSpan {
+    system: String,
+    operation: String,
+    namespace: Option<String>,
+    statement: Option<String>,
+    capture_limit: usize,
+}
+
+impl DbSpan {
+    /// A client span for `operation` (e.g. `SELECT`) against the given
+    /// database system (e.g. `postgresql`).
+    pub fn new(system: impl Into<String>, operation: impl Into<String>) -> Self {
+        DbSpan {
+            system: system.into(),
+            operation: operation.into(),
+            namespace: None,
+            statement: None,
+            capture_limit: DEFAULT_STATEMENT_CAPTURE_LIMIT,
+        }
+    }
+
+    /// The database/schema name; also becomes part of the span name per
+    /// the `{operation} {target}` convention.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Capture the query text (subject to the capture limit).
+    pub fn with_statement(mut self, statement: impl Into<String>) -> Self {
+        self.statement = Some(statement.into());
+        self
+    }
+
+    /// Override the statement capture limit (default 2 KiB).
+    pub fn with_capture_limit(mut self, max_len: usize) -> Self {
+        self.capture_limit = max_len;
+        self
+    }
+
+    /// The conventional span name.
+    pub fn span_name(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{} {namespace}", self.operation),
+            None => self.operation.clone(),
+        }
+    }
+
+    /// Create the `tracing` span carrying the `db.*` attributes. The
+    /// returned span has `Client` kind and the conventional name.
+    pub fn span(&self) -> tracing::Span {
+        let span = tracing::info_span!(
+            "db.query",
+            otel.name = %self.span_name(),
+            otel.kind = "client",
+        );
+        crate::OpenTelemetrySpanExt::set_attribute(
+            &span,
+            semconv::DB_SYSTEM,
+            self.system.clone(),
+        );
+        crate::OpenTelemetrySpanExt::set_attribute(
+            &span,
+            semconv::DB_OPERATION_NAME,
+            self.operation.clone(),
+        );
+        if let Some(namespace) = &self.namespace {
+            crate::OpenTelemetrySpanExt::set_attribute(
+                &span,
+                semconv::DB_NAMESPACE,
+                namespace.clone(),
+            );
+        }
+        if let Some(statement) = &self.statement {
+            crate::OpenTelemetrySpanExt::set_attribute(
+                &span,
+                semconv::DB_QUERY_TEXT,
+                capture_statement(statement, self.capture_limit),
+            );
+        }
+        span
+    }
+
+    /// The attribute list alone, for callers managing their own spans.
+    pub fn into_attributes(self) -> Vec<KeyValue> {
+        let mut attrs = semconv::DbAttributes::statement(
+            self.system,
+            self.statement
+                .as_deref()
+                .map(|s| capture_statement(s, self.capture_limit))
+                .unwrap_or_default(),
+        )
+        .with_operation(self.operation)
+        .into_attributes();
+        if let Some(namespace) = self.namespace {
+            attrs.push(KeyValue::new(semconv::DB_NAMESPACE, namespace));
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_are_truncated_at_char_boundaries() {
+        assert_eq!(capture_statement("short", 100), "short");
+        let truncated = capture_statement(&"x".repeat(100), 10);
+        assert!(truncated.starts_with("xxxxxxxxxx…"));
+        assert!(truncated.contains("90 chars truncated"));
+        // Multi-byte boundary safety.
+        let unicode = capture_statement("ééééé", 3);
+        assert!(unicode.starts_with("é…"));
+    }
+}
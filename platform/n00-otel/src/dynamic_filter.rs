@@ -0,0 +1,111 @@
+//! Runtime-reloadable per-target enablement for the layer.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use tracing::level_filters::LevelFilter;
+use tracing_core::Metadata;
+
+/// A shared, runtime-mutable map of target prefixes to level filters,
+/// consulted by the layer for every span and event when installed via
+/// [`OpenTelemetryLayer::with_dynamic_targets`].
+///
+/// Clone the handle, keep one side in an admin endpoint or config watcher,
+/// and flip targets without rebuilding the subscriber:
+///
+/// ```
+/// use tracing::level_filters::LevelFilter;
+///
+/// let targets = n00_otel::DynamicTargets::new(LevelFilter::INFO);
+/// let layer = n00_otel::layer::<tracing_subscriber::Registry>()
+///     .with_dynamic_targets(targets.clone());
+/// // later, from anywhere:
+/// targets.set_target("my_app::db", LevelFilter::TRACE);
+/// # drop(layer);
+/// ```
+///
+/// Longest matching prefix wins; the default level applies elsewhere.
+///
+/// [`OpenTelemetryLayer::with_dynamic_targets`]: crate::OpenTelemetryLayer::with_dynamic_targets
+#[derive(Clone, Debug)]
+pub struct DynamicTargets {
+    inner: Arc<RwLock<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    default: LevelFilter,
+    targets: HashMap<String, LevelFilter>,
+}
+
+impl DynamicTargets {
+    /// A handle whose default level applies to every target initially.
+    pub fn new(default: LevelFilter) -> Self {
+        DynamicTargets {
+            inner: Arc::new(RwLock::new(State {
+                default,
+                targets: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Set the level for targets starting with `prefix`, taking effect for
+    /// subsequently created spans and events. The layer consults the map
+    /// per span/event, so no interest-cache rebuild is needed.
+    pub fn set_target(&self, prefix: impl Into<String>, level: LevelFilter) {
+        self.inner
+            .write()
+            .unwrap()
+            .targets
+            .insert(prefix.into(), level);
+    }
+
+    /// Remove a target override, falling back to the default level.
+    pub fn clear_target(&self, prefix: &str) {
+        self.inner.write().unwrap().targets.remove(prefix);
+    }
+
+    /// Replace the default level.
+    pub fn set_default(&self, level: LevelFilter) {
+        self.inner.write().unwrap().default = level;
+    }
+
+    /// The current configuration: default level and target overrides.
+    pub fn snapshot(&self) -> (LevelFilter, Vec<(String, LevelFilter)>) {
+        let state = self.inner.read().unwrap();
+        let mut targets: Vec<(String, LevelFilter)> = state
+            .targets
+            .iter()
+            .map(|(prefix, level)| (prefix.clone(), *level))
+            .collect();
+        targets.sort_by(|a, b| a.0.cmp(&b.0));
+        (state.default, targets)
+    }
+
+    /// Atomically replace the configuration: target overrides are swapped
+    /// wholesale and, when given, the default level too.
+    pub fn replace(
+        &self,
+        default: Option<LevelFilter>,
+        targets: impl IntoIterator<Item = (String, LevelFilter)>,
+    ) {
+        let mut state = self.inner.write().unwrap();
+        if let Some(default) = default {
+            state.default = default;
+        }
+        state.targets = targets.into_iter().collect();
+    }
+
+    /// Whether the given callsite passes the current configuration.
+    pub(crate) fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let state = self.inner.read().unwrap();
+        let level = state
+            .targets
+            .iter()
+            .filter(|(prefix, _)| metadata.target().starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(state.default);
+        metadata.level() <= &level
+    }
+}
@@ -0,0 +1,87 @@
+//! A local feed of span lifecycle events, in the spirit of tokio-console's
+//! aggregation stream.
+//!
+//! Subscribers get a bounded receiver of [`SpanLifecycle`] notifications to
+//! drive in-process UIs, TUIs or ad-hoc aggregation without going through
+//! an exporter. Slow subscribers lose events rather than stalling the
+//! instrumented code.
+
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use opentelemetry::trace::{SpanId, TraceId};
+
+/// One span lifecycle notification.
+#[derive(Clone, Debug)]
+pub enum SpanLifecycle {
+    /// A span was created.
+    Started {
+        /// Span name.
+        name: String,
+        /// `tracing` target.
+        target: &'static str,
+        /// Creation time.
+        at: SystemTime,
+    },
+    /// A span closed.
+    Closed {
+        /// Span name (after any renames).
+        name: String,
+        /// `tracing` target.
+        target: &'static str,
+        /// Allocated trace ID, if the span ever needed one.
+        trace_id: Option<TraceId>,
+        /// Allocated span ID, if the span ever needed one.
+        span_id: Option<SpanId>,
+        /// Wall-clock duration.
+        duration: std::time::Duration,
+        /// Whether the span ended with error status.
+        is_error: bool,
+    },
+}
+
+/// Fan-out hub for [`SpanLifecycle`] events; install with
+/// [`OpenTelemetryLayer::with_span_feed`].
+///
+/// [`OpenTelemetryLayer::with_span_feed`]: crate::OpenTelemetryLayer::with_span_feed
+#[derive(Clone, Default)]
+pub struct SpanFeed {
+    subscribers: std::sync::Arc<Mutex<Vec<SyncSender<SpanLifecycle>>>>,
+}
+
+impl std::fmt::Debug for SpanFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpanFeed").finish_non_exhaustive()
+    }
+}
+
+impl SpanFeed {
+    /// A feed with no subscribers yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe with a buffer of `capacity` events. When the buffer is
+    /// full, new events for this subscriber are dropped; a disconnected
+    /// receiver unsubscribes automatically.
+    pub fn subscribe(&self, capacity: usize) -> Receiver<SpanLifecycle> {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(capacity.max(1));
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub(crate) fn publish(&self, event: SpanLifecycle) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|sender| {
+            !matches!(
+                sender.try_send(event.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+
+    pub(crate) fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+}
@@ -0,0 +1,99 @@
+//! C-callable access to the current thread's span context.
+//!
+//! Native profilers, crash handlers, and other C/C++ sidecar code need the
+//! active trace context without being able to call Rust generics. These
+//! exports read the same per-thread stack as
+//! [`profiling::current_thread_trace_ids`], so they require the layer to
+//! run with [`with_profiling_correlation`] enabled.
+//!
+//! The header-side declaration:
+//!
+//! ```c
+//! typedef struct {
+//!     uint64_t trace_id_high;
+//!     uint64_t trace_id_low;
+//!     uint64_t span_id;
+//! } n00_otel_span_context;
+//!
+//! bool n00_otel_current_span_context(n00_otel_span_context *out);
+//! ```
+//!
+//! [`profiling::current_thread_trace_ids`]: crate::profiling::current_thread_trace_ids
+//! [`with_profiling_correlation`]: crate::OpenTelemetryLayer::with_profiling_correlation
+
+/// The current span context in C-friendly form; all zeroes means "none".
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct N00OtelSpanContext {
+    /// High 64 bits of the 128-bit trace ID.
+    pub trace_id_high: u64,
+    /// Low 64 bits of the 128-bit trace ID.
+    pub trace_id_low: u64,
+    /// The 64-bit span ID.
+    pub span_id: u64,
+}
+
+/// Write the calling thread's innermost entered span context to `out`.
+///
+/// Returns `true` and fills `out` when a span is entered on this thread
+/// (and profiling correlation is enabled); returns `false` and zeroes
+/// `out` otherwise.
+///
+/// # Safety
+///
+/// `out` must be a valid, writable pointer to an [`N00OtelSpanContext`].
+/// The function does not allocate and takes no locks, making it callable
+/// from most native callback contexts.
+#[no_mangle]
+pub unsafe extern "C" fn n00_otel_current_span_context(out: *mut N00OtelSpanContext) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    match crate::profiling::current_thread_trace_ids() {
+        Some((trace_id, span_id)) => {
+            let trace = u128::from_be_bytes(trace_id.to_bytes());
+            unsafe {
+                *out = N00OtelSpanContext {
+                    trace_id_high: (trace >> 64) as u64,
+                    trace_id_low: trace as u64,
+                    span_id: u64::from_be_bytes(span_id.to_bytes()),
+                };
+            }
+            true
+        }
+        None => {
+            unsafe { *out = N00OtelSpanContext::default() };
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_false_and_zeroes_without_a_span() {
+        let mut out = N00OtelSpanContext {
+            trace_id_high: 1,
+            trace_id_low: 2,
+            span_id: 3,
+        };
+        assert!(!unsafe { n00_otel_current_span_context(&mut out) });
+        assert_eq!(out, N00OtelSpanContext::default());
+        assert!(!unsafe { n00_otel_current_span_context(std::ptr::null_mut()) });
+    }
+
+    #[test]
+    fn exposes_entered_span_ids() {
+        crate::profiling::push(
+            opentelemetry::trace::TraceId::from_bytes(0xfeed_u128.to_be_bytes()),
+            opentelemetry::trace::SpanId::from_bytes(0xbeef_u64.to_be_bytes()),
+        );
+        let mut out = N00OtelSpanContext::default();
+        assert!(unsafe { n00_otel_current_span_context(&mut out) });
+        assert_eq!(out.trace_id_low, 0xfeed);
+        assert_eq!(out.span_id, 0xbeef);
+        crate::profiling::pop();
+    }
+}
@@ -0,0 +1,116 @@
+//! GraphQL instrumentation conventions.
+//!
+//! Helpers for spans around GraphQL execution, following the GraphQL
+//! semantic conventions: `graphql.operation.type`,
+//! `graphql.operation.name`, `graphql.document`, and the
+//! `{operation.type} {operation.name}` span naming rule.
+
+use opentelemetry::KeyValue;
+
+/// GraphQL operation type attribute key.
+pub const GRAPHQL_OPERATION_TYPE: &str = "graphql.operation.type";
+/// GraphQL operation name attribute key.
+pub const GRAPHQL_OPERATION_NAME: &str = "graphql.operation.name";
+/// The GraphQL document being executed.
+pub const GRAPHQL_DOCUMENT: &str = "graphql.document";
+
+/// Typed builder for GraphQL span attributes.
+///
+/// ```
+/// use n00_otel::graphql::GraphqlAttributes;
+///
+/// let attrs = GraphqlAttributes::operation("query", "GetUser")
+///     .with_document(r#"query GetUser { user(id: "1") { name } }"#)
+///     .into_attributes();
+/// assert_eq!(attrs.len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GraphqlAttributes {
+    operation_type: String,
+    operation_name: Option<String>,
+    document: Option<String>,
+}
+
+impl GraphqlAttributes {
+    /// Attributes for an operation; `operation_type` is `query`,
+    /// `mutation` or `subscription`.
+    pub fn operation(
+        operation_type: impl Into<String>,
+        operation_name: impl Into<String>,
+    ) -> Self {
+        GraphqlAttributes {
+            operation_type: operation_type.into(),
+            operation_name: Some(operation_name.into()),
+            document: None,
+        }
+    }
+
+    /// Attributes for an anonymous operation.
+    pub fn anonymous(operation_type: impl Into<String>) -> Self {
+        GraphqlAttributes {
+            operation_type: operation_type.into(),
+            operation_name: None,
+            document: None,
+        }
+    }
+
+    /// Attach the executed document, sanitized with
+    /// [`sanitize_document`] so inline literals don't leak into traces.
+    pub fn with_document(mut self, document: &str) -> Self {
+        self.document = Some(sanitize_document(document));
+        self
+    }
+
+    /// The conventional span name: `{type} {name}` for named operations,
+    /// just the type for anonymous ones.
+    pub fn span_name(&self) -> String {
+        match &self.operation_name {
+            Some(name) => format!("{} {name}", self.operation_type),
+            None => self.operation_type.clone(),
+        }
+    }
+
+    /// The attribute list.
+    pub fn into_attributes(self) -> Vec<KeyValue> {
+        let mut attrs = vec![KeyValue::new(GRAPHQL_OPERATION_TYPE, self.operation_type)];
+        if let Some(name) = self.operation_name {
+            attrs.push(KeyValue::new(GRAPHQL_OPERATION_NAME, name));
+        }
+        if let Some(document) = self.document {
+            attrs.push(KeyValue::new(GRAPHQL_DOCUMENT, document));
+        }
+        attrs
+    }
+}
+
+/// Replace inline string and numeric literals in a GraphQL document with
+/// `?` placeholders, preserving the document structure. Variable references
+/// (`$var`) and field/argument names survive.
+pub fn sanitize_document(document: &str) -> String {
+    // GraphQL string literals use double quotes only; numbers follow the
+    // same token shape as SQL literals, so the SQL sanitizer's rules apply.
+    crate::sanitize_sql(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_sanitizes_operations() {
+        let attrs = GraphqlAttributes::operation("query", "GetUser")
+            .with_document(r#"query GetUser { user(id: "1", limit: 25) { name } }"#);
+        assert_eq!(attrs.span_name(), "query GetUser");
+        let attrs = attrs.into_attributes();
+        let document = attrs
+            .iter()
+            .find(|kv| kv.key.as_str() == GRAPHQL_DOCUMENT)
+            .unwrap();
+        assert_eq!(
+            document.value.to_string(),
+            "query GetUser { user(id: ?, limit: ?) { name } }"
+        );
+
+        assert_eq!(GraphqlAttributes::anonymous("mutation").span_name(), "mutation");
+    }
+}
@@ -0,0 +1,156 @@
+//! gRPC semantic conventions for tonic (or any gRPC stack).
+//!
+//! Transport-agnostic helpers: span macros following the
+//! `{package.Service}/{Method}` naming rule with `rpc.*` attributes, and
+//! the status-code mapping the spec prescribes (only genuinely failed
+//! codes mark the span as error).
+
+use crate::semconv;
+use crate::OpenTelemetrySpanExt;
+
+/// Create a `Client`-kind span for an outgoing gRPC call, named
+/// `{service}/{method}` with `rpc.system`/`rpc.service`/`rpc.method`.
+///
+/// ```
+/// let span = n00_otel::grpc_client_span!("checkout.CartService", "AddItem");
+/// span.in_scope(|| { /* issue the call */ });
+/// ```
+#[macro_export]
+macro_rules! grpc_client_span {
+    ($service:expr, $method:expr) => {
+        tracing::info_span!(
+            "grpc.request",
+            otel.name = %format!("{}/{}", $service, $method),
+            otel.kind = "client",
+            rpc.system = "grpc",
+            rpc.service = $service,
+            rpc.method = $method,
+        )
+    };
+}
+
+/// Create a `Server`-kind span for handling an incoming gRPC call;
+/// counterpart of [`grpc_client_span!`].
+#[macro_export]
+macro_rules! grpc_server_span {
+    ($service:expr, $method:expr) => {
+        tracing::info_span!(
+            "grpc.request",
+            otel.name = %format!("{}/{}", $service, $method),
+            otel.kind = "server",
+            rpc.system = "grpc",
+            rpc.service = $service,
+            rpc.method = $method,
+        )
+    };
+}
+
+/// The canonical name of a gRPC status code (`0` → `OK`, `4` →
+/// `DEADLINE_EXCEEDED`, ...). Unknown values format as the number.
+pub fn grpc_status_name(code: i32) -> String {
+    match code {
+        0 => "OK",
+        1 => "CANCELLED",
+        2 => "UNKNOWN",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        6 => "ALREADY_EXISTS",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        9 => "FAILED_PRECONDITION",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => "UNAUTHENTICATED",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+/// Which side of the call a span represents; the status-to-error mapping
+/// differs between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcRole {
+    /// The caller: any non-OK status is an error from its point of view.
+    Client,
+    /// The callee: statuses the *caller* caused (`NOT_FOUND`,
+    /// `INVALID_ARGUMENT`, `ALREADY_EXISTS`, ...) are successful handling,
+    /// not server errors.
+    Server,
+}
+
+/// Whether a status code marks the span as failed, per the OTel RPC
+/// semantic conventions.
+///
+/// Client spans error on every non-OK code. Server spans error only on
+/// codes that indicate the server itself failed: `UNKNOWN`,
+/// `DEADLINE_EXCEEDED`, `UNIMPLEMENTED`, `INTERNAL`, `UNAVAILABLE` and
+/// `DATA_LOSS`.
+pub fn grpc_status_is_error(role: RpcRole, code: i32) -> bool {
+    match role {
+        RpcRole::Client => code != 0,
+        RpcRole::Server => matches!(code, 2 | 4 | 12 | 13 | 14 | 15),
+    }
+}
+
+/// Record a finished call's gRPC status on the span: always the numeric
+/// `rpc.grpc.status_code` attribute, plus error span status when
+/// [`grpc_status_is_error`] says the code is a failure for the given role
+/// (message `{NAME}: {detail}` when a detail is given).
+///
+/// With tonic: `record_grpc_status(&span, RpcRole::Client,
+/// status.code() as i32, Some(status.message()))`.
+pub fn record_grpc_status(span: &tracing::Span, role: RpcRole, code: i32, detail: Option<&str>) {
+    span.set_attribute(semconv::RPC_GRPC_STATUS_CODE, i64::from(code));
+    if code == 0 {
+        span.set_status(opentelemetry::trace::Status::Ok);
+        return;
+    }
+    if !grpc_status_is_error(role, code) {
+        return;
+    }
+    let name = grpc_status_name(code);
+    let message = match detail.filter(|d| !d.is_empty()) {
+        Some(detail) => format!("{name}: {detail}"),
+        None => name,
+    };
+    span.set_status(opentelemetry::trace::Status::error(message));
+}
+
+/// Direction of a streamed message, for [`record_grpc_message`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageDirection {
+    /// A message this process sent.
+    Sent,
+    /// A message this process received.
+    Received,
+}
+
+/// Record one streamed message as a `message` span event with the
+/// conventional `rpc.message.type` and `rpc.message.id` attributes.
+///
+/// `message_id` counts from 1 per direction within the RPC, per the
+/// semantic conventions; streaming handlers typically keep one counter for
+/// requests and one for responses.
+pub fn record_grpc_message(span: &tracing::Span, direction: MessageDirection, message_id: u64) {
+    span.add_event(
+        "message",
+        vec![
+            opentelemetry::KeyValue::new(
+                "rpc.message.type",
+                match direction {
+                    MessageDirection::Sent => "SENT",
+                    MessageDirection::Received => "RECEIVED",
+                },
+            ),
+            opentelemetry::KeyValue::new(
+                "rpc.message.id",
+                i64::try_from(message_id).unwrap_or(i64::MAX),
+            ),
+        ],
+    );
+}
@@ -0,0 +1,141 @@
+//! Allocation-free hex formatting and parsing for trace/span IDs.
+//!
+//! ID formatting sits on the hot path of every injection (headers, log
+//! correlation, FFI) and `TraceId::to_string` allocates per call. These
+//! helpers write into fixed stack buffers via a byte-pair lookup table and
+//! parse with a reverse nibble table, branch-free per character.
+
+use opentelemetry::trace::{SpanId, TraceId};
+
+/// Two-character hex expansion of every byte value.
+static HEX_PAIRS: [[u8; 2]; 256] = {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut table = [[0u8; 2]; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = [DIGITS[i >> 4], DIGITS[i & 0x0f]];
+        i += 1;
+    }
+    table
+};
+
+/// Reverse nibble table; 0xff marks invalid characters.
+static NIBBLES: [u8; 256] = {
+    let mut table = [0xffu8; 256];
+    let mut i = 0u8;
+    while i < 10 {
+        table[(b'0' + i) as usize] = i;
+        i += 1;
+    }
+    let mut i = 0u8;
+    while i < 6 {
+        table[(b'a' + i) as usize] = 10 + i;
+        table[(b'A' + i) as usize] = 10 + i;
+        i += 1;
+    }
+    table
+};
+
+/// A stack-allocated lowercase hex rendering of an ID.
+#[derive(Clone, Copy, Debug)]
+pub struct HexId<const N: usize>([u8; N]);
+
+impl<const N: usize> HexId<N> {
+    /// The hex text.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII hex digits are ever written.
+        std::str::from_utf8(&self.0).expect("hex output is ASCII")
+    }
+}
+
+impl<const N: usize> std::ops::Deref for HexId<N> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> std::fmt::Display for HexId<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn encode<const BYTES: usize, const CHARS: usize>(bytes: [u8; BYTES]) -> HexId<CHARS> {
+    let mut out = [0u8; CHARS];
+    let mut i = 0;
+    while i < BYTES {
+        let pair = HEX_PAIRS[bytes[i] as usize];
+        out[i * 2] = pair[0];
+        out[i * 2 + 1] = pair[1];
+        i += 1;
+    }
+    HexId(out)
+}
+
+/// Format a trace ID as 32 lowercase hex characters without allocating.
+pub fn trace_id_hex(trace_id: TraceId) -> HexId<32> {
+    encode::<16, 32>(trace_id.to_bytes())
+}
+
+/// Format a span ID as 16 lowercase hex characters without allocating.
+pub fn span_id_hex(span_id: SpanId) -> HexId<16> {
+    encode::<8, 16>(span_id.to_bytes())
+}
+
+fn decode<const BYTES: usize>(text: &str) -> Option<[u8; BYTES]> {
+    let text = text.as_bytes();
+    if text.len() != BYTES * 2 {
+        return None;
+    }
+    let mut out = [0u8; BYTES];
+    let mut invalid = 0u8;
+    for (i, chunk) in text.chunks_exact(2).enumerate() {
+        let hi = NIBBLES[chunk[0] as usize];
+        let lo = NIBBLES[chunk[1] as usize];
+        invalid |= hi | lo;
+        out[i] = (hi << 4) | (lo & 0x0f);
+    }
+    // 0xff anywhere poisons the accumulator's high bit.
+    (invalid & 0x80 == 0).then_some(out)
+}
+
+/// Parse a 32-character hex trace ID. Returns `None` on any malformed
+/// input (wrong length or non-hex characters).
+pub fn parse_trace_id(text: &str) -> Option<TraceId> {
+    decode::<16>(text).map(TraceId::from_bytes)
+}
+
+/// Parse a 16-character hex span ID.
+pub fn parse_span_id(text: &str) -> Option<SpanId> {
+    decode::<8>(text).map(SpanId::from_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_matches_display() {
+        let trace_id = TraceId::from_bytes(0x0af7651916cd43dd8448eb211c80319c_u128.to_be_bytes());
+        let span_id = SpanId::from_bytes(0xb7ad6b7169203331_u64.to_be_bytes());
+        assert_eq!(trace_id_hex(trace_id).as_str(), trace_id.to_string());
+        assert_eq!(span_id_hex(span_id).as_str(), span_id.to_string());
+    }
+
+    #[test]
+    fn parsing_round_trips_and_rejects_garbage() {
+        let trace_id = TraceId::from_bytes(0xdeadbeef_u128.to_be_bytes());
+        assert_eq!(
+            parse_trace_id(trace_id_hex(trace_id).as_str()),
+            Some(trace_id)
+        );
+        assert_eq!(
+            parse_span_id("B7AD6B7169203331").map(|id| id.to_string()),
+            Some("b7ad6b7169203331".to_string())
+        );
+        assert_eq!(parse_trace_id("short"), None);
+        assert_eq!(parse_span_id("zzzzzzzzzzzzzzzz"), None);
+        assert_eq!(parse_span_id("b7ad6b71692033311"), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! HTTP header carriers and context extraction for web frameworks.
+//!
+//! Axum and actix-web both speak [`http`] header maps; these adapters and
+//! helpers are the framework-neutral core an extractor needs. Enabled with
+//! the `http` cargo feature.
+//!
+//! An axum extractor is a few lines on top:
+//!
+//! ```ignore
+//! use axum::extract::FromRequestParts;
+//! use axum::http::request::Parts;
+//!
+//! struct OtelContext(opentelemetry::Context);
+//!
+//! impl<S: Send + Sync> FromRequestParts<S> for OtelContext {
+//!     type Rejection = std::convert::Infallible;
+//!
+//!     async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+//!         Ok(OtelContext(n00_otel::http_ext::extract_context(&parts.headers)))
+//!     }
+//! }
+//! ```
+//!
+//! and the actix-web equivalent wraps `extract_context(req.headers())` in a
+//! `FromRequest` impl the same way. Handlers typically pass the result to
+//! [`OpenTelemetrySpanExt::set_parent`].
+//!
+//! [`OpenTelemetrySpanExt::set_parent`]: crate::OpenTelemetrySpanExt::set_parent
+
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::Context;
+
+/// [`Extractor`] adapter over an [`http::HeaderMap`].
+pub struct HeaderExtractor<'a>(pub &'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key)?.to_str().ok()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(http::HeaderName::as_str).collect()
+    }
+}
+
+/// [`Injector`] adapter over an [`http::HeaderMap`].
+pub struct HeaderInjector<'a>(pub &'a mut http::HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            http::HeaderName::try_from(key),
+            http::HeaderValue::try_from(value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Extract the OpenTelemetry context from request headers using the global
+/// propagator. The result is what a handler passes to
+/// [`set_parent`](crate::OpenTelemetrySpanExt::set_parent).
+pub fn extract_context(headers: &http::HeaderMap) -> Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    })
+}
+
+/// Inject `cx` into outgoing request headers using the global propagator.
+pub fn inject_context(cx: &Context, headers: &mut http::HeaderMap) {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(cx, &mut HeaderInjector(headers))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+    #[test]
+    fn header_map_round_trip() {
+        let propagator = TraceContextPropagator::new();
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            http::HeaderValue::from_static(
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ),
+        );
+
+        let cx = propagator.extract(&HeaderExtractor(&headers));
+        let sc = cx.span().span_context().clone();
+        assert!(sc.is_valid() && sc.is_remote() && sc.is_sampled());
+
+        let mut outgoing = http::HeaderMap::new();
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut outgoing));
+        assert!(outgoing
+            .get("traceparent")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+    }
+}
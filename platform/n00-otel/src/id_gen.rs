@@ -0,0 +1,140 @@
+//! ID generation hooks for the SDK tracer provider.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry_sdk::trace::IdGenerator;
+
+/// An [`IdGenerator`] that hands out sequential IDs, for tests and golden
+/// files that need stable trace output across runs.
+///
+/// Trace IDs count up from the seed, span IDs from 1, so a test run always
+/// produces the same IDs in the same order:
+///
+/// ```
+/// use n00_otel::DeterministicIdGenerator;
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+///
+/// let provider = SdkTracerProvider::builder()
+///     .with_id_generator(DeterministicIdGenerator::new())
+///     .build();
+/// # drop(provider);
+/// ```
+///
+/// Never use this outside tests: sequential IDs collide across processes
+/// and defeat ratio-based samplers, which hash the trace ID.
+pub struct DeterministicIdGenerator {
+    next_trace_id: AtomicU64,
+    next_span_id: AtomicU64,
+}
+
+impl Default for DeterministicIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeterministicIdGenerator {
+    /// A generator starting at trace ID 1 and span ID 1.
+    pub fn new() -> Self {
+        Self::with_seed(1)
+    }
+
+    /// A generator whose first trace ID is `seed`, when several providers
+    /// in one test must not overlap.
+    pub fn with_seed(seed: u64) -> Self {
+        DeterministicIdGenerator {
+            next_trace_id: AtomicU64::new(seed),
+            next_span_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl fmt::Debug for DeterministicIdGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeterministicIdGenerator")
+            .finish_non_exhaustive()
+    }
+}
+
+impl IdGenerator for DeterministicIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        let id = self.next_trace_id.fetch_add(1, Ordering::Relaxed);
+        TraceId::from_bytes(u128::from(id).to_be_bytes())
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        let id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        SpanId::from_bytes(id.to_be_bytes())
+    }
+}
+
+/// An [`IdGenerator`] producing AWS X-Ray compatible trace IDs.
+///
+/// X-Ray requires the first 32 bits of a trace ID to be the epoch seconds of
+/// trace start; backends reject (or mis-bucket) fully random IDs. The
+/// remaining 96 bits are random, and span IDs are plain random. Install it
+/// on the provider when exporting to X-Ray, typically together with
+/// [`XrayPropagator`]:
+///
+/// [`XrayPropagator`]: crate::propagation::XrayPropagator
+///
+/// ```
+/// use n00_otel::XrayIdGenerator;
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+///
+/// let provider = SdkTracerProvider::builder()
+///     .with_id_generator(XrayIdGenerator::default())
+///     .build();
+/// # drop(provider);
+/// ```
+#[derive(Debug, Default)]
+pub struct XrayIdGenerator {
+    inner: opentelemetry_sdk::trace::RandomIdGenerator,
+}
+
+impl IdGenerator for XrayIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        let epoch_seconds = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or_default();
+        let random = u128::from_be_bytes(self.inner.new_trace_id().to_bytes());
+        let id = (u128::from(epoch_seconds) << 96) | (random & ((1u128 << 96) - 1));
+        TraceId::from_bytes(id.to_be_bytes())
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        self.inner.new_span_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xray_trace_ids_embed_epoch_seconds() {
+        let id_gen = XrayIdGenerator::default();
+        let before = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
+        let trace_id = id_gen.new_trace_id();
+        let embedded =
+            u32::from_be_bytes(trace_id.to_bytes()[..4].try_into().unwrap());
+        assert!(embedded >= before && embedded <= before + 1);
+        // IDs still differ in their random portion.
+        assert_ne!(id_gen.new_trace_id(), id_gen.new_trace_id());
+    }
+
+    #[test]
+    fn ids_are_sequential_and_stable() {
+        let id_gen = DeterministicIdGenerator::with_seed(7);
+        assert_eq!(id_gen.new_trace_id().to_string(), format!("{:032x}", 7));
+        assert_eq!(id_gen.new_trace_id().to_string(), format!("{:032x}", 8));
+        assert_eq!(id_gen.new_span_id().to_string(), format!("{:016x}", 1));
+        assert_eq!(id_gen.new_span_id().to_string(), format!("{:016x}", 2));
+    }
+}
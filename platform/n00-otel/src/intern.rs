@@ -0,0 +1,56 @@
+//! A shared dictionary of attribute keys.
+//!
+//! Attribute-heavy spans with *dynamic* keys (flattened JSON, per-field
+//! metrics) allocate a fresh `String` per key per span — the same handful
+//! of key texts, thousands of times per batch. [`intern_key`] keeps one
+//! process-wide dictionary and hands out [`Key`]s backed by a single shared
+//! allocation per distinct text, so repeated keys across spans and batches
+//! cost a lookup instead of an allocation.
+//!
+//! The dictionary is bounded: beyond [`MAX_INTERNED_KEYS`] distinct texts,
+//! keys fall back to per-use allocation rather than growing the (leaked)
+//! dictionary further. Interned texts live for the process lifetime, which
+//! is the standard trade-off for telemetry key sets — they are small and
+//! recur forever.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+use opentelemetry::Key;
+
+/// Upper bound on distinct interned key texts.
+pub const MAX_INTERNED_KEYS: usize = 4096;
+
+fn dictionary() -> &'static Mutex<HashSet<&'static str>> {
+    static DICTIONARY: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// An attribute [`Key`] for `text`, shared through the process-wide
+/// dictionary when possible.
+pub fn intern_key(text: &str) -> Key {
+    let mut dictionary = dictionary().lock().unwrap();
+    if let Some(interned) = dictionary.get(text) {
+        return Key::from_static_str(interned);
+    }
+    if dictionary.len() >= MAX_INTERNED_KEYS {
+        // Dictionary full: don't leak unbounded cardinality.
+        return Key::new(text.to_string());
+    }
+    let interned: &'static str = Box::leak(text.to_string().into_boxed_str());
+    dictionary.insert(interned);
+    Key::from_static_str(interned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_keys_share_one_backing_str() {
+        let first = intern_key("n00.test.interned_key");
+        let second = intern_key("n00.test.interned_key");
+        // Both keys borrow the same leaked allocation.
+        assert!(std::ptr::eq(first.as_str(), second.as_str()));
+    }
+}
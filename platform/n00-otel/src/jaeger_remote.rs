@@ -0,0 +1,439 @@
+//! A Jaeger remote sampler: polls a Jaeger agent/collector sampling
+//! strategies endpoint and applies the returned strategy as a
+//! [`ShouldSample`] for the SDK tracer provider.
+//!
+//! ```no_run
+//! use n00_otel::JaegerRemoteSampler;
+//! use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+//!
+//! let sampler = JaegerRemoteSampler::builder("http://localhost:5778/sampling", "my-service")
+//!     .with_default_sampler(Sampler::TraceIdRatioBased(0.001))
+//!     .build();
+//! let provider = SdkTracerProvider::builder().with_sampler(sampler).build();
+//! # drop(provider);
+//! ```
+//!
+//! Only plain-HTTP endpoints are supported, matching the Jaeger agent's
+//! sampling port; the strategies document is the standard
+//! `strategyType`/`probabilisticSampling`/`operationSampling` JSON.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+use serde::Deserialize;
+
+/// Default polling interval used by Jaeger clients.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The parsed strategies document served by a Jaeger sampling endpoint.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StrategyResponse {
+    #[serde(default)]
+    strategy_type: Option<String>,
+    #[serde(default)]
+    probabilistic_sampling: Option<ProbabilisticSampling>,
+    #[serde(default)]
+    rate_limiting_sampling: Option<RateLimitingSampling>,
+    #[serde(default)]
+    operation_sampling: Option<OperationSampling>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProbabilisticSampling {
+    sampling_rate: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitingSampling {
+    max_traces_per_second: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OperationSampling {
+    #[serde(default)]
+    default_sampling_probability: Option<f64>,
+    #[serde(default)]
+    per_operation_strategies: Vec<PerOperationStrategy>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PerOperationStrategy {
+    operation: String,
+    probabilistic_sampling: ProbabilisticSampling,
+}
+
+/// The strategy currently in force.
+enum Strategy {
+    /// No successful poll yet, or the endpoint misbehaved: defer to the
+    /// configured default sampler.
+    Default,
+    Probabilistic(f64),
+    RateLimiting(RateBucket),
+    PerOperation {
+        default_probability: f64,
+        per_operation: Vec<(String, f64)>,
+    },
+}
+
+/// Minimal leaky bucket for the rate-limiting strategy.
+struct RateBucket {
+    per_second: f64,
+    state: std::sync::Mutex<(f64, std::time::Instant)>,
+}
+
+impl RateBucket {
+    fn new(per_second: f64) -> Self {
+        RateBucket {
+            per_second,
+            state: std::sync::Mutex::new((per_second.max(1.0), std::time::Instant::now())),
+        }
+    }
+
+    fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = std::time::Instant::now();
+        let (ref mut tokens, ref mut last) = *state;
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.per_second)
+            .min(self.per_second.max(1.0));
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Builder for [`JaegerRemoteSampler`].
+pub struct JaegerRemoteSamplerBuilder {
+    endpoint: String,
+    service_name: String,
+    poll_interval: Duration,
+    default_sampler: Sampler,
+}
+
+impl JaegerRemoteSamplerBuilder {
+    /// Sampler used until the first successful poll and whenever the
+    /// endpoint is unreachable. Defaults to [`Sampler::AlwaysOn`].
+    pub fn with_default_sampler(mut self, sampler: Sampler) -> Self {
+        self.default_sampler = sampler;
+        self
+    }
+
+    /// How often to re-fetch the strategies document. Defaults to 60s.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Build the sampler and start the background polling thread.
+    pub fn build(self) -> JaegerRemoteSampler {
+        let shared = Arc::new(Shared {
+            strategy: RwLock::new(Strategy::Default),
+        });
+        let sampler = JaegerRemoteSampler {
+            shared: shared.clone(),
+            default_sampler: self.default_sampler,
+        };
+
+        let url = if self.endpoint.contains('?') {
+            format!("{}&service={}", self.endpoint, self.service_name)
+        } else {
+            format!("{}?service={}", self.endpoint, self.service_name)
+        };
+        let poll_interval = self.poll_interval;
+        let weak = Arc::downgrade(&shared);
+        std::thread::Builder::new()
+            .name("n00-otel-jaeger-sampler".into())
+            .spawn(move || {
+                // Stop polling once every sampler clone is gone.
+                while let Some(shared) = weak.upgrade() {
+                    if let Some(response) = fetch_strategies(&url) {
+                        *shared.strategy.write().unwrap() = Strategy::from_response(response);
+                    }
+                    drop(shared);
+                    std::thread::sleep(poll_interval);
+                }
+            })
+            .expect("failed to spawn jaeger sampler polling thread");
+
+        sampler
+    }
+}
+
+struct Shared {
+    strategy: RwLock<Strategy>,
+}
+
+/// A [`ShouldSample`] implementation driven by a Jaeger sampling endpoint.
+#[derive(Clone)]
+pub struct JaegerRemoteSampler {
+    shared: Arc<Shared>,
+    default_sampler: Sampler,
+}
+
+impl fmt::Debug for JaegerRemoteSampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JaegerRemoteSampler").finish_non_exhaustive()
+    }
+}
+
+impl JaegerRemoteSampler {
+    /// Start building a sampler polling `endpoint` (e.g.
+    /// `http://localhost:5778/sampling`) for `service_name`'s strategies.
+    pub fn builder(
+        endpoint: impl Into<String>,
+        service_name: impl Into<String>,
+    ) -> JaegerRemoteSamplerBuilder {
+        JaegerRemoteSamplerBuilder {
+            endpoint: endpoint.into(),
+            service_name: service_name.into(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            default_sampler: Sampler::AlwaysOn,
+        }
+    }
+}
+
+impl Strategy {
+    fn from_response(response: StrategyResponse) -> Self {
+        if let Some(operation_sampling) = response.operation_sampling {
+            return Strategy::PerOperation {
+                default_probability: operation_sampling
+                    .default_sampling_probability
+                    .unwrap_or(0.001),
+                per_operation: operation_sampling
+                    .per_operation_strategies
+                    .into_iter()
+                    .map(|s| (s.operation, s.probabilistic_sampling.sampling_rate))
+                    .collect(),
+            };
+        }
+        match response.strategy_type.as_deref() {
+            Some("PROBABILISTIC") | Some("probabilistic") => Strategy::Probabilistic(
+                response
+                    .probabilistic_sampling
+                    .map(|p| p.sampling_rate)
+                    .unwrap_or(0.001),
+            ),
+            Some("RATE_LIMITING") | Some("ratelimiting") => Strategy::RateLimiting(
+                RateBucket::new(
+                    response
+                        .rate_limiting_sampling
+                        .map(|r| r.max_traces_per_second)
+                        .unwrap_or(1.0),
+                ),
+            ),
+            _ => Strategy::Default,
+        }
+    }
+}
+
+impl ShouldSample for JaegerRemoteSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let strategy = self.shared.strategy.read().unwrap();
+        let probability = match &*strategy {
+            Strategy::Default => {
+                return self.default_sampler.should_sample(
+                    parent_context,
+                    trace_id,
+                    name,
+                    span_kind,
+                    attributes,
+                    links,
+                )
+            }
+            Strategy::RateLimiting(bucket) => {
+                let decision = if bucket.allow() {
+                    SamplingDecision::RecordAndSample
+                } else {
+                    SamplingDecision::Drop
+                };
+                return SamplingResult {
+                    decision,
+                    attributes: Vec::new(),
+                    trace_state: Default::default(),
+                };
+            }
+            Strategy::Probabilistic(rate) => *rate,
+            Strategy::PerOperation {
+                default_probability,
+                per_operation,
+            } => per_operation
+                .iter()
+                .find(|(operation, _)| operation == name)
+                .map(|(_, rate)| *rate)
+                .unwrap_or(*default_probability),
+        };
+        drop(strategy);
+        Sampler::TraceIdRatioBased(probability).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+/// Fetch and parse the strategies document. Plain HTTP/1.1 over TCP; any
+/// failure returns `None` and leaves the previous strategy in force.
+fn fetch_strategies(url: &str) -> Option<StrategyResponse> {
+    let rest = url.strip_prefix("http://")?;
+    let (host_port, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+    let host = host_port.split(':').next().unwrap_or(host_port);
+    let addr = if host_port.contains(':') {
+        host_port.to_string()
+    } else {
+        format!("{host_port}:5778")
+    };
+
+    let mut stream = TcpStream::connect(&addr).ok()?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .ok()?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )
+    .ok()?;
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            // A reset after the full document arrived is still a success;
+            // the JSON parse below decides.
+            Err(_) if !response.is_empty() => break,
+            Err(_) => return None,
+        }
+    }
+    let response = String::from_utf8(response).ok()?;
+    let body = response.split_once("\r\n\r\n")?.1;
+    serde_json::from_str(body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accept one connection, read the request, respond with an always-on
+    /// probabilistic strategy.
+    fn serve_strategy_once(listener: &std::net::TcpListener) {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let body =
+                r#"{"strategyType":"PROBABILISTIC","probabilisticSampling":{"samplingRate":1.0}}"#;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            // Close gracefully: shut down our write side and drain the
+            // client's, so the response isn't destroyed by an RST.
+            let _ = stream.shutdown(std::net::Shutdown::Write);
+            while matches!(stream.read(&mut request), Ok(n) if n > 0) {}
+        }
+    }
+
+    fn sample(sampler: &JaegerRemoteSampler, name: &str) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from_bytes(1u128.to_be_bytes()),
+                name,
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn parses_per_operation_strategies() {
+        let response: StrategyResponse = serde_json::from_str(
+            r#"{
+                "strategyType": "PROBABILISTIC",
+                "probabilisticSampling": {"samplingRate": 0.5},
+                "operationSampling": {
+                    "defaultSamplingProbability": 0.0,
+                    "perOperationStrategies": [
+                        {"operation": "checkout", "probabilisticSampling": {"samplingRate": 1.0}}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let sampler = JaegerRemoteSampler::builder("http://unused", "svc").build();
+        *sampler.shared.strategy.write().unwrap() = Strategy::from_response(response);
+
+        assert_eq!(sample(&sampler, "checkout"), SamplingDecision::RecordAndSample);
+        assert_eq!(sample(&sampler, "other"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn serves_default_sampler_until_first_poll() {
+        let sampler = JaegerRemoteSampler::builder("http://unused", "svc")
+            .with_default_sampler(Sampler::AlwaysOff)
+            .build();
+        assert_eq!(sample(&sampler, "any"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn fetches_and_parses_over_http() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || serve_strategy_once(&listener));
+        let parsed = fetch_strategies(&format!("http://{addr}/sampling?service=svc"));
+        let parsed = parsed.expect("fetch failed");
+        assert_eq!(parsed.strategy_type.as_deref(), Some("PROBABILISTIC"));
+    }
+
+    #[test]
+    fn polls_a_live_endpoint() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || loop {
+            serve_strategy_once(&listener);
+        });
+
+        let sampler = JaegerRemoteSampler::builder(format!("http://{addr}/sampling"), "svc")
+            .with_default_sampler(Sampler::AlwaysOff)
+            .with_poll_interval(Duration::from_millis(10))
+            .build();
+
+        // Wait for the poller to pick up the strategy.
+        for _ in 0..100 {
+            if sample(&sampler, "any") == SamplingDecision::RecordAndSample {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("sampler never adopted the remote strategy");
+    }
+}
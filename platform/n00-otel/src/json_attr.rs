@@ -0,0 +1,167 @@
+//! Structured JSON values as span attributes.
+
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+
+/// Flatten a JSON value into OpenTelemetry attributes under `key`.
+///
+/// OpenTelemetry attributes are scalars or homogeneous primitive arrays, so
+/// structure has to be encoded rather than passed through:
+///
+/// * scalars map to their native value types (`i64`, `f64`, `bool`,
+///   `String`)
+/// * objects flatten recursively into dotted keys (`key.field.subfield`),
+///   down to [`DEFAULT_JSON_DEPTH`] levels — anything deeper is serialized
+///   to its JSON text instead of exploding into more keys (see
+///   [`json_attributes_with_depth`] to choose the cutoff)
+/// * arrays of one primitive type become typed OTel arrays
+/// * anything else (mixed or nested arrays, nulls) falls back to its JSON
+///   text under the given key
+///
+/// ```
+/// let attrs = n00_otel::json_attributes(
+///     "payload",
+///     &serde_json::json!({"user": {"id": 7}, "tags": ["a", "b"]}),
+/// );
+/// assert!(attrs.iter().any(|kv| kv.key.as_str() == "payload.user.id"));
+/// assert!(attrs.iter().any(|kv| kv.key.as_str() == "payload.tags"));
+/// ```
+pub fn json_attributes(key: &str, value: &serde_json::Value) -> Vec<KeyValue> {
+    json_attributes_with_depth(key, value, DEFAULT_JSON_DEPTH)
+}
+
+/// Object levels [`json_attributes`] flattens before falling back to JSON
+/// text.
+pub const DEFAULT_JSON_DEPTH: usize = 8;
+
+/// Like [`json_attributes`], flattening at most `max_depth` object levels;
+/// values nested deeper keep their JSON text under the dotted key built so
+/// far. A depth of `0` stores the whole value as one JSON string.
+pub fn json_attributes_with_depth(
+    key: &str,
+    value: &serde_json::Value,
+    max_depth: usize,
+) -> Vec<KeyValue> {
+    let mut attributes = Vec::new();
+    flatten(key, value, max_depth, &mut attributes);
+    attributes
+}
+
+fn flatten(key: &str, value: &serde_json::Value, depth: usize, out: &mut Vec<KeyValue>) {
+    match value {
+        serde_json::Value::Object(map) if depth > 0 => {
+            for (field, value) in map {
+                flatten(&format!("{key}.{field}"), value, depth - 1, out);
+            }
+        }
+        // Past the depth cutoff an object keeps its JSON text; a deep
+        // payload can't explode into hundreds of dotted keys.
+        object @ serde_json::Value::Object(_) => out.push(KeyValue::new(
+            crate::intern_key(key),
+            Value::String(object.to_string().into()),
+        )),
+        // Dotted keys recur across spans; share them through the key
+        // dictionary instead of allocating per span.
+        other => out.push(KeyValue::new(crate::intern_key(key), json_to_value(other))),
+    }
+}
+
+/// Convert a non-object JSON value to the closest OpenTelemetry [`Value`].
+fn json_to_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Bool(b) => Value::Bool(*b),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::I64(i)
+            } else {
+                Value::F64(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        serde_json::Value::String(s) => Value::String(s.clone().into()),
+        serde_json::Value::Array(items) => array_value(items)
+            .unwrap_or_else(|| Value::String(value.to_string().into())),
+        // Nulls and objects (objects only reach here inside arrays) keep
+        // their JSON text.
+        other => Value::String(other.to_string().into()),
+    }
+}
+
+/// A typed OTel array, if every element is the same primitive type.
+fn array_value(items: &[serde_json::Value]) -> Option<Value> {
+    if items.is_empty() {
+        return Some(Value::Array(Array::String(Vec::new())));
+    }
+    if items.iter().all(|v| v.is_boolean()) {
+        return Some(Value::Array(Array::Bool(
+            items.iter().filter_map(|v| v.as_bool()).collect(),
+        )));
+    }
+    if items.iter().all(|v| v.is_i64()) {
+        return Some(Value::Array(Array::I64(
+            items.iter().filter_map(|v| v.as_i64()).collect(),
+        )));
+    }
+    if items.iter().all(|v| v.is_number()) {
+        return Some(Value::Array(Array::F64(
+            items.iter().filter_map(|v| v.as_f64()).collect(),
+        )));
+    }
+    if items.iter().all(|v| v.is_string()) {
+        return Some(Value::Array(Array::String(
+            items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| StringValue::from(s.to_string()))
+                .collect(),
+        )));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_cutoff_falls_back_to_json_text() {
+        let deep = serde_json::json!({"a": {"b": {"c": {"d": 1}}}});
+        let attrs = json_attributes_with_depth("root", &deep, 2);
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(attrs[0].key.as_str(), "root.a.b");
+        assert_eq!(
+            attrs[0].value,
+            Value::String(r#"{"c":{"d":1}}"#.into())
+        );
+
+        let whole = json_attributes_with_depth("root", &deep, 0);
+        assert_eq!(whole[0].key.as_str(), "root");
+        assert!(matches!(&whole[0].value, Value::String(s) if s.as_str().starts_with("{\"a\"")));
+    }
+
+    #[test]
+    fn flattens_objects_and_types_arrays() {
+        let attrs = json_attributes(
+            "req",
+            &serde_json::json!({
+                "user": {"id": 7, "admin": false},
+                "tags": ["alpha", "beta"],
+                "scores": [1, 2, 3],
+                "mixed": [1, "two"],
+            }),
+        );
+        let get = |key: &str| {
+            attrs
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+        assert_eq!(get("req.user.id"), Some(Value::I64(7)));
+        assert_eq!(get("req.user.admin"), Some(Value::Bool(false)));
+        assert_eq!(
+            get("req.scores"),
+            Some(Value::Array(Array::I64(vec![1, 2, 3])))
+        );
+        assert!(matches!(get("req.tags"), Some(Value::Array(Array::String(_)))));
+        // Mixed arrays keep their JSON text.
+        assert_eq!(get("req.mixed"), Some(Value::String("[1,\"two\"]".into())));
+    }
+}
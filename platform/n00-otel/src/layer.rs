@@ -0,0 +1,2884 @@
+use std::any::TypeId;
+use std::fmt;
+use std::marker;
+
+use opentelemetry::trace::{self as otel, noop, SpanBuilder, SpanKind, Status, TraceContextExt};
+use opentelemetry::{Context as OtelContext, KeyValue};
+use tracing_core::span::{self, Attributes, Id, Record};
+use tracing_core::{field, Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::conventions::{self, ConventionsMode};
+use crate::dynamic_filter::DynamicTargets;
+use crate::adaptive::AdaptiveSampler;
+use crate::clock::{Clock, SystemClock};
+use crate::backpressure::BackpressureSignal;
+use crate::feed::{SpanFeed, SpanLifecycle};
+use crate::stats::LayerStats;
+use crate::live::{LiveSpanRegistry, LiveSpans, OpenSpan};
+use crate::rate_limit::SpanRateLimiter;
+use crate::redact::RedactionPolicy;
+use crate::tail_sampling::{BufferedSpan, TailSamplingState, TailVerdict, TraceSummary};
+use crate::time::Instant;
+use crate::{time, OtelData, OtelDataMap, PreSampledTracer};
+
+pub(crate) const SPAN_NAME_FIELD: &str = "otel.name";
+pub(crate) const SPAN_KIND_FIELD: &str = "otel.kind";
+pub(crate) const SPAN_STATUS_CODE_FIELD: &str = "otel.status_code";
+pub(crate) const SPAN_STATUS_DESCRIPTION_FIELD: &str = "otel.status_description";
+pub(crate) const SPAN_CAPTURE_EVENTS_FIELD: &str = "otel.capture_events";
+pub(crate) const SPAN_DROP_FIELD: &str = "otel.drop";
+#[cfg(feature = "events")]
+pub(crate) const EVENT_TIME_FIELD: &str = "otel.event_time";
+
+/// Attribute recording how many in-span events were discarded by the
+/// configured [`EventOverflowPolicy`].
+pub(crate) const DROPPED_EVENT_COUNT_ATTR: &str = "otel.dropped_event_count";
+
+/// Attribute recording how many attributes were discarded by the buffering
+/// cap of [`OpenTelemetryLayer::with_max_attributes_per_span`].
+pub(crate) const DROPPED_ATTRIBUTE_COUNT_ATTR: &str = "otel.dropped_attributes_count";
+
+/// Attribute recording how many events a span observed when the events
+/// themselves were withheld from export (see
+/// [`OpenTelemetryLayer::with_events_on_error_only`]).
+pub(crate) const EVENT_COUNT_ATTR: &str = "otel.tracing_event_count";
+
+/// What to do with new events once a span has reached its
+/// [event limit](OpenTelemetryLayer::with_max_events_per_span).
+///
+/// Regardless of the chosen policy, the number of discarded events is
+/// exported in the `otel.dropped_event_count` span attribute so that a
+/// truncated span is distinguishable from a quiet one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventOverflowPolicy {
+    /// Discard the oldest buffered event to make room for the new one. The
+    /// exported span carries the *last* N events, which is usually where
+    /// failure context lives.
+    DropOldest,
+
+    /// Discard the incoming event and keep the first N. This is the cheapest
+    /// policy and preserves the span's setup phase.
+    #[default]
+    DropNewest,
+
+    /// Discard the incoming event, keeping only the count. Combined with a
+    /// limit of `0` this reduces event capture to pure counting.
+    Summarize,
+}
+
+/// How repeated recordings of the same attribute key are stored (see
+/// [`OpenTelemetryLayer::with_duplicate_field_policy`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateFieldPolicy {
+    /// Keep every recording as its own attribute; downstream consumers see
+    /// duplicates, per OpenTelemetry's default semantics.
+    #[default]
+    Append,
+    /// Keep only the most recent value for a key.
+    LastWins,
+    /// Merge repeated recordings into an array value (promoting the first
+    /// scalar); mixed-type recordings degrade to a string array.
+    MultiValue,
+}
+
+/// Decides at span close whether the buffered events are attached to the
+/// exported span (see [`OpenTelemetryLayer::with_events_on_error_only`]).
+#[derive(Clone, Default)]
+enum EventsExportFilter {
+    /// Attach events to every exported span.
+    #[default]
+    Always,
+    /// Attach events only to spans whose status is [`Status::Error`].
+    OnErrorOnly,
+    /// Attach events only when the predicate matches the finished builder.
+    Predicate(std::sync::Arc<dyn Fn(&SpanBuilder) -> bool + Send + Sync>),
+}
+
+impl EventsExportFilter {
+    fn should_export(&self, builder: &SpanBuilder) -> bool {
+        match self {
+            EventsExportFilter::Always => true,
+            EventsExportFilter::OnErrorOnly => {
+                matches!(builder.status, Status::Error { .. })
+            }
+            EventsExportFilter::Predicate(predicate) => predicate(builder),
+        }
+    }
+}
+
+/// A [`Layer`] that exports `tracing` spans and events as OpenTelemetry
+/// spans.
+///
+/// Span data is buffered in the span's extensions while it is open and built
+/// into an OpenTelemetry span via the configured tracer when the span closes.
+///
+/// # Fields vs. attributes
+///
+/// `tracing` fields must be declared at span creation, but their *values*
+/// may arrive later: declare the field as [`tracing::field::Empty`] and fill
+/// it in with [`tracing::Span::record`] once the value exists — typically a
+/// result computed by the parent after the child span was created. Because
+/// this layer only builds the OpenTelemetry span at close, late-recorded
+/// values land in the exported attributes exactly like creation-time ones;
+/// the reserved `otel.*` fields keep their special meaning when recorded
+/// late. For values whose *keys* aren't known statically, use
+/// [`OpenTelemetrySpanExt::set_attribute`] instead.
+///
+/// [`OpenTelemetrySpanExt::set_attribute`]: crate::OpenTelemetrySpanExt::set_attribute
+pub struct OpenTelemetryLayer<S, T> {
+    /// Distinguishes this instance's per-span state when several
+    /// OpenTelemetry layers share one registry.
+    layer_id: u64,
+    tracer: T,
+    /// Tracers used for spans whose target starts with the mapped prefix
+    /// (longest prefix wins); see [`Self::with_scoped_tracer`].
+    scoped_tracers: Vec<(String, T)>,
+    location: bool,
+    tracked_inactivity: bool,
+    per_enter_timings: bool,
+    profiling_correlation: bool,
+    follows_from_attribute_snapshot: bool,
+    closed_span_lru: Option<std::sync::Arc<ClosedSpanLru>>,
+    child_aggregation_threshold: Option<u64>,
+    live_spans: Option<std::sync::Arc<LiveSpanRegistry>>,
+    span_feed: Option<SpanFeed>,
+    deferred_export: Option<std::sync::mpsc::SyncSender<BufferedSpan>>,
+    memory_budget: Option<std::sync::Arc<MemoryBudget>>,
+    #[cfg(feature = "metrics")]
+    span_metrics: Option<std::sync::Arc<crate::metrics::SpanMetrics>>,
+    stats: Option<LayerStats>,
+    backpressure: Option<BackpressureSignal>,
+    adaptive_feedback: Option<AdaptiveSampler>,
+    error_fields: Vec<String>,
+    inherited_fields: Vec<String>,
+    error_events_to_status: bool,
+    lifecycle_events: bool,
+    max_attributes_per_span: Option<usize>,
+    max_attribute_value_len: Option<usize>,
+    /// Statically disabled via `OTEL_SDK_DISABLED`; the layer observes
+    /// nothing.
+    disabled: bool,
+    clock: std::sync::Arc<dyn Clock>,
+    callsite_cache: std::sync::Arc<CallsiteCache>,
+    duplicate_field_policy: DuplicateFieldPolicy,
+    duration_budget: Option<std::time::Duration>,
+    budget_hook: Option<BudgetHook>,
+    span_namer: Option<SpanNamer>,
+    dynamic_targets: Option<DynamicTargets>,
+    with_threads: bool,
+    with_level: bool,
+    with_target: bool,
+    max_events_per_span: Option<usize>,
+    event_overflow_policy: EventOverflowPolicy,
+    events_export_filter: EventsExportFilter,
+    tracestate_debug_flag: Option<(String, String)>,
+    tail_sampling: Option<std::sync::Arc<TailSamplingState>>,
+    span_rate_limiter: Option<std::sync::Arc<SpanRateLimiter>>,
+    propagator: Option<std::sync::Arc<dyn opentelemetry::propagation::TextMapPropagator + Send + Sync>>,
+    conventions: ConventionsMode,
+    db_statement_sanitizer: Option<StatementSanitizer>,
+    redaction: Option<std::sync::Arc<RedactionPolicy>>,
+    #[cfg(feature = "logs")]
+    span_log_mirror: Option<std::sync::Arc<crate::span_log_mirror::SpanLogMirror>>,
+    get_context: WithContext,
+    _registry: marker::PhantomData<S>,
+}
+
+impl<S> Default for OpenTelemetryLayer<S, noop::NoopTracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn default() -> Self {
+        OpenTelemetryLayer::new(noop::NoopTracer::new())
+    }
+}
+
+/// Construct an [`OpenTelemetryLayer`] with a no-op tracer.
+///
+/// Use [`OpenTelemetryLayer::with_tracer`] to swap in a real tracer:
+///
+/// ```
+/// use opentelemetry::trace::TracerProvider as _;
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+/// use tracing_subscriber::layer::SubscriberExt;
+/// use tracing_subscriber::Registry;
+///
+/// let provider = SdkTracerProvider::builder().build();
+/// let subscriber = Registry::default()
+///     .with(n00_otel::layer().with_tracer(provider.tracer("my-service")));
+/// # drop(subscriber);
+/// ```
+pub fn layer<S>() -> OpenTelemetryLayer<S, noop::NoopTracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    OpenTelemetryLayer::default()
+}
+
+/// Callbacks that let [`OpenTelemetrySpanExt`] reach the layer's span data
+/// through an opaque [`tracing::Dispatch`], without knowing the concrete
+/// subscriber or tracer types.
+///
+/// [`OpenTelemetrySpanExt`]: crate::OpenTelemetrySpanExt
+pub(crate) struct WithContext {
+    #[allow(clippy::type_complexity)]
+    with_context:
+        fn(&tracing::Dispatch, &span::Id, f: &mut dyn FnMut(&mut OtelData, &dyn PreSampledTracer)),
+    /// The layer's own propagator, when configured via
+    /// [`OpenTelemetryLayer::with_propagator`].
+    #[allow(clippy::type_complexity)]
+    propagator: fn(
+        &tracing::Dispatch,
+    )
+        -> Option<std::sync::Arc<dyn opentelemetry::propagation::TextMapPropagator + Send + Sync>>,
+    /// Buffer an event through the layer's own policies (per-span cap,
+    /// overflow policy, memory budget, backpressure) rather than appending
+    /// to the raw buffer.
+    record_event: fn(&tracing::Dispatch, &span::Id, opentelemetry::trace::Event),
+}
+
+impl WithContext {
+    pub(crate) fn with_context(
+        &self,
+        dispatch: &tracing::Dispatch,
+        id: &span::Id,
+        mut f: impl FnMut(&mut OtelData, &dyn PreSampledTracer),
+    ) {
+        (self.with_context)(dispatch, id, &mut f)
+    }
+
+    pub(crate) fn propagator(
+        &self,
+        dispatch: &tracing::Dispatch,
+    ) -> Option<std::sync::Arc<dyn opentelemetry::propagation::TextMapPropagator + Send + Sync>>
+    {
+        (self.propagator)(dispatch)
+    }
+
+    pub(crate) fn record_event(
+        &self,
+        dispatch: &tracing::Dispatch,
+        id: &span::Id,
+        event: opentelemetry::trace::Event,
+    ) {
+        (self.record_event)(dispatch, id, event)
+    }
+}
+
+/// Fold a new recording into an existing value per
+/// [`DuplicateFieldPolicy::MultiValue`].
+fn merge_multi_value(
+    existing: opentelemetry::Value,
+    new: opentelemetry::Value,
+) -> opentelemetry::Value {
+    use opentelemetry::{Array, StringValue, Value};
+    match (existing, new) {
+        (Value::Array(Array::I64(mut items)), Value::I64(v)) => {
+            items.push(v);
+            Value::Array(Array::I64(items))
+        }
+        (Value::Array(Array::F64(mut items)), Value::F64(v)) => {
+            items.push(v);
+            Value::Array(Array::F64(items))
+        }
+        (Value::Array(Array::Bool(mut items)), Value::Bool(v)) => {
+            items.push(v);
+            Value::Array(Array::Bool(items))
+        }
+        (Value::Array(Array::String(mut items)), v) => {
+            items.push(StringValue::from(v.to_string()));
+            Value::Array(Array::String(items))
+        }
+        (Value::I64(a), Value::I64(b)) => Value::Array(Array::I64(vec![a, b])),
+        (Value::F64(a), Value::F64(b)) => Value::Array(Array::F64(vec![a, b])),
+        (Value::Bool(a), Value::Bool(b)) => Value::Array(Array::Bool(vec![a, b])),
+        (Value::String(a), Value::String(b)) => Value::Array(Array::String(vec![a, b])),
+        // Mixed types (or an existing non-string array meeting a different
+        // type) degrade to a string array.
+        (a, b) => Value::Array(Array::String(vec![
+            StringValue::from(a.to_string()),
+            StringValue::from(b.to_string()),
+        ])),
+    }
+}
+
+/// Truncate a string attribute value to the configured length limit, at a
+/// char boundary, per `OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT` semantics.
+fn truncate_value(value: opentelemetry::Value, max_len: Option<usize>) -> opentelemetry::Value {
+    let Some(max_len) = max_len else { return value };
+    match value {
+        opentelemetry::Value::String(s) if s.as_str().len() > max_len => {
+            let text = s.as_str();
+            let mut cut = max_len;
+            while cut > 0 && !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            opentelemetry::Value::String(text[..cut].to_string().into())
+        }
+        other => other,
+    }
+}
+
+/// Add to (or create) the `otel.dropped_attributes_count` attribute.
+fn bump_dropped_attribute_count(builder: &mut SpanBuilder, dropped: u64) {
+    let attributes = builder.attributes.get_or_insert_with(Vec::new);
+    if let Some(existing) = attributes
+        .iter_mut()
+        .find(|kv| kv.key.as_str() == DROPPED_ATTRIBUTE_COUNT_ATTR)
+    {
+        if let opentelemetry::Value::I64(count) = &mut existing.value {
+            *count += dropped as i64;
+            return;
+        }
+    }
+    attributes.push(KeyValue::new(DROPPED_ATTRIBUTE_COUNT_ATTR, dropped as i64));
+}
+
+/// Global accounting for the layer's buffered event data (see
+/// [`OpenTelemetryLayer::with_memory_budget`]).
+// Charging happens on the event path; without the `events` feature the
+// budget is configured but never consulted.
+#[cfg_attr(not(feature = "events"), allow(dead_code))]
+struct MemoryBudget {
+    used: std::sync::atomic::AtomicUsize,
+    budget: usize,
+}
+
+#[cfg_attr(not(feature = "events"), allow(dead_code))]
+impl MemoryBudget {
+    /// Try to reserve `bytes`; `false` means the budget is exhausted.
+    fn try_charge(&self, bytes: usize) -> bool {
+        use std::sync::atomic::Ordering;
+        let mut used = self.used.load(Ordering::Relaxed);
+        loop {
+            if used + bytes > self.budget {
+                return false;
+            }
+            match self.used.compare_exchange_weak(
+                used,
+                used + bytes,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    fn refund(&self, bytes: usize) {
+        self.used
+            .fetch_sub(bytes, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Rough in-memory footprint of a buffered event.
+#[cfg(feature = "events")]
+fn estimate_event_bytes(event: &otel::Event) -> usize {
+    let attrs: usize = event
+        .attributes
+        .iter()
+        .map(|kv| kv.key.as_str().len() + estimate_value_bytes(&kv.value) + 16)
+        .sum();
+    event.name.len() + attrs + 64
+}
+
+#[cfg(feature = "events")]
+fn estimate_value_bytes(value: &opentelemetry::Value) -> usize {
+    match value {
+        opentelemetry::Value::String(s) => s.as_str().len(),
+        opentelemetry::Value::Array(_) => 64,
+        _ => 8,
+    }
+}
+
+/// Facts about a span callsite that never change after first sight:
+/// the exported name (the naming function is documented as
+/// metadata-deterministic, which is what makes caching its result sound)
+/// and whether any reserved `otel.*` fields are declared. Cached per callsite
+/// in [`OpenTelemetryLayer::register_callsite`]-adjacent state so span
+/// creation skips the namer and, for the common reserved-field-free case,
+/// the per-field reserved-name comparisons.
+struct CachedCallsite {
+    name: String,
+    has_reserved_fields: bool,
+}
+
+#[derive(Default)]
+struct CallsiteCache {
+    entries: std::sync::RwLock<
+        std::collections::HashMap<tracing_core::callsite::Identifier, std::sync::Arc<CachedCallsite>>,
+    >,
+}
+
+impl CallsiteCache {
+    fn get_or_insert(
+        &self,
+        metadata: &'static tracing_core::Metadata<'static>,
+        namer: Option<&SpanNamer>,
+    ) -> std::sync::Arc<CachedCallsite> {
+        let id = metadata.callsite();
+        if let Some(cached) = self.entries.read().unwrap().get(&id) {
+            return cached.clone();
+        }
+        let cached = std::sync::Arc::new(CachedCallsite {
+            name: namer
+                .and_then(|namer| namer(metadata))
+                .unwrap_or_else(|| metadata.name().to_string()),
+            has_reserved_fields: metadata
+                .fields()
+                .iter()
+                .any(|field| field.name().starts_with("otel.")),
+        });
+        self.entries
+            .write()
+            .unwrap()
+            .insert(id, cached.clone());
+        cached
+    }
+}
+
+/// What a child span inherits from its parent at creation.
+#[derive(Default)]
+struct InheritedState {
+    capture_events: Option<bool>,
+    context_values:
+        Option<std::sync::Arc<std::collections::HashMap<String, opentelemetry::Value>>>,
+    /// Parent values of the configured inherited fields.
+    attributes: Vec<KeyValue>,
+}
+
+/// Unique per-instance ID so several layers can share one registry.
+fn next_layer_id() -> u64 {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Hook rewriting a DB statement attribute value before export.
+type StatementSanitizer = std::sync::Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Callback invoked when a span exceeds the configured duration budget.
+type BudgetHook = std::sync::Arc<dyn Fn(&str, std::time::Duration) + Send + Sync>;
+
+/// Hook choosing the exported name for a span from its metadata.
+type SpanNamer =
+    std::sync::Arc<dyn Fn(&tracing_core::Metadata<'_>) -> Option<String> + Send + Sync>;
+
+/// Bounded map of recently closed spans' contexts, so links created after a
+/// span closed (late `follows_from`) can still resolve it.
+struct ClosedSpanLru {
+    capacity: usize,
+    entries: std::sync::Mutex<(
+        std::collections::HashMap<u64, otel::SpanContext>,
+        std::collections::VecDeque<u64>,
+    )>,
+}
+
+impl ClosedSpanLru {
+    fn new(capacity: usize) -> Self {
+        ClosedSpanLru {
+            capacity: capacity.max(1),
+            entries: std::sync::Mutex::new(Default::default()),
+        }
+    }
+
+    fn insert(&self, id: u64, span_context: otel::SpanContext) {
+        let mut entries = self.entries.lock().unwrap();
+        let (map, order) = &mut *entries;
+        if map.insert(id, span_context).is_none() {
+            order.push_back(id);
+            if order.len() > self.capacity {
+                if let Some(evicted) = order.pop_front() {
+                    map.remove(&evicted);
+                }
+            }
+        }
+    }
+
+    fn get(&self, id: u64) -> Option<otel::SpanContext> {
+        self.entries.lock().unwrap().0.get(&id).cloned()
+    }
+}
+
+fn str_to_span_kind(s: &str) -> Option<SpanKind> {
+    match s {
+        s if s.eq_ignore_ascii_case("server") => Some(SpanKind::Server),
+        s if s.eq_ignore_ascii_case("client") => Some(SpanKind::Client),
+        s if s.eq_ignore_ascii_case("producer") => Some(SpanKind::Producer),
+        s if s.eq_ignore_ascii_case("consumer") => Some(SpanKind::Consumer),
+        s if s.eq_ignore_ascii_case("internal") => Some(SpanKind::Internal),
+        _ => None,
+    }
+}
+
+/// Parse the value of the reserved `otel.capture_events` field. `"debug"`,
+/// `"all"` and `true` force event capture for the span subtree; `"off"`,
+/// `"none"` and `false` suppress it.
+fn str_to_capture_events(s: &str) -> Option<bool> {
+    match s {
+        s if s.eq_ignore_ascii_case("debug") || s.eq_ignore_ascii_case("all") => Some(true),
+        s if s.eq_ignore_ascii_case("off") || s.eq_ignore_ascii_case("none") => Some(false),
+        _ => None,
+    }
+}
+
+fn str_to_status(s: &str) -> Status {
+    match s {
+        s if s.eq_ignore_ascii_case("ok") => Status::Ok,
+        s if s.eq_ignore_ascii_case("error") => Status::error(""),
+        _ => Status::Unset,
+    }
+}
+
+/// Elapsed busy/idle tracking for a span, recorded as `busy_ns`/`idle_ns`
+/// attributes when [`OpenTelemetryLayer::with_tracked_inactivity`] is on.
+///
+/// The default single-cursor accounting assumes enters and exits strictly
+/// alternate; a span entered on several threads at once (parallel fan-out,
+/// work-stealing executors) corrupts it. Per-enter mode (see
+/// [`OpenTelemetryLayer::with_per_enter_timings`]) keys each active enter
+/// by thread and sums the individual segments instead.
+#[derive(Debug)]
+pub(crate) struct Timings {
+    idle: u64,
+    busy: u64,
+    last: Instant,
+    /// Per-thread active enters; `Some` switches on per-enter mode.
+    active_enters: Option<std::collections::HashMap<std::thread::ThreadId, Instant>>,
+    enter_count: u64,
+    max_concurrency: usize,
+}
+
+impl Timings {
+    fn new(per_enter: bool) -> Self {
+        Timings {
+            idle: 0,
+            busy: 0,
+            last: Instant::now(),
+            active_enters: per_enter.then(std::collections::HashMap::new),
+            enter_count: 0,
+            max_concurrency: 0,
+        }
+    }
+
+    fn record_enter(&mut self) {
+        let now = Instant::now();
+        self.enter_count += 1;
+        match &mut self.active_enters {
+            Some(active) => {
+                active.insert(std::thread::current().id(), now);
+                self.max_concurrency = self.max_concurrency.max(active.len());
+            }
+            None => {
+                self.idle += (now - self.last).as_nanos() as u64;
+                self.last = now;
+            }
+        }
+    }
+
+    fn record_exit(&mut self) {
+        let now = Instant::now();
+        match &mut self.active_enters {
+            Some(active) => {
+                if let Some(entered) = active.remove(&std::thread::current().id()) {
+                    self.busy += (now - entered).as_nanos() as u64;
+                }
+            }
+            None => {
+                self.busy += (now - self.last).as_nanos() as u64;
+                self.last = now;
+            }
+        }
+    }
+}
+
+/// Records a `tracing` event's fields into an OpenTelemetry event.
+///
+/// Deliberately single-pass: one `event.record` walk fills both the event
+/// name (from the `message` field) and the attribute vector, which is
+/// pre-sized for the declared field count plus the metadata attributes.
+/// Splitting message extraction and attribute collection into two visitors
+/// would iterate every field twice per event — this is the layer's hottest
+/// path, so any change here must preserve the one-walk structure (covered
+/// by `event_fields_are_visited_exactly_once`).
+///
+/// The reserved `otel.event_time` field (unix time in nanoseconds)
+/// overrides the event's timestamp, for events describing something that
+/// happened at a different moment than the log statement — e.g. replaying
+/// device readings or attaching hardware-timestamped samples.
+#[cfg(feature = "events")]
+struct SpanEventVisitor<'a> {
+    event: &'a mut otel::Event,
+    /// Cap on string value length (`OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT`).
+    max_value_len: Option<usize>,
+}
+
+#[cfg(feature = "events")]
+impl field::Visit for SpanEventVisitor<'_> {
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        match field.name() {
+            "message" => self.event.name = value.to_string().into(),
+            name => self
+                .event
+                .attributes
+                .push(KeyValue::new(name, value)),
+        }
+    }
+
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        match field.name() {
+            "message" => self.event.name = value.to_string().into(),
+            name => self
+                .event
+                .attributes
+                .push(KeyValue::new(name, value)),
+        }
+    }
+
+    fn record_i64(&mut self, field: &field::Field, value: i64) {
+        match field.name() {
+            "message" => self.event.name = value.to_string().into(),
+            name => self
+                .event
+                .attributes
+                .push(KeyValue::new(name, value)),
+        }
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        if field.name() == EVENT_TIME_FIELD {
+            self.event.timestamp =
+                std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(value);
+            return;
+        }
+        self.record_i64(field, value as i64)
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        match field.name() {
+            "message" => self.event.name = value.to_string().into(),
+            name => self.event.attributes.push(KeyValue::new(
+                name,
+                truncate_value(value.to_string().into(), self.max_value_len),
+            )),
+        }
+    }
+
+    fn record_error(&mut self, field: &field::Field, value: &(dyn std::error::Error + 'static)) {
+        self.record_debug(field, &tracing::field::display(value))
+    }
+
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.event.name = format!("{value:?}").into(),
+            name => self.event.attributes.push(KeyValue::new(
+                name,
+                truncate_value(format!("{value:?}").into(), self.max_value_len),
+            )),
+        }
+    }
+}
+
+/// Fast-path visitor for callsites with no reserved `otel.*` fields (and a
+/// layer with no error-field or duplicate handling): records values
+/// straight into the builder without per-field name comparisons.
+struct FastAttributeVisitor<'a> {
+    builder: &'a mut SpanBuilder,
+    max_attributes: Option<usize>,
+    max_value_len: Option<usize>,
+    dropped_attributes: u64,
+}
+
+impl<'a> FastAttributeVisitor<'a> {
+    fn new(
+        builder: &'a mut SpanBuilder,
+        max_attributes: Option<usize>,
+        max_value_len: Option<usize>,
+    ) -> Self {
+        FastAttributeVisitor {
+            builder,
+            max_attributes,
+            max_value_len,
+            dropped_attributes: 0,
+        }
+    }
+
+    fn record(&mut self, mut attribute: KeyValue) {
+        let attributes = self.builder.attributes.get_or_insert_with(Vec::new);
+        if self
+            .max_attributes
+            .is_some_and(|max| attributes.len() >= max)
+        {
+            self.dropped_attributes += 1;
+            return;
+        }
+        attribute.value = truncate_value(attribute.value, self.max_value_len);
+        attributes.push(attribute);
+    }
+
+    fn finish(self) {
+        if self.dropped_attributes > 0 {
+            bump_dropped_attribute_count(self.builder, self.dropped_attributes);
+        }
+    }
+}
+
+impl field::Visit for FastAttributeVisitor<'_> {
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        self.record(KeyValue::new(field.name(), value));
+    }
+
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        self.record(KeyValue::new(field.name(), value));
+    }
+
+    fn record_i64(&mut self, field: &field::Field, value: i64) {
+        self.record(KeyValue::new(field.name(), value));
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        self.record(KeyValue::new(field.name(), value as i64));
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        self.record(KeyValue::new(field.name(), value.to_string()));
+    }
+
+    fn record_error(&mut self, field: &field::Field, value: &(dyn std::error::Error + 'static)) {
+        self.record_debug(field, &tracing::field::display(value))
+    }
+
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        self.record(KeyValue::new(field.name(), format!("{value:?}")));
+    }
+}
+
+/// Records a `tracing` span's fields into its pending [`SpanBuilder`],
+/// interpreting the reserved `otel.*` fields.
+///
+/// The status fields are merged in [`finish`](Self::finish) rather than
+/// applied immediately so the outcome does not depend on the order in which
+/// `otel.status_code` and `otel.status_description` are recorded.
+struct SpanAttributeVisitor<'a> {
+    builder: &'a mut SpanBuilder,
+    status_code: Option<Status>,
+    status_message: Option<String>,
+    capture_events: Option<bool>,
+    drop_span: Option<bool>,
+    /// Field names that imply error status (see
+    /// [`OpenTelemetryLayer::with_error_fields_to_status`]).
+    error_fields: &'a [String],
+    /// Cap on buffered attributes; surplus recordings are counted, not kept.
+    max_attributes: Option<usize>,
+    /// Cap on string value length (`OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT`).
+    max_value_len: Option<usize>,
+    dropped_attributes: u64,
+    duplicate_policy: DuplicateFieldPolicy,
+    /// Error message derived from one of `error_fields`; explicit
+    /// `otel.status_*` fields win over it.
+    derived_error: Option<String>,
+}
+
+impl<'a> SpanAttributeVisitor<'a> {
+    fn new(
+        builder: &'a mut SpanBuilder,
+        error_fields: &'a [String],
+        max_attributes: Option<usize>,
+        max_value_len: Option<usize>,
+        duplicate_policy: DuplicateFieldPolicy,
+    ) -> Self {
+        SpanAttributeVisitor {
+            builder,
+            status_code: None,
+            status_message: None,
+            capture_events: None,
+            drop_span: None,
+            error_fields,
+            derived_error: None,
+            max_attributes,
+            max_value_len,
+            dropped_attributes: 0,
+            duplicate_policy,
+        }
+    }
+
+    fn note_error_field(&mut self, field: &field::Field, message: String) {
+        if self.error_fields.iter().any(|f| f == field.name()) {
+            self.derived_error = Some(message);
+        }
+    }
+
+    fn record(&mut self, mut attribute: KeyValue) {
+        attribute.value = truncate_value(attribute.value, self.max_value_len);
+        let attributes = self.builder.attributes.get_or_insert_with(Vec::new);
+        if self.duplicate_policy != DuplicateFieldPolicy::Append {
+            if let Some(existing) = attributes.iter_mut().find(|kv| kv.key == attribute.key) {
+                existing.value = match self.duplicate_policy {
+                    DuplicateFieldPolicy::LastWins => attribute.value,
+                    DuplicateFieldPolicy::MultiValue => {
+                        merge_multi_value(std::mem::replace(
+                            &mut existing.value,
+                            opentelemetry::Value::Bool(false),
+                        ), attribute.value)
+                    }
+                    DuplicateFieldPolicy::Append => unreachable!(),
+                };
+                return;
+            }
+        }
+        if self
+            .max_attributes
+            .is_some_and(|max| attributes.len() >= max)
+        {
+            self.dropped_attributes += 1;
+            return;
+        }
+        attributes.push(attribute);
+    }
+
+    /// Apply the recorded status fields to the builder, returning the
+    /// values of the reserved `otel.capture_events` and `otel.drop` fields
+    /// if recorded.
+    fn finish(self) -> (Option<bool>, Option<bool>) {
+        if self.dropped_attributes > 0 {
+            bump_dropped_attribute_count(self.builder, self.dropped_attributes);
+        }
+        let reserved = (self.capture_events, self.drop_span);
+        match (self.status_code, self.status_message) {
+            (Some(Status::Error { .. }), Some(message)) | (None, Some(message)) => {
+                self.builder.status = Status::error(message)
+            }
+            (Some(status), None) | (Some(status), Some(_)) => self.builder.status = status,
+            (None, None) => {
+                if let Some(message) = self.derived_error {
+                    self.builder.status = Status::error(message);
+                }
+            }
+        }
+        reserved
+    }
+}
+
+impl field::Visit for SpanAttributeVisitor<'_> {
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        match field.name() {
+            SPAN_CAPTURE_EVENTS_FIELD => self.capture_events = Some(value),
+            SPAN_DROP_FIELD => self.drop_span = Some(value),
+            name => {
+                if value {
+                    self.note_error_field(field, String::new());
+                }
+                self.record(KeyValue::new(name, value));
+            }
+        }
+    }
+
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        self.record(KeyValue::new(field.name(), value));
+    }
+
+    fn record_i64(&mut self, field: &field::Field, value: i64) {
+        self.record(KeyValue::new(field.name(), value));
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        self.record_i64(field, value as i64)
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        match field.name() {
+            SPAN_NAME_FIELD => self.builder.name = value.to_string().into(),
+            SPAN_KIND_FIELD => self.builder.span_kind = str_to_span_kind(value),
+            SPAN_STATUS_CODE_FIELD => self.status_code = Some(str_to_status(value)),
+            SPAN_STATUS_DESCRIPTION_FIELD => self.status_message = Some(value.to_string()),
+            SPAN_CAPTURE_EVENTS_FIELD => self.capture_events = str_to_capture_events(value),
+            name => {
+                self.note_error_field(field, value.to_string());
+                self.record(KeyValue::new(name, value.to_string()));
+            }
+        }
+    }
+
+    fn record_error(&mut self, field: &field::Field, value: &(dyn std::error::Error + 'static)) {
+        self.record_debug(field, &tracing::field::display(value))
+    }
+
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            SPAN_NAME_FIELD => self.builder.name = format!("{value:?}").into(),
+            SPAN_KIND_FIELD => {
+                self.builder.span_kind = str_to_span_kind(&format!("{value:?}"))
+            }
+            SPAN_STATUS_CODE_FIELD => {
+                self.status_code = Some(str_to_status(&format!("{value:?}")))
+            }
+            SPAN_STATUS_DESCRIPTION_FIELD => {
+                self.status_message = Some(format!("{value:?}"))
+            }
+            SPAN_CAPTURE_EVENTS_FIELD => {
+                self.capture_events = str_to_capture_events(&format!("{value:?}"))
+            }
+            name => {
+                self.note_error_field(field, format!("{value:?}"));
+                self.record(KeyValue::new(name, format!("{value:?}")));
+            }
+        }
+    }
+}
+
+impl<S, T> OpenTelemetryLayer<S, T>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    T: otel::Tracer + PreSampledTracer + 'static,
+{
+    /// Create a new layer using the given [`PreSampledTracer`].
+    pub fn new(tracer: T) -> Self {
+        OpenTelemetryLayer {
+            layer_id: next_layer_id(),
+            tracer,
+            scoped_tracers: Vec::new(),
+            location: true,
+            tracked_inactivity: true,
+            per_enter_timings: false,
+            profiling_correlation: false,
+            follows_from_attribute_snapshot: false,
+            closed_span_lru: None,
+            child_aggregation_threshold: None,
+            live_spans: None,
+            span_feed: None,
+            deferred_export: None,
+            memory_budget: None,
+            #[cfg(feature = "metrics")]
+            span_metrics: None,
+            stats: None,
+            backpressure: None,
+            adaptive_feedback: None,
+            error_fields: Vec::new(),
+            inherited_fields: Vec::new(),
+            error_events_to_status: false,
+            lifecycle_events: false,
+            max_attributes_per_span: None,
+            max_attribute_value_len: None,
+            disabled: false,
+            clock: std::sync::Arc::new(SystemClock),
+            callsite_cache: Default::default(),
+            duplicate_field_policy: DuplicateFieldPolicy::default(),
+            duration_budget: None,
+            budget_hook: None,
+            span_namer: None,
+            dynamic_targets: None,
+            with_threads: true,
+            with_level: false,
+            with_target: true,
+            max_events_per_span: None,
+            event_overflow_policy: EventOverflowPolicy::default(),
+            events_export_filter: EventsExportFilter::Always,
+            tracestate_debug_flag: None,
+            tail_sampling: None,
+            span_rate_limiter: None,
+            propagator: None,
+            conventions: ConventionsMode::default(),
+            db_statement_sanitizer: None,
+            redaction: None,
+            #[cfg(feature = "logs")]
+            span_log_mirror: None,
+            get_context: WithContext {
+                with_context: Self::get_context,
+                propagator: Self::get_propagator,
+                record_event: Self::record_span_event,
+            },
+            _registry: marker::PhantomData,
+        }
+    }
+
+    /// Apply configuration directives from the `N00_OTEL` environment
+    /// variable, in an `EnvFilter`-like comma-separated `key[=value]`
+    /// syntax. See [`apply_directives`](Self::apply_directives) for the
+    /// vocabulary. Missing variable means no changes.
+    pub fn with_env_config(self) -> Self {
+        match std::env::var("N00_OTEL") {
+            Ok(directives) => self.apply_directives(&directives),
+            Err(_) => self,
+        }
+    }
+
+    /// Honor the standard `OTEL_*` environment variables that map onto
+    /// layer options:
+    ///
+    /// * `OTEL_SDK_DISABLED=true` — disable the layer entirely
+    /// * `OTEL_SPAN_EVENT_COUNT_LIMIT` — [`with_max_events_per_span`]
+    /// * `OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT` — [`with_max_attributes_per_span`]
+    /// * `OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT` —
+    ///   [`with_max_attribute_value_length`]
+    ///
+    /// Resource and sampler variables (`OTEL_SERVICE_NAME`,
+    /// `OTEL_RESOURCE_ATTRIBUTES`, `OTEL_TRACES_SAMPLER`, ...) belong to
+    /// the SDK provider and are honored there, not here.
+    ///
+    /// [`with_max_events_per_span`]: Self::with_max_events_per_span
+    /// [`with_max_attributes_per_span`]: Self::with_max_attributes_per_span
+    /// [`with_max_attribute_value_length`]: Self::with_max_attribute_value_length
+    pub fn with_standard_otel_env(self) -> Self {
+        self.apply_standard_otel_env(|name| std::env::var(name).ok())
+    }
+
+    /// Testable core of [`with_standard_otel_env`](Self::with_standard_otel_env).
+    fn apply_standard_otel_env(mut self, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        if lookup("OTEL_SDK_DISABLED").as_deref() == Some("true") {
+            self.disabled = true;
+        }
+        if let Some(limit) = lookup("OTEL_SPAN_EVENT_COUNT_LIMIT").and_then(|v| v.parse().ok()) {
+            self = self.with_max_events_per_span(limit);
+        }
+        if let Some(limit) =
+            lookup("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT").and_then(|v| v.parse().ok())
+        {
+            self = self.with_max_attributes_per_span(limit);
+        }
+        if let Some(limit) =
+            lookup("OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT").and_then(|v| v.parse().ok())
+        {
+            self = self.with_max_attribute_value_length(limit);
+        }
+        self
+    }
+
+    /// Test-only entry to the standard env mapping with explicit values.
+    #[doc(hidden)]
+    pub fn apply_standard_otel_vars(
+        self,
+        vars: &std::collections::HashMap<String, String>,
+    ) -> Self {
+        self.apply_standard_otel_env(|name| vars.get(name).cloned())
+    }
+
+    /// Apply a directive string such as
+    /// `tail_events=64,events_on_error_only,rate_limit=100/10,memory_budget=4MiB`.
+    ///
+    /// Supported keys: `max_events`, `tail_events`, `overflow`
+    /// (`oldest`/`newest`/`summarize`), `events_on_error_only`,
+    /// `max_attributes`, `rate_limit` (`per_second/burst`),
+    /// `memory_budget` (byte size), `duration_budget` (duration),
+    /// `location`, `tracked_inactivity`, `threads`, `level`, `target`
+    /// (booleans: `on`/`off`), `redact` (`common_pii`). Unknown or
+    /// malformed directives are ignored so a stale variable can't take a
+    /// service down.
+    pub fn apply_directives(mut self, directives: &str) -> Self {
+        let as_bool = |value: Option<&str>| !matches!(value, Some("off") | Some("false") | Some("0"));
+        for directive in directives.split(',') {
+            let directive = directive.trim();
+            if directive.is_empty() {
+                continue;
+            }
+            let (key, value) = match directive.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (directive, None),
+            };
+            match key {
+                "max_events" => {
+                    if let Some(n) = value.and_then(|v| v.parse().ok()) {
+                        self = self.with_max_events_per_span(n);
+                    }
+                }
+                "tail_events" => {
+                    if let Some(n) = value.and_then(|v| v.parse().ok()) {
+                        self = self.with_tail_events(n);
+                    }
+                }
+                "overflow" => {
+                    let policy = match value {
+                        Some("oldest") => Some(EventOverflowPolicy::DropOldest),
+                        Some("newest") => Some(EventOverflowPolicy::DropNewest),
+                        Some("summarize") => Some(EventOverflowPolicy::Summarize),
+                        _ => None,
+                    };
+                    if let Some(policy) = policy {
+                        self = self.with_event_overflow_policy(policy);
+                    }
+                }
+                "events_on_error_only" => {
+                    self = self.with_events_on_error_only(as_bool(value));
+                }
+                "max_attributes" => {
+                    if let Some(n) = value.and_then(|v| v.parse().ok()) {
+                        self = self.with_max_attributes_per_span(n);
+                    }
+                }
+                "rate_limit" => {
+                    if let Some((per_second, burst)) = value.and_then(|v| {
+                        let (p, b) = v.split_once('/')?;
+                        Some((p.parse().ok()?, b.parse().ok()?))
+                    }) {
+                        self = self.with_span_rate_limit(per_second, burst);
+                    }
+                }
+                "memory_budget" => {
+                    if let Some(bytes) = value.and_then(crate::attrs::parse_byte_size) {
+                        self = self.with_memory_budget(bytes as usize);
+                    }
+                }
+                "duration_budget" => {
+                    if let Some(budget) = value.and_then(crate::attrs::parse_duration) {
+                        self = self.with_duration_budget(budget);
+                    }
+                }
+                "location" => self = self.with_location(as_bool(value)),
+                "tracked_inactivity" => self = self.with_tracked_inactivity(as_bool(value)),
+                "threads" => self = self.with_threads(as_bool(value)),
+                "level" => self = self.with_level(as_bool(value)),
+                "target" => self = self.with_target(as_bool(value)),
+                "redact" if value == Some("common_pii") => {
+                    self = self.with_redaction(RedactionPolicy::common_pii());
+                }
+                // Unknown directives are ignored by design.
+                _ => {}
+            }
+        }
+        self
+    }
+
+    /// Swap the tracer, keeping the layer's configuration.
+    pub fn with_tracer<Tracer>(self, tracer: Tracer) -> OpenTelemetryLayer<S, Tracer>
+    where
+        Tracer: otel::Tracer + PreSampledTracer + 'static,
+    {
+        OpenTelemetryLayer {
+            layer_id: self.layer_id,
+            tracer,
+            scoped_tracers: Vec::new(),
+            location: self.location,
+            tracked_inactivity: self.tracked_inactivity,
+            per_enter_timings: self.per_enter_timings,
+            profiling_correlation: self.profiling_correlation,
+            follows_from_attribute_snapshot: self.follows_from_attribute_snapshot,
+            closed_span_lru: self.closed_span_lru,
+            child_aggregation_threshold: self.child_aggregation_threshold,
+            live_spans: self.live_spans,
+            span_feed: self.span_feed,
+            deferred_export: None,
+            memory_budget: self.memory_budget,
+            #[cfg(feature = "metrics")]
+            span_metrics: self.span_metrics,
+            stats: self.stats,
+            backpressure: self.backpressure,
+            adaptive_feedback: self.adaptive_feedback,
+            error_fields: self.error_fields,
+            inherited_fields: self.inherited_fields,
+            error_events_to_status: self.error_events_to_status,
+            lifecycle_events: self.lifecycle_events,
+            max_attributes_per_span: self.max_attributes_per_span,
+            max_attribute_value_len: self.max_attribute_value_len,
+            disabled: self.disabled,
+            clock: self.clock,
+            callsite_cache: self.callsite_cache,
+            duplicate_field_policy: self.duplicate_field_policy,
+            duration_budget: self.duration_budget,
+            budget_hook: self.budget_hook,
+            span_namer: self.span_namer,
+            dynamic_targets: self.dynamic_targets,
+            with_threads: self.with_threads,
+            with_level: self.with_level,
+            with_target: self.with_target,
+            max_events_per_span: self.max_events_per_span,
+            event_overflow_policy: self.event_overflow_policy,
+            events_export_filter: self.events_export_filter,
+            tracestate_debug_flag: self.tracestate_debug_flag,
+            tail_sampling: self.tail_sampling,
+            span_rate_limiter: self.span_rate_limiter,
+            propagator: self.propagator,
+            conventions: self.conventions,
+            db_statement_sanitizer: self.db_statement_sanitizer,
+            redaction: self.redaction,
+            #[cfg(feature = "logs")]
+            span_log_mirror: self.span_log_mirror,
+            get_context: WithContext {
+                with_context: OpenTelemetryLayer::<S, Tracer>::get_context,
+                propagator: OpenTelemetryLayer::<S, Tracer>::get_propagator,
+                record_event: OpenTelemetryLayer::<S, Tracer>::record_span_event,
+            },
+            _registry: self._registry,
+        }
+    }
+
+    /// Export spans whose `tracing` target starts with `target_prefix`
+    /// through `tracer` instead of the default one.
+    ///
+    /// This maps module trees to OpenTelemetry instrumentation scopes, so
+    /// e.g. `my_app::db` spans can carry a `db-client` scope while the rest
+    /// of the application uses the service scope. The longest matching
+    /// prefix wins; IDs and sampling still come from the default tracer, so
+    /// all scopes share one trace.
+    pub fn with_scoped_tracer(mut self, target_prefix: impl Into<String>, tracer: T) -> Self {
+        self.scoped_tracers.push((target_prefix.into(), tracer));
+        // Longest prefix first, so lookup can take the first match.
+        self.scoped_tracers
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    /// The tracer responsible for spans with the given target.
+    fn tracer_for(&self, target: &str) -> &T {
+        self.scoped_tracers
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map(|(_, tracer)| tracer)
+            .unwrap_or(&self.tracer)
+    }
+
+    /// Record source code locations (`code.*` attributes) on events.
+    /// Enabled by default.
+    pub fn with_location(mut self, location: bool) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Record busy/idle timings (`busy_ns`/`idle_ns` attributes) on spans.
+    /// Enabled by default.
+    pub fn with_tracked_inactivity(mut self, tracked_inactivity: bool) -> Self {
+        self.tracked_inactivity = tracked_inactivity;
+        self
+    }
+
+    /// Choose how repeated recordings of one attribute key are stored:
+    /// appended as duplicates (the default, OTel semantics), last-wins, or
+    /// merged into an array value. See [`DuplicateFieldPolicy`].
+    pub fn with_duplicate_field_policy(mut self, policy: DuplicateFieldPolicy) -> Self {
+        self.duplicate_field_policy = policy;
+        self
+    }
+
+    /// Take wall-clock timestamps (span start/end, event times) from the
+    /// given [`Clock`] instead of the system clock. Inject a
+    /// [`ManualClock`] for deterministic timing in tests and simulations.
+    ///
+    /// [`ManualClock`]: crate::ManualClock
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = std::sync::Arc::new(clock);
+        self
+    }
+
+    /// Cap how many attributes a span may *buffer*, dropping (and
+    /// counting, in `otel.dropped_attributes_count`) further recordings.
+    ///
+    /// The SDK's own span limits truncate at export; on embedded and other
+    /// memory-tight targets the buffering itself is the problem, so this
+    /// cap applies at record time and bounds the per-span allocation.
+    pub fn with_max_attributes_per_span(mut self, n: usize) -> Self {
+        self.max_attributes_per_span = Some(n);
+        self
+    }
+
+    /// Record `span.entered`/`span.exited` events on every enter and exit.
+    ///
+    /// A span's busy/idle attributes summarize scheduling; the lifecycle
+    /// events show it on the timeline — each await-point suspension of an
+    /// instrumented future becomes a visible exit/enter pair. Off by
+    /// default: enters are the hottest path there is, and the events count
+    /// against the per-span limits like any others.
+    pub fn with_lifecycle_events(mut self, enabled: bool) -> Self {
+        self.lifecycle_events = enabled;
+        self
+    }
+
+    /// Truncate string attribute values (span and event attributes alike)
+    /// to `max_len` bytes at record time, the behavior
+    /// `OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT` standardizes. Bounds buffering,
+    /// unlike the SDK's export-time limits.
+    pub fn with_max_attribute_value_length(mut self, max_len: usize) -> Self {
+        self.max_attribute_value_len = Some(max_len);
+        self
+    }
+
+    /// Set the span status to [`Status::Error`] when an ERROR-level event
+    /// occurs inside it, using the (last) event's message as the status
+    /// description. Explicit statuses — `otel.status_*` fields,
+    /// [`set_status`], configured error fields — are never overridden.
+    ///
+    /// [`set_status`]: crate::OpenTelemetrySpanExt::set_status
+    pub fn with_error_events_to_status(mut self, enabled: bool) -> Self {
+        self.error_events_to_status = enabled;
+        self
+    }
+
+    /// Copy the given attributes from a parent span onto each child span
+    /// at creation.
+    ///
+    /// Identity attributes like `tenant.id` or `request.id` are usually
+    /// recorded once on the request root, but backends that only search
+    /// leaf spans then miss them. With the keys configured here, every
+    /// child carries its parent's value (and re-inherits transitively down
+    /// the tree); a value the child records itself wins over the inherited
+    /// one.
+    ///
+    /// Only creation-time parent values propagate — a value recorded on
+    /// the parent after a child was created is not retrofitted.
+    pub fn with_inherited_fields<I, F>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        self.inherited_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Treat the given span fields as error markers: recording one of them
+    /// (a non-empty string/debug value, or `true`) sets the span status to
+    /// [`Status::Error`] with the value as description, unless an explicit
+    /// `otel.status_code`/`otel.status_description` says otherwise.
+    ///
+    /// Replaces any previously configured list; the fields are still
+    /// exported as ordinary attributes too.
+    pub fn with_error_fields_to_status<I, F>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        self.error_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Feed span outcomes (name + error status) back into an
+    /// [`AdaptiveSampler`] at span close, closing the loop that lets the
+    /// sampler boost names with rising error rates.
+    pub fn with_adaptive_feedback(mut self, sampler: AdaptiveSampler) -> Self {
+        self.adaptive_feedback = Some(sampler);
+        self
+    }
+
+    /// Degrade under exporter overload, staged by the given
+    /// [`BackpressureSignal`]'s thresholds: past the first, span events are
+    /// shed (counted in `otel.dropped_event_count`); past the second
+    /// (see [`BackpressureSignal::with_thresholds`]), non-error spans are
+    /// no longer exported at all, so a saturated exporter receives only
+    /// failures. Pair with [`BackpressureExporter`] (or anything else
+    /// driving the signal).
+    ///
+    /// [`BackpressureExporter`]: crate::BackpressureExporter
+    pub fn with_backpressure(mut self, signal: BackpressureSignal) -> Self {
+        self.backpressure = Some(signal);
+        self
+    }
+
+    /// Record self-telemetry counters into the given [`LayerStats`] handle:
+    /// spans started/exported/suppressed, events recorded/dropped.
+    pub fn with_stats(mut self, stats: LayerStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Publish span lifecycle events to the given [`SpanFeed`] for local
+    /// consumers (console UIs, in-process aggregation). Publishing is a
+    /// bounded non-blocking send per subscriber; without subscribers it is
+    /// a no-op.
+    pub fn with_span_feed(mut self, feed: SpanFeed) -> Self {
+        self.span_feed = Some(feed);
+        self
+    }
+
+    /// Track open spans in the given [`LiveSpans`] handle, enabling live
+    /// introspection of everything currently in flight.
+    ///
+    /// Forces ID allocation at span creation so the listed spans are
+    /// joinable against exported data.
+    pub fn with_live_spans(mut self, live_spans: LiveSpans) -> Self {
+        self.live_spans = Some(live_spans.registry);
+        self
+    }
+
+    /// Flag spans that run longer than `budget`.
+    ///
+    /// Offending spans export `n00.budget_exceeded = true` together with
+    /// the budget and the overrun in milliseconds, so a backend query for
+    /// the attribute finds every blown budget without duration math.
+    pub fn with_duration_budget(mut self, budget: std::time::Duration) -> Self {
+        self.duration_budget = Some(budget);
+        self
+    }
+
+    /// Additionally invoke `hook(span_name, duration)` when a span exceeds
+    /// the [duration budget](Self::with_duration_budget) — e.g. to bump a
+    /// counter or page in-process.
+    pub fn with_budget_exceeded_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, std::time::Duration) + Send + Sync + 'static,
+    {
+        self.budget_hook = Some(std::sync::Arc::new(hook));
+        self
+    }
+
+    /// Derive RED metrics from finished spans: a `traces.span.calls`
+    /// counter and `traces.span.duration` histogram (ms), attributed by
+    /// span name, kind and outcome. Requires the `metrics` feature.
+    ///
+    /// This sidesteps the classic gap where dashboards need rate/error/
+    /// duration but the sampled traces alone can't provide unbiased
+    /// counts: the metrics are recorded for *every* finished span, before
+    /// sampling or rate limiting drop it.
+    #[cfg(feature = "metrics")]
+    pub fn with_span_metrics(mut self, meter: &opentelemetry::metrics::Meter) -> Self {
+        self.span_metrics = Some(std::sync::Arc::new(crate::metrics::SpanMetrics::new(
+            meter, None,
+        )));
+        self
+    }
+
+    /// Like [`with_span_metrics`](Self::with_span_metrics), with explicit
+    /// bucket boundaries (in milliseconds) for the duration histogram.
+    ///
+    /// The SDK's default buckets top out early for batch workloads and are
+    /// too coarse for single-digit-millisecond services; the boundaries
+    /// should bracket the latency range that actually matters.
+    #[cfg(feature = "metrics")]
+    pub fn with_span_metrics_buckets(
+        mut self,
+        meter: &opentelemetry::metrics::Meter,
+        bucket_boundaries_ms: Vec<f64>,
+    ) -> Self {
+        self.span_metrics = Some(std::sync::Arc::new(crate::metrics::SpanMetrics::new(
+            meter,
+            Some(bucket_boundaries_ms),
+        )));
+        self
+    }
+
+    /// Cap the estimated bytes of event data buffered across *all* open
+    /// spans of this layer.
+    ///
+    /// Per-span limits bound each span; a surge of ten thousand concurrent
+    /// spans still multiplies them. Once the global estimate reaches
+    /// `bytes`, further events are dropped (and counted) until closing
+    /// spans refund their share.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(std::sync::Arc::new(MemoryBudget {
+            used: std::sync::atomic::AtomicUsize::new(0),
+            budget: bytes,
+        }));
+        self
+    }
+
+    /// Build and export finished spans on a dedicated background thread
+    /// instead of inline in `on_close`.
+    ///
+    /// `build_with_context` runs sampling, limits and (with a simple
+    /// processor) the exporter itself; offloading moves that cost off the
+    /// instrumented thread. The queue is bounded to `queue_capacity`
+    /// finished spans — when full, new spans are dropped (counted as
+    /// suppressed in [`LayerStats`]) rather than blocking the application.
+    ///
+    /// Call after [`with_tracer`](Self::with_tracer); the worker exports
+    /// through a clone of the current default tracer, so per-target scoped
+    /// tracers do not apply to offloaded spans. The worker exits when the
+    /// layer is dropped.
+    ///
+    /// [`LayerStats`]: crate::LayerStats
+    pub fn with_deferred_export(mut self, queue_capacity: usize) -> Self
+    where
+        T: Clone + Send + Sync,
+    {
+        let (sender, receiver) =
+            std::sync::mpsc::sync_channel::<BufferedSpan>(queue_capacity.max(1));
+        let tracer = self.tracer.clone();
+        std::thread::Builder::new()
+            .name("n00-otel-span-builder".into())
+            .spawn(move || {
+                while let Ok(buffered) = receiver.recv() {
+                    let _ = tracer.build_with_context(buffered.builder, &buffered.parent_cx);
+                }
+            })
+            .expect("failed to spawn span builder thread");
+        self.deferred_export = Some(sender);
+        self
+    }
+
+    /// Watch *open* spans for budget overruns, every `interval`.
+    ///
+    /// The close-time attributes only ever describe spans that finish; a
+    /// genuinely stalled span — the case a watchdog exists for — never
+    /// closes and so never gets flagged there. The watchdog sweeps the
+    /// open-span registry on a background thread and invokes the
+    /// [budget hook](Self::with_budget_exceeded_hook) once per offending
+    /// span while it is still running, with its age so far; the close-time
+    /// attributes remain the export record for spans that do finish.
+    ///
+    /// Call after [`with_duration_budget`](Self::with_duration_budget) and
+    /// the hook; the thread exits when the layer is dropped.
+    pub fn with_stall_watchdog(mut self, interval: std::time::Duration) -> Self {
+        let Some(budget) = self.duration_budget else {
+            return self;
+        };
+        let registry = self
+            .live_spans
+            .get_or_insert_with(Default::default)
+            .clone();
+        let hook = self.budget_hook.clone();
+        let weak = std::sync::Arc::downgrade(&registry);
+        std::thread::Builder::new()
+            .name("n00-otel-watchdog".into())
+            .spawn(move || {
+                while let Some(registry) = weak.upgrade() {
+                    for open in registry.due_for_budget_flag(budget) {
+                        let age = std::time::SystemTime::now()
+                            .duration_since(open.start)
+                            .unwrap_or_default();
+                        if let Some(hook) = &hook {
+                            hook(&open.name, age);
+                        }
+                    }
+                    drop(registry);
+                    std::thread::sleep(interval);
+                }
+            })
+            .expect("failed to spawn watchdog thread");
+        self
+    }
+
+    /// Periodically export *partial* copies of spans that have been open
+    /// for at least `min_age`, every `interval`.
+    ///
+    /// A span only becomes visible when it closes; for multi-hour jobs that
+    /// means nothing is queryable until the end (or ever, if the process
+    /// dies). Heartbeat exports reuse the span's real trace/span IDs with
+    /// end time "now" and `n00.partial = true` plus a `n00.heartbeat.seq`
+    /// counter, so the backend always holds the latest approximation and
+    /// the final export supersedes it.
+    ///
+    /// Call after [`with_tracer`](Self::with_tracer): the heartbeat thread
+    /// exports through a clone of the current tracer.
+    pub fn with_heartbeat(self, interval: std::time::Duration, min_age: std::time::Duration) -> Self
+    where
+        T: Clone + Send + Sync,
+    {
+        let mut layer = self;
+        let registry = layer
+            .live_spans
+            .get_or_insert_with(Default::default)
+            .clone();
+        let tracer = layer.tracer.clone();
+        let weak = std::sync::Arc::downgrade(&registry);
+        std::thread::Builder::new()
+            .name("n00-otel-heartbeat".into())
+            .spawn(move || {
+                while let Some(registry) = weak.upgrade() {
+                    for open in registry.due_for_heartbeat(min_age) {
+                        let mut builder = SpanBuilder::from_name(open.name.clone())
+                            .with_start_time(open.start)
+                            .with_end_time(time::now())
+                            .with_attributes(vec![
+                                KeyValue::new("n00.partial", true),
+                                KeyValue::new("n00.heartbeat.seq", open.heartbeats as i64),
+                            ]);
+                        builder.trace_id = Some(open.trace_id);
+                        builder.span_id = Some(open.span_id);
+                        builder.sampling_result = Some(otel::SamplingResult {
+                            decision: otel::SamplingDecision::RecordAndSample,
+                            attributes: Vec::new(),
+                            trace_state: Default::default(),
+                        });
+                        let _ = tracer.build_with_context(builder, &OtelContext::new());
+                    }
+                    drop(registry);
+                    std::thread::sleep(interval);
+                }
+            })
+            .expect("failed to spawn heartbeat thread");
+        layer
+    }
+
+    /// Collapse repetitive child spans: once a parent has exported
+    /// `threshold` children with the same name, further same-named children
+    /// are folded into a per-name count and total duration on the parent
+    /// (an `aggregated_children` event per name at parent close).
+    ///
+    /// A retry loop or per-row operation that creates ten thousand
+    /// identical children drowns the trace view and the exporter; with
+    /// aggregation the first `threshold` representatives survive and the
+    /// rest become one line of arithmetic.
+    pub fn with_child_span_aggregation(mut self, threshold: u64) -> Self {
+        self.child_aggregation_threshold = Some(threshold.max(1));
+        self
+    }
+
+    /// Gate this layer's spans and events through a runtime-reloadable
+    /// [`DynamicTargets`] map.
+    ///
+    /// Only this layer's OTel export is affected: the check runs inside
+    /// this layer's `on_new_span`/`on_event`, never through
+    /// `Layer::enabled`, so fmt/file logging and every other layer on the
+    /// registry keep their own filtering and verbosity. Descendants of a
+    /// filtered-out span stay in the trace, parented to the nearest
+    /// exported ancestor.
+    pub fn with_dynamic_targets(mut self, targets: DynamicTargets) -> Self {
+        self.dynamic_targets = Some(targets);
+        self
+    }
+
+    /// Derive exported span names from *static metadata* (name, target,
+    /// module path, file) instead of using the `tracing` span name
+    /// verbatim.
+    ///
+    /// Returning `None` keeps the original name. Because the function only
+    /// sees `&Metadata`, its result is cached per callsite — it must be
+    /// metadata-deterministic, and names derived from recorded *field
+    /// values* (e.g. `"{http.method} {http.route}"`) cannot be expressed
+    /// here: record those through the reserved `otel.name` field, which
+    /// always overrides the naming function. Runs at span creation, so
+    /// samplers and tail-sampling policies see the derived name too:
+    ///
+    /// ```
+    /// let layer = n00_otel::layer::<tracing_subscriber::Registry>()
+    ///     .with_span_name_fn(|meta| Some(format!("{}::{}", meta.target(), meta.name())));
+    /// # drop(layer);
+    /// ```
+    pub fn with_span_name_fn<F>(mut self, namer: F) -> Self
+    where
+        F: Fn(&tracing_core::Metadata<'_>) -> Option<String> + Send + Sync + 'static,
+    {
+        self.span_namer = Some(std::sync::Arc::new(namer));
+        self
+    }
+
+    /// Remember the contexts of the last `capacity` closed spans, so a
+    /// `follows_from` recorded after the followed span already closed still
+    /// produces a link instead of being dropped.
+    ///
+    /// Common with detached work: the producer span closes while the
+    /// consumer task is still queued. Costs one bounded map insert per
+    /// closed span.
+    pub fn with_closed_span_lru(mut self, capacity: usize) -> Self {
+        self.closed_span_lru = Some(std::sync::Arc::new(ClosedSpanLru::new(capacity)));
+        self
+    }
+
+    /// Copy the followed span's attributes (as recorded at link time) onto
+    /// `follows_from` links.
+    ///
+    /// A follows-from link normally carries only IDs; when the followed
+    /// span lives in another trace or is sampled away, the link is all the
+    /// consumer gets, and a snapshot of the originating span's attributes
+    /// often makes it actionable. Off by default since it clones the
+    /// attribute list per link.
+    pub fn with_follows_from_attribute_snapshot(mut self, enabled: bool) -> Self {
+        self.follows_from_attribute_snapshot = enabled;
+        self
+    }
+
+    /// Track busy time per individual enter, keyed by thread, instead of
+    /// assuming enters and exits alternate.
+    ///
+    /// Correct for spans entered concurrently from several threads; also
+    /// exports `enter_count` and `max_concurrency` attributes. Idle time is
+    /// not tracked in this mode. Implies keeping the timing state of
+    /// [`with_tracked_inactivity`](Self::with_tracked_inactivity).
+    pub fn with_per_enter_timings(mut self, enabled: bool) -> Self {
+        self.per_enter_timings = enabled;
+        if enabled {
+            self.tracked_inactivity = true;
+        }
+        self
+    }
+
+    /// Maintain per-thread trace/span IDs for profiler correlation (see
+    /// [`profiling::current_thread_trace_ids`]). Disabled by default: it
+    /// forces ID allocation and sampling on first enter, which the common
+    /// case doesn't need.
+    ///
+    /// [`profiling::current_thread_trace_ids`]: crate::profiling::current_thread_trace_ids
+    pub fn with_profiling_correlation(mut self, enabled: bool) -> Self {
+        self.profiling_correlation = enabled;
+        self
+    }
+
+    /// Record thread name/id (`thread.*` attributes) on spans. Enabled by
+    /// default.
+    pub fn with_threads(mut self, threads: bool) -> Self {
+        self.with_threads = threads;
+        self
+    }
+
+    /// Record the event's `tracing` level as a `level` attribute. Disabled by
+    /// default.
+    pub fn with_level(mut self, level: bool) -> Self {
+        self.with_level = level;
+        self
+    }
+
+    /// Record the event's `tracing` target as a `target` attribute. Enabled
+    /// by default.
+    pub fn with_target(mut self, target: bool) -> Self {
+        self.with_target = target;
+        self
+    }
+
+    /// Cap the number of events buffered for a single span.
+    ///
+    /// Without a cap, a tight loop logging inside one long-lived span
+    /// accumulates events until the span closes, which can exhaust memory.
+    /// Once `n` events are buffered, further events are handled according to
+    /// the configured [`EventOverflowPolicy`] and counted in the
+    /// `otel.dropped_event_count` span attribute.
+    pub fn with_max_events_per_span(mut self, n: usize) -> Self {
+        self.max_events_per_span = Some(n);
+        self
+    }
+
+    /// Choose what happens to events recorded past the
+    /// [limit](Self::with_max_events_per_span). Defaults to
+    /// [`EventOverflowPolicy::DropNewest`].
+    pub fn with_event_overflow_policy(mut self, policy: EventOverflowPolicy) -> Self {
+        self.event_overflow_policy = policy;
+        self
+    }
+
+    /// Keep only the last `n` events per span, in a fixed-size ring.
+    ///
+    /// Shorthand for [`with_max_events_per_span`] combined with
+    /// [`EventOverflowPolicy::DropOldest`]. When a span fails, the events
+    /// immediately preceding the failure are the ones worth exporting, and
+    /// in this mode they are the ones retained — a head-biased limit would
+    /// keep the span's setup chatter instead.
+    ///
+    /// [`with_max_events_per_span`]: Self::with_max_events_per_span
+    pub fn with_tail_events(self, n: usize) -> Self {
+        self.with_max_events_per_span(n)
+            .with_event_overflow_policy(EventOverflowPolicy::DropOldest)
+    }
+
+    /// Attach buffered events to the exported span only when the span ends
+    /// with [`Status::Error`].
+    ///
+    /// Spans that end cleanly export just the observed event count in the
+    /// `otel.tracing_event_count` attribute, which keeps the happy path
+    /// close to free while failures still carry their full event detail.
+    /// Usually combined with [`with_tail_events`](Self::with_tail_events).
+    pub fn with_events_on_error_only(mut self, on_error_only: bool) -> Self {
+        self.events_export_filter = if on_error_only {
+            EventsExportFilter::OnErrorOnly
+        } else {
+            EventsExportFilter::Always
+        };
+        self
+    }
+
+    /// Run every `db.query.text`/`db.statement` attribute value through
+    /// `sanitizer` before export.
+    ///
+    /// Statement attributes routinely carry literals — emails, tokens, row
+    /// contents — that must not reach a trace backend. The hook is applied
+    /// at span close to both span and event attributes;
+    /// [`sanitize_sql`](crate::sanitize_sql) is a ready-made sanitizer for
+    /// SQL literals.
+    pub fn with_db_statement_sanitizer<F>(mut self, sanitizer: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.db_statement_sanitizer = Some(std::sync::Arc::new(sanitizer));
+        self
+    }
+
+    /// Keys the DB statement sanitizer applies to.
+    fn is_db_statement_key(key: &str) -> bool {
+        matches!(key, "db.query.text" | "db.statement")
+    }
+
+    /// Apply the configured statement sanitizer to an attribute list.
+    fn sanitize_db_statements(&self, attributes: &mut [KeyValue]) {
+        let Some(sanitizer) = &self.db_statement_sanitizer else {
+            return;
+        };
+        for kv in attributes {
+            if Self::is_db_statement_key(kv.key.as_str()) {
+                if let opentelemetry::Value::String(text) = &kv.value {
+                    kv.value = opentelemetry::Value::String(sanitizer(text.as_str()).into());
+                }
+            }
+        }
+    }
+
+    /// Additionally emit every exported span as one record on the OTel
+    /// Logs signal, via the given logger provider.
+    ///
+    /// The record carries the span name as body, severity mapped from the
+    /// span status, duration and attributes, and is trace-correlated, so
+    /// log-only pipelines still see request outcomes. Requires the `logs`
+    /// feature.
+    #[cfg(feature = "logs")]
+    pub fn with_span_log_mirror(
+        mut self,
+        provider: &opentelemetry_sdk::logs::SdkLoggerProvider,
+    ) -> Self {
+        self.span_log_mirror = Some(std::sync::Arc::new(
+            crate::span_log_mirror::SpanLogMirror::new(provider),
+        ));
+        self
+    }
+
+    /// Apply a [`RedactionPolicy`] to every span and event attribute before
+    /// export. Ignored when the policy has no rules.
+    pub fn with_redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = (!policy.is_empty()).then(|| std::sync::Arc::new(policy));
+        self
+    }
+
+    /// Map exported spans onto a vendor's attribute conventions; see
+    /// [`ConventionsMode`]. Defaults to plain OpenTelemetry semantics.
+    pub fn with_conventions(mut self, conventions: ConventionsMode) -> Self {
+        self.conventions = conventions;
+        self
+    }
+
+    /// Use this propagator for this subscriber's context extraction and
+    /// injection (see [`OpenTelemetrySpanExt::set_parent_from`] and
+    /// [`OpenTelemetrySpanExt::inject_context`]) instead of the process-wide
+    /// global one.
+    ///
+    /// Lets two subscribers in one process speak different header formats —
+    /// e.g. W3C towards the mesh and a vendor format towards a legacy
+    /// system — without touching `opentelemetry::global`.
+    ///
+    /// [`OpenTelemetrySpanExt::set_parent_from`]: crate::OpenTelemetrySpanExt::set_parent_from
+    /// [`OpenTelemetrySpanExt::inject_context`]: crate::OpenTelemetrySpanExt::inject_context
+    pub fn with_propagator<P>(mut self, propagator: P) -> Self
+    where
+        P: opentelemetry::propagation::TextMapPropagator + Send + Sync + 'static,
+    {
+        self.propagator = Some(std::sync::Arc::new(propagator));
+        self
+    }
+
+    /// Cap how many spans each callsite may export: a token bucket per
+    /// span callsite refilling at `per_second`, holding at most `burst`
+    /// tokens.
+    ///
+    /// A hot loop creating the same span millions of times exports at most
+    /// `burst` spans immediately and `per_second` thereafter; the rest are
+    /// suppressed at close. Suppressed spans still hand out valid contexts,
+    /// so children created before the decision remain correctly parented.
+    pub fn with_span_rate_limit(mut self, per_second: f64, burst: u32) -> Self {
+        self.span_rate_limiter = Some(std::sync::Arc::new(SpanRateLimiter::new(
+            per_second, burst,
+        )));
+        self
+    }
+
+    /// Buffer finished spans per trace and only export a trace once its
+    /// local root has closed and `policy` accepted the [`TraceSummary`].
+    ///
+    /// This trades memory for the ability to decide with hindsight, e.g.
+    /// keep every failed trace but none of the successes (see
+    /// [`tail_sampling::errors_only`]). `max_buffered_spans` bounds the
+    /// total buffer; when it overflows, the largest buffered trace is
+    /// exported as-is rather than silently discarded.
+    ///
+    /// Spans that close after their trace was decided (detached tasks)
+    /// follow the recorded verdict.
+    ///
+    /// [`tail_sampling::errors_only`]: crate::tail_sampling::errors_only
+    pub fn with_tail_sampling<F>(mut self, policy: F, max_buffered_spans: usize) -> Self
+    where
+        F: Fn(&TraceSummary) -> bool + Send + Sync + 'static,
+    {
+        self.tail_sampling = Some(std::sync::Arc::new(TailSamplingState::new(
+            std::sync::Arc::new(policy),
+            max_buffered_spans,
+        )));
+        self
+    }
+
+    /// Treat a trace as opted into verbose event capture when its W3C
+    /// `tracestate` carries the given vendor entry, e.g.
+    /// `with_tracestate_debug_flag("myvendor", "debug:1")`.
+    ///
+    /// The flag is evaluated against the extracted remote parent context, so
+    /// a caller can turn on full event detail for a single request without
+    /// redeploying filter configuration; every span under that trace behaves
+    /// as if it had set `otel.capture_events = "debug"`.
+    pub fn with_tracestate_debug_flag(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.tracestate_debug_flag = Some((key.into(), value.into()));
+        self
+    }
+
+    /// Whether the parent context's tracestate carries the configured debug
+    /// flag.
+    fn tracestate_debug_requested(&self, parent_cx: &OtelContext) -> bool {
+        let Some((key, value)) = &self.tracestate_debug_flag else {
+            return false;
+        };
+        if !parent_cx.has_active_span() {
+            return false;
+        }
+        parent_cx
+            .span()
+            .span_context()
+            .trace_state()
+            .get(key)
+            .is_some_and(|v| v == value)
+    }
+
+    /// Attach buffered events only to spans for which the predicate returns
+    /// `true`, given the finished [`SpanBuilder`]. A generalisation of
+    /// [`with_events_on_error_only`](Self::with_events_on_error_only) for
+    /// conditions beyond the span status.
+    pub fn with_events_export_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&SpanBuilder) -> bool + Send + Sync + 'static,
+    {
+        self.events_export_filter = EventsExportFilter::Predicate(std::sync::Arc::new(predicate));
+        self
+    }
+
+    /// Buffer an event on the span, enforcing the configured limit.
+    #[cfg(feature = "events")]
+    fn push_event(&self, data: &mut OtelData, event: otel::Event) {
+        if let Some(stats) = &self.stats {
+            stats.event_recorded();
+        }
+        if self
+            .backpressure
+            .as_ref()
+            .is_some_and(BackpressureSignal::is_overloaded)
+        {
+            data.dropped_event_count += 1;
+            if let Some(stats) = &self.stats {
+                stats.event_dropped();
+            }
+            return;
+        }
+
+        // Apply the per-span limit first, so the budget is only ever
+        // charged for events that actually stay buffered — and refunded
+        // for the ones a rotating ring evicts, keeping a long-lived tail
+        // buffer from monotonically eating the global budget.
+        if let Some(max) = self.max_events_per_span {
+            if data.events.len() >= max {
+                data.dropped_event_count += 1;
+                if let Some(stats) = &self.stats {
+                    stats.event_dropped();
+                }
+                match self.event_overflow_policy {
+                    // Guard against `max == 0`: no slot to rotate into.
+                    EventOverflowPolicy::DropOldest if max > 0 => {
+                        if let Some(evicted) = data.events.pop_front() {
+                            self.refund_event(data, &evicted);
+                        }
+                        // Fall through and buffer the incoming event.
+                    }
+                    _ => return,
+                }
+            }
+        }
+
+        if let Some(budget) = &self.memory_budget {
+            let bytes = estimate_event_bytes(&event);
+            if !budget.try_charge(bytes) {
+                data.dropped_event_count += 1;
+                if let Some(stats) = &self.stats {
+                    stats.event_dropped();
+                }
+                return;
+            }
+            data.buffered_bytes += bytes;
+        }
+        data.events.push_back(event);
+    }
+
+    /// Give an evicted event's estimate back to the global budget.
+    #[cfg(feature = "events")]
+    fn refund_event(&self, data: &mut OtelData, event: &otel::Event) {
+        if let Some(budget) = &self.memory_budget {
+            let bytes = estimate_event_bytes(event).min(data.buffered_bytes);
+            budget.refund(bytes);
+            data.buffered_bytes -= bytes;
+        }
+    }
+
+    /// Resolve the parent OpenTelemetry context for a new span.
+    ///
+    /// An explicit or contextual `tracing` parent wins; otherwise the current
+    /// OpenTelemetry context is used, so remote contexts attached via
+    /// [`OpenTelemetrySpanExt::set_parent`] or `Context::attach` are honored.
+    ///
+    /// [`OpenTelemetrySpanExt::set_parent`]: crate::OpenTelemetrySpanExt::set_parent
+    /// Whether this layer processes work for the given callsite right now.
+    ///
+    /// Deliberately *not* implemented via `Layer::enabled`: returning
+    /// `false` there short-circuits the whole subscriber stack and would
+    /// silence other layers (fmt, files) along with span export. The check
+    /// runs at the top of `on_new_span`/`on_event` instead, so only this
+    /// layer's processing is skipped.
+    fn observing(&self, metadata: &tracing_core::Metadata<'_>) -> bool {
+        if self.disabled || !crate::is_enabled() || crate::is_suppressed() {
+            return false;
+        }
+        match &self.dynamic_targets {
+            Some(targets) => targets.enabled(metadata),
+            None => true,
+        }
+    }
+
+    fn parent_context(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> OtelContext {
+        // Walk towards the root: the nearest ancestor this layer is
+        // tracking provides the context, so spans filtered out (dynamic
+        // targets) don't orphan their descendants.
+        let nearest_tracked = |span: tracing_subscriber::registry::SpanRef<'_, S>| {
+            span.scope().find_map(|ancestor| {
+                let mut extensions = ancestor.extensions_mut();
+                extensions
+                    .get_mut::<OtelDataMap>()
+                    .and_then(|map| map.get_mut(self.layer_id))
+                    .map(|data| self.tracer.sampled_context(data))
+            })
+        };
+        if let Some(parent) = attrs.parent() {
+            ctx.span(parent).and_then(nearest_tracked).unwrap_or_default()
+        } else if attrs.is_contextual() {
+            ctx.lookup_current()
+                .and_then(nearest_tracked)
+                .unwrap_or_else(OtelContext::current)
+        } else {
+            OtelContext::new()
+        }
+    }
+
+    /// Inheritable per-subtree state from the parent span: the
+    /// `otel.capture_events` override, the span-scoped context values, and
+    /// the values of any [configured inherited fields]
+    /// (Self::with_inherited_fields).
+    fn inherited_state(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> InheritedState {
+        let parent = if let Some(parent) = attrs.parent() {
+            ctx.span(parent)
+        } else if attrs.is_contextual() {
+            ctx.lookup_current()
+        } else {
+            None
+        };
+        let Some(parent) = parent else {
+            return InheritedState::default();
+        };
+        let extensions = parent.extensions();
+        extensions
+            .get::<OtelDataMap>()
+            .and_then(|map| {
+                map.entries
+                    .iter()
+                    .find(|(id, _)| *id == self.layer_id)
+                    .map(|(_, data)| InheritedState {
+                        capture_events: data.capture_events,
+                        context_values: data.context_values.clone(),
+                        attributes: if self.inherited_fields.is_empty() {
+                            Vec::new()
+                        } else {
+                            data.builder
+                                .attributes
+                                .as_deref()
+                                .unwrap_or(&[])
+                                .iter()
+                                .filter(|kv| {
+                                    self.inherited_fields
+                                        .iter()
+                                        .any(|field| field == kv.key.as_str())
+                                })
+                                .cloned()
+                                .collect()
+                        },
+                    })
+            })
+            .unwrap_or_default()
+    }
+
+    fn get_propagator(
+        dispatch: &tracing::Dispatch,
+    ) -> Option<std::sync::Arc<dyn opentelemetry::propagation::TextMapPropagator + Send + Sync>>
+    {
+        dispatch
+            .downcast_ref::<OpenTelemetryLayer<S, T>>()
+            .and_then(|layer| layer.propagator.clone())
+    }
+
+    /// The event-capture body of `on_event`; compiled out without the
+    /// `events` feature.
+    #[cfg(feature = "events")]
+    fn capture_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if !self.observing(event.metadata()) {
+            return;
+        }
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+
+        // Events bridged from the `log` crate all share one synthetic
+        // callsite; recover the record's real metadata so level, target and
+        // location attributes describe the log statement, not the shim.
+        #[cfg(feature = "tracing-log")]
+        let normalized_meta = tracing_log::NormalizeEvent::normalized_metadata(event);
+        #[cfg(feature = "tracing-log")]
+        let meta = normalized_meta.as_ref().unwrap_or_else(|| event.metadata());
+        #[cfg(not(feature = "tracing-log"))]
+        let meta = event.metadata();
+        let mut otel_event = otel::Event::new(
+            String::new(),
+            self.clock.now(),
+            Vec::with_capacity(meta.fields().len() + 3),
+            0,
+        );
+        if self.with_level {
+            otel_event
+                .attributes
+                .push(KeyValue::new("level", meta.level().as_str()));
+        }
+        if self.with_target {
+            otel_event
+                .attributes
+                .push(KeyValue::new("target", meta.target().to_string()));
+        }
+        if self.location {
+            if let Some(file) = meta.file() {
+                otel_event
+                    .attributes
+                    .push(KeyValue::new("code.file.path", file.to_string()));
+            }
+            if let Some(line) = meta.line() {
+                otel_event
+                    .attributes
+                    .push(KeyValue::new("code.line.number", line as i64));
+            }
+        }
+        event.record(&mut SpanEventVisitor {
+            event: &mut otel_event,
+            max_value_len: self.max_attribute_value_len,
+        });
+
+        // The event's own span may be filtered out for this layer; attach
+        // to the nearest tracked ancestor instead.
+        for ancestor in span.scope() {
+            let mut extensions = ancestor.extensions_mut();
+            if let Some(data) = extensions
+                .get_mut::<OtelDataMap>()
+                .and_then(|map| map.get_mut(self.layer_id))
+            {
+                if self.error_events_to_status && *meta.level() == tracing_core::Level::ERROR {
+                    data.error_event_message = Some(otel_event.name.to_string());
+                }
+                self.push_event(data, otel_event);
+                break;
+            }
+        }
+    }
+
+    /// [`OpenTelemetrySpanExt::add_event`] entry: routes the event through
+    /// this layer's caps, overflow policy, budget and backpressure, so the
+    /// extension API cannot bypass the memory ceiling.
+    ///
+    /// [`OpenTelemetrySpanExt::add_event`]: crate::OpenTelemetrySpanExt::add_event
+    fn record_span_event(
+        dispatch: &tracing::Dispatch,
+        id: &span::Id,
+        event: opentelemetry::trace::Event,
+    ) {
+        let Some(subscriber) = dispatch.downcast_ref::<S>() else {
+            return;
+        };
+        let Some(span) = subscriber.span(id) else {
+            return;
+        };
+        let Some(layer) = dispatch.downcast_ref::<OpenTelemetryLayer<S, T>>() else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(layer.layer_id))
+        {
+            #[cfg(feature = "events")]
+            layer.push_event(data, event);
+            #[cfg(not(feature = "events"))]
+            data.events.push_back(event);
+        }
+    }
+
+    fn get_context(
+        dispatch: &tracing::Dispatch,
+        id: &span::Id,
+        f: &mut dyn FnMut(&mut OtelData, &dyn PreSampledTracer),
+    ) {
+        let subscriber = dispatch
+            .downcast_ref::<S>()
+            .expect("subscriber should downcast to expected type; this is a bug!");
+        let span = subscriber
+            .span(id)
+            .expect("registry should have a span for the current ID");
+        let layer = dispatch
+            .downcast_ref::<OpenTelemetryLayer<S, T>>()
+            .expect("layer should downcast to expected type; this is a bug!");
+
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(layer.layer_id))
+        {
+            f(data, &layer.tracer);
+        }
+    }
+}
+
+impl<S, T> Layer<S> for OpenTelemetryLayer<S, T>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    T: otel::Tracer + PreSampledTracer + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !self.observing(attrs.metadata()) {
+            return;
+        }
+        let span = ctx.span(id).expect("span must exist in registry; this is a bug");
+        let mut extensions = span.extensions_mut();
+
+        let parent_cx = self.parent_context(attrs, &ctx);
+        let cached = self
+            .callsite_cache
+            .get_or_insert(attrs.metadata(), self.span_namer.as_ref());
+        let mut builder = SpanBuilder::from_name(cached.name.clone())
+            .with_start_time(self.clock.now());
+
+        builder.attributes = Some(crate::pool::take_attr_vec(attrs.fields().len() + 3));
+        if self.with_target {
+            builder
+                .attributes
+                .as_mut()
+                .unwrap()
+                .push(KeyValue::new("target", attrs.metadata().target()));
+        }
+        if self.with_threads {
+            let thread = std::thread::current();
+            let attributes = builder.attributes.as_mut().unwrap();
+            if let Some(name) = thread.name() {
+                attributes.push(KeyValue::new("thread.name", name.to_string()));
+            }
+            attributes.push(KeyValue::new(
+                "thread.id",
+                thread_id_u64(&thread) as i64,
+            ));
+        }
+
+        let (capture_events, drop_span) = if !cached.has_reserved_fields
+            && self.error_fields.is_empty()
+            && self.duplicate_field_policy == DuplicateFieldPolicy::Append
+        {
+            let mut visitor = FastAttributeVisitor::new(
+                &mut builder,
+                self.max_attributes_per_span,
+                self.max_attribute_value_len,
+            );
+            attrs.record(&mut visitor);
+            visitor.finish();
+            (None, None)
+        } else {
+            let mut visitor = SpanAttributeVisitor::new(
+                &mut builder,
+                &self.error_fields,
+                self.max_attributes_per_span,
+                self.max_attribute_value_len,
+                self.duplicate_field_policy,
+            );
+            attrs.record(&mut visitor);
+            visitor.finish()
+        };
+
+        let mut data = OtelData::new(parent_cx, builder);
+        // A span that does not set `otel.capture_events` itself inherits the
+        // override from its parent, so one annotated handler span covers its
+        // whole subtree; span-scoped context values and configured
+        // inherited fields flow down the same way.
+        let inherited = self.inherited_state(attrs, &ctx);
+        data.capture_events = capture_events.or(inherited.capture_events);
+        data.context_values = inherited.context_values;
+        for kv in inherited.attributes {
+            let attributes = data.builder.attributes.get_or_insert_with(Vec::new);
+            // The child's own recording of the key wins.
+            if !attributes.iter().any(|existing| existing.key == kv.key) {
+                attributes.push(kv);
+            }
+        }
+        data.drop_span = drop_span.unwrap_or(false);
+        if self.tracked_inactivity {
+            data.timings = Some(Timings::new(self.per_enter_timings));
+        }
+        if let Some(live_spans) = &self.live_spans {
+            // Heartbeats need real IDs; allocate them now.
+            let _ = self.tracer.sampled_context(&mut data);
+            if let (Some(trace_id), Some(span_id)) = (data.builder.trace_id, data.builder.span_id)
+            {
+                live_spans.insert(
+                    id.into_u64(),
+                    OpenSpan {
+                        name: data.builder.name.to_string(),
+                        target: attrs.metadata().target(),
+                        start: data.builder.start_time.unwrap_or_else(|| self.clock.now()),
+                        trace_id,
+                        span_id,
+                        heartbeats: 0,
+                        budget_flagged: false,
+                    },
+                );
+            }
+        }
+        if extensions.get_mut::<OtelDataMap>().is_none() {
+            extensions.insert(OtelDataMap::default());
+        }
+        if let Some(stats) = &self.stats {
+            stats.span_started();
+        }
+        if let Some(feed) = self.span_feed.as_ref().filter(|f| f.has_subscribers()) {
+            feed.publish(SpanLifecycle::Started {
+                name: data.builder.name.to_string(),
+                target: attrs.metadata().target(),
+                at: data.builder.start_time.unwrap_or_else(|| self.clock.now()),
+            });
+        }
+        let map = extensions
+            .get_mut::<OtelDataMap>()
+            .expect("OtelDataMap was just inserted");
+        map.entries.push((self.layer_id, data));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span must exist in registry; this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(self.layer_id))
+        {
+            let mut visitor = SpanAttributeVisitor::new(
+                &mut data.builder,
+                &self.error_fields,
+                self.max_attributes_per_span,
+                self.max_attribute_value_len,
+                self.duplicate_field_policy,
+            );
+            values.record(&mut visitor);
+            let (capture_events, drop_span) = visitor.finish();
+            if let Some(capture_events) = capture_events {
+                data.capture_events = Some(capture_events);
+            }
+            if let Some(drop_span) = drop_span {
+                data.drop_span = drop_span;
+            }
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span must exist in registry; this is a bug");
+
+        let (follows_span_context, snapshot) = match ctx.span(follows) {
+            Some(follows_span) => {
+                let mut extensions = follows_span.extensions_mut();
+                match extensions
+                    .get_mut::<OtelDataMap>()
+                    .and_then(|map| map.get_mut(self.layer_id))
+                {
+                    Some(data) => {
+                        let cx = self.tracer.sampled_context(data);
+                        let snapshot = if self.follows_from_attribute_snapshot {
+                            data.builder.attributes.clone().unwrap_or_default()
+                        } else {
+                            Vec::new()
+                        };
+                        (Some(cx.span().span_context().clone()), snapshot)
+                    }
+                    None => (None, Vec::new()),
+                }
+            }
+            // The followed span already closed; fall back to the LRU of
+            // recently closed contexts.
+            None => (
+                self.closed_span_lru
+                    .as_ref()
+                    .and_then(|lru| lru.get(follows.into_u64())),
+                Vec::new(),
+            ),
+        };
+        let Some(follows_span_context) = follows_span_context else {
+            return;
+        };
+        let follows_link = otel::Link::new(follows_span_context, snapshot, 0);
+
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(self.layer_id))
+        {
+            data.builder
+                .links
+                .get_or_insert_with(Vec::new)
+                .push(follows_link);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // With the `events` feature off, event capture compiles away; the
+        // layer exports spans only.
+        #[cfg(not(feature = "events"))]
+        {
+            let _ = (event, ctx);
+        }
+        #[cfg(feature = "events")]
+        self.capture_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if !self.tracked_inactivity && !self.profiling_correlation && !self.lifecycle_events {
+            return;
+        }
+        let span = ctx.span(id).expect("span must exist in registry; this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(self.layer_id))
+        {
+            if self.profiling_correlation {
+                let _ = self.tracer.sampled_context(data);
+                if let (Some(trace_id), Some(span_id)) =
+                    (data.builder.trace_id, data.builder.span_id)
+                {
+                    crate::profiling::push(trace_id, span_id);
+                }
+            }
+            if let Some(timings) = data.timings.as_mut() {
+                timings.record_enter();
+            }
+            #[cfg(feature = "events")]
+            if self.lifecycle_events {
+                self.push_event(
+                    data,
+                    otel::Event::new("span.entered", self.clock.now(), Vec::new(), 0),
+                );
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if !self.tracked_inactivity && !self.profiling_correlation && !self.lifecycle_events {
+            return;
+        }
+        let span = ctx.span(id).expect("span must exist in registry; this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.get_mut(self.layer_id))
+        {
+            // Pop only what the matching enter pushed: spans without this
+            // layer's data (filtered, suppressed, created while disabled)
+            // never pushed, and popping here would drop their *parent's*
+            // entry and corrupt the per-thread ID stack.
+            if self.profiling_correlation
+                && data.builder.trace_id.is_some()
+                && data.builder.span_id.is_some()
+            {
+                crate::profiling::pop();
+            }
+            if let Some(timings) = data.timings.as_mut() {
+                timings.record_exit();
+            }
+            #[cfg(feature = "events")]
+            if self.lifecycle_events {
+                self.push_event(
+                    data,
+                    otel::Event::new("span.exited", self.clock.now(), Vec::new(), 0),
+                );
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).expect("span must exist in registry; this is a bug");
+        let mut extensions = span.extensions_mut();
+        let Some(mut data) = extensions
+            .get_mut::<OtelDataMap>()
+            .and_then(|map| map.remove(self.layer_id))
+        else {
+            return;
+        };
+
+        if let Some(budget) = &self.memory_budget {
+            if data.buffered_bytes > 0 {
+                budget.refund(data.buffered_bytes);
+                data.buffered_bytes = 0;
+            }
+        }
+
+        if let Some(live_spans) = &self.live_spans {
+            live_spans.remove(id.into_u64());
+        }
+
+        if let Some(limiter) = &self.span_rate_limiter {
+            if !limiter.allow(span.metadata().callsite()) {
+                if let Some(stats) = &self.stats {
+                    stats.span_suppressed();
+                }
+                if let Some(attributes) = data.builder.attributes.take() {
+                    crate::pool::recycle_attr_vec(attributes);
+                }
+                crate::pool::recycle_event_buf(std::mem::take(&mut data.events));
+                return;
+            }
+        }
+
+        // Stamp the end time before any close-time processing (sanitizing,
+        // redaction, tail buffering) so pipeline latency never inflates the
+        // exported duration.
+        data.builder.end_time = Some(match (data.end_time_override, data.duration_override) {
+            (Some(end), _) => end,
+            (None, Some(duration)) => data
+                .builder
+                .start_time
+                .map(|start| start + duration)
+                .unwrap_or_else(|| self.clock.now()),
+            (None, None) => self.clock.now(),
+        });
+
+        if matches!(data.builder.status, Status::Unset) {
+            if let Some(message) = data.error_event_message.take() {
+                data.builder.status = Status::error(message);
+            }
+        }
+
+        // Second backpressure stage: under sustained overload, only error
+        // spans are worth the exporter's remaining capacity.
+        if !matches!(data.builder.status, Status::Error { .. })
+            && self
+                .backpressure
+                .as_ref()
+                .is_some_and(BackpressureSignal::should_shed_spans)
+        {
+            if let Some(stats) = &self.stats {
+                stats.span_suppressed();
+            }
+            if let Some(attributes) = data.builder.attributes.take() {
+                crate::pool::recycle_attr_vec(attributes);
+            }
+            crate::pool::recycle_event_buf(std::mem::take(&mut data.events));
+            return;
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(span_metrics) = &self.span_metrics {
+            let duration = match (data.builder.start_time, data.builder.end_time) {
+                (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+                _ => std::time::Duration::ZERO,
+            };
+            span_metrics.record(
+                &data.builder.name,
+                data.builder.span_kind.as_ref().unwrap_or(&SpanKind::Internal),
+                matches!(data.builder.status, Status::Error { .. }),
+                duration,
+            );
+        }
+
+        if let Some(adaptive) = &self.adaptive_feedback {
+            adaptive.record_outcome(
+                &data.builder.name,
+                matches!(data.builder.status, Status::Error { .. }),
+            );
+        }
+
+        if let Some(feed) = self.span_feed.as_ref().filter(|f| f.has_subscribers()) {
+            feed.publish(SpanLifecycle::Closed {
+                name: data.builder.name.to_string(),
+                target: span.metadata().target(),
+                trace_id: data.builder.trace_id,
+                span_id: data.builder.span_id,
+                duration: match (data.builder.start_time, data.builder.end_time) {
+                    (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+                    _ => std::time::Duration::ZERO,
+                },
+                is_error: matches!(data.builder.status, Status::Error { .. }),
+            });
+        }
+
+        if let Some(budget) = self.duration_budget {
+            let duration = match (data.builder.start_time, data.builder.end_time) {
+                (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+                _ => std::time::Duration::ZERO,
+            };
+            if duration > budget {
+                let attributes = data.builder.attributes.get_or_insert_with(Vec::new);
+                attributes.push(KeyValue::new("n00.budget_exceeded", true));
+                attributes.push(KeyValue::new(
+                    "n00.budget_ms",
+                    budget.as_secs_f64() * 1_000.0,
+                ));
+                attributes.push(KeyValue::new(
+                    "n00.budget_overrun_ms",
+                    (duration - budget).as_secs_f64() * 1_000.0,
+                ));
+                if let Some(hook) = &self.budget_hook {
+                    hook(&data.builder.name, duration);
+                }
+            }
+        }
+
+        if data.drop_span {
+            // Children keep their parenting: the span's IDs were handed out
+            // when they were created; only the export is suppressed.
+            if let Some(stats) = &self.stats {
+                stats.span_suppressed();
+            }
+            if let Some(attributes) = data.builder.attributes.take() {
+                crate::pool::recycle_attr_vec(attributes);
+            }
+            crate::pool::recycle_event_buf(std::mem::take(&mut data.events));
+            return;
+        }
+
+        if let Some(threshold) = self.child_aggregation_threshold {
+            if let Some(parent) = span.parent() {
+                let span_name = data.builder.name.to_string();
+                let duration = match (data.builder.start_time, data.builder.end_time) {
+                    (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+                    _ => std::time::Duration::ZERO,
+                };
+                let mut parent_extensions = parent.extensions_mut();
+                if let Some(parent_data) = parent_extensions
+                    .get_mut::<OtelDataMap>()
+                    .and_then(|map| map.get_mut(self.layer_id))
+                {
+                    let (count, total) = parent_data
+                        .aggregated_children
+                        .entry(span_name)
+                        .or_insert((0, std::time::Duration::ZERO));
+                    *count += 1;
+                    if *count > threshold {
+                        // Folded: contribute to the aggregate, skip export.
+                        *total += duration;
+                        if let Some(stats) = &self.stats {
+                            stats.span_suppressed();
+                        }
+                        drop(parent_extensions);
+                        if let Some(attributes) = data.builder.attributes.take() {
+                            crate::pool::recycle_attr_vec(attributes);
+                        }
+                        crate::pool::recycle_event_buf(std::mem::take(&mut data.events));
+                        return;
+                    }
+                }
+            }
+        }
+
+        if let Some(lru) = &self.closed_span_lru {
+            let cx = self.tracer.sampled_context(&mut data);
+            let span_context = cx.span().span_context().clone();
+            if span_context.is_valid() {
+                lru.insert(id.into_u64(), span_context);
+            }
+        }
+
+
+        if let Some(timings) = data.timings.take() {
+            let attributes = data.builder.attributes.get_or_insert_with(Vec::new);
+            attributes.push(KeyValue::new("busy_ns", timings.busy as i64));
+            if timings.active_enters.is_some() {
+                attributes.push(KeyValue::new("enter_count", timings.enter_count as i64));
+                attributes.push(KeyValue::new(
+                    "max_concurrency",
+                    timings.max_concurrency as i64,
+                ));
+            } else {
+                attributes.push(KeyValue::new("idle_ns", timings.idle as i64));
+            }
+        }
+
+        if !data.events.is_empty() {
+            // The tracestate flag is evaluated here rather than at creation
+            // because a remote parent is typically attached via `set_parent`
+            // after the span already exists.
+            let export_events = match data.capture_events {
+                Some(capture) => capture,
+                None => {
+                    self.tracestate_debug_requested(&data.parent_cx)
+                        || self.events_export_filter.should_export(&data.builder)
+                }
+            };
+            if export_events {
+                data.builder
+                    .events
+                    .get_or_insert_with(Vec::new)
+                    .extend(data.events.drain(..));
+            } else {
+                let observed = data.events.len() as u64 + data.dropped_event_count;
+                data.events.clear();
+                data.dropped_event_count = 0;
+                data.builder
+                    .attributes
+                    .get_or_insert_with(Vec::new)
+                    .push(KeyValue::new(EVENT_COUNT_ATTR, observed as i64));
+            }
+        }
+        if data.dropped_event_count > 0 {
+            data.builder
+                .attributes
+                .get_or_insert_with(Vec::new)
+                .push(KeyValue::new(
+                    DROPPED_EVENT_COUNT_ATTR,
+                    data.dropped_event_count as i64,
+                ));
+        }
+        // The deque is drained (or cleared) by now; its allocation goes
+        // back to the pool even for exported spans.
+        crate::pool::recycle_event_buf(std::mem::take(&mut data.events));
+
+
+        if self.db_statement_sanitizer.is_some() {
+            if let Some(attributes) = data.builder.attributes.as_mut() {
+                self.sanitize_db_statements(attributes);
+            }
+            for event in data.events.iter_mut().chain(
+                data.builder
+                    .events
+                    .as_mut()
+                    .map(|events| events.iter_mut())
+                    .unwrap_or_default(),
+            ) {
+                self.sanitize_db_statements(&mut event.attributes);
+            }
+        }
+
+        if let Some(redaction) = &self.redaction {
+            if let Some(attributes) = data.builder.attributes.as_mut() {
+                redaction.apply(attributes);
+            }
+            for event in data.events.iter_mut().chain(
+                data.builder
+                    .events
+                    .as_mut()
+                    .map(|events| events.iter_mut())
+                    .unwrap_or_default(),
+            ) {
+                redaction.apply(&mut event.attributes);
+            }
+        }
+
+        for (name, (count, total)) in std::mem::take(&mut data.aggregated_children) {
+            let folded = count.saturating_sub(self.child_aggregation_threshold.unwrap_or(0));
+            if folded == 0 {
+                continue;
+            }
+            data.builder.events.get_or_insert_with(Vec::new).push(otel::Event::new(
+                "aggregated_children",
+                self.clock.now(),
+                vec![
+                    KeyValue::new("span.name", name),
+                    KeyValue::new("folded_count", folded as i64),
+                    KeyValue::new("folded_total_ms", total.as_secs_f64() * 1_000.0),
+                ],
+                0,
+            ));
+        }
+
+        let target = span.metadata().target();
+        conventions::apply(self.conventions, &mut data.builder, target);
+        #[cfg(feature = "logs")]
+        if let Some(mirror) = &self.span_log_mirror {
+            // Make sure IDs exist so the record can be trace-correlated.
+            let _ = self.tracer.sampled_context(&mut data);
+            mirror.emit(&data.builder, target);
+        }
+
+        if let Some(tail_sampling) = &self.tail_sampling {
+            // Allocate IDs now so the span can be buffered under its trace.
+            let _ = self.tracer.sampled_context(&mut data);
+            if let Some(trace_id) = data.builder.trace_id {
+                let is_local_root = !data.parent_cx.has_active_span()
+                    || data.parent_cx.span().span_context().is_remote();
+                let OtelData {
+                    parent_cx, builder, ..
+                } = data;
+                drop(extensions);
+                drop(span);
+                match tail_sampling.offer(
+                    trace_id,
+                    BufferedSpan {
+                        parent_cx,
+                        builder,
+                        target,
+                    },
+                    is_local_root,
+                ) {
+                    TailVerdict::Buffered => {}
+                    TailVerdict::Drop => {
+                        if let Some(stats) = &self.stats {
+                            stats.span_suppressed();
+                        }
+                    }
+                    TailVerdict::Export(spans) => {
+                        for buffered in spans {
+                            if let Some(stats) = &self.stats {
+                                stats.span_exported();
+                            }
+                            let _ = self
+                                .tracer_for(buffered.target)
+                                .build_with_context(buffered.builder, &buffered.parent_cx);
+                        }
+                    }
+                }
+                return;
+            }
+        }
+
+        let OtelData {
+            parent_cx, builder, ..
+        } = data;
+        drop(extensions);
+        drop(span);
+        if let Some(deferred) = &self.deferred_export {
+            match deferred.try_send(BufferedSpan {
+                parent_cx,
+                builder,
+                target,
+            }) {
+                Ok(()) => {
+                    if let Some(stats) = &self.stats {
+                        stats.span_exported();
+                    }
+                }
+                Err(_) => {
+                    if let Some(stats) = &self.stats {
+                        stats.span_suppressed();
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(stats) = &self.stats {
+            stats.span_exported();
+        }
+        let _ = self
+            .tracer_for(target)
+            .build_with_context(builder, &parent_cx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        match id {
+            id if id == TypeId::of::<Self>() => Some(self as *const _ as *const ()),
+            id if id == TypeId::of::<WithContext>() => {
+                Some(&self.get_context as *const _ as *const ())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn thread_id_u64(thread: &std::thread::Thread) -> u64 {
+    // `ThreadId::as_u64` is unstable; parse the Debug form ("ThreadId(N)")
+    // the same way other subscribers do.
+    let id = format!("{:?}", thread.id());
+    id.trim_start_matches("ThreadId(")
+        .trim_end_matches(')')
+        .parse()
+        .unwrap_or(0)
+}
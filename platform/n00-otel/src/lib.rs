@@ -0,0 +1,555 @@
+//! OpenTelemetry bridge for [`tracing`].
+//!
+//! This crate provides [`OpenTelemetryLayer`], a [`tracing_subscriber::Layer`]
+//! that records `tracing` spans and events and exports them as OpenTelemetry
+//! spans when they close. Span data is accumulated in the span's extensions
+//! while it is open and only handed to the configured [`Tracer`] at close
+//! time, which lets the layer apply per-span policies (event limits, status
+//! mapping, and so on) before anything reaches an exporter.
+//!
+//! ```
+//! use tracing_subscriber::layer::SubscriberExt;
+//! use tracing_subscriber::Registry;
+//!
+//! let subscriber = Registry::default().with(n00_otel::layer());
+//! # drop(subscriber);
+//! ```
+//!
+//! # Bridging with the OpenTelemetry API
+//!
+//! The bridge is bidirectional. `tracing` spans expose their OpenTelemetry
+//! context via [`OpenTelemetrySpanExt::context`] for OTel-API code to build
+//! under; and spans created directly through an OTel [`Tracer`] are adopted
+//! as parents of `tracing` spans — a root `tracing` span created while an
+//! OTel context is current (via [`attach_otel_context`],
+//! `Context::attach`, or inside `Context::with_span` scopes) parents to
+//! the API span in that context.
+//!
+//! # Embedded libraries and nested subscribers
+//!
+//! All per-span state lives in the owning registry's extensions and every
+//! extension API resolves spans through the span's own subscriber, never
+//! through globals. An embedded library can therefore run its own registry
+//! with its own `OpenTelemetryLayer` (different tracer, filters, exporter)
+//! inside an application that has a global subscriber: spans created under
+//! the nested dispatch export through the nested pipeline, the host's spans
+//! are untouched, and [`OpenTelemetrySpanExt`] works against whichever
+//! subscriber a given span belongs to.
+//!
+//! [`Tracer`]: opentelemetry::trace::Tracer
+
+#![warn(missing_docs, unreachable_pub)]
+
+mod adaptive;
+pub mod attrs;
+mod chrome_trace;
+mod clock;
+mod backpressure;
+pub mod conventions;
+pub mod db;
+mod dynamic_filter;
+mod feed;
+pub mod ffi;
+pub mod graphql;
+pub mod grpc;
+pub mod hex;
+#[cfg(feature = "http")]
+pub mod http_ext;
+mod id_gen;
+mod intern;
+mod jaeger_remote;
+mod json_attr;
+mod layer;
+pub mod live;
+mod log_sampling;
+pub mod messaging;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod otlp_json;
+mod panic_hook;
+mod pool;
+mod pre_init;
+pub mod profiling;
+pub mod propagation;
+mod rate_limit;
+mod redact;
+mod remote_config;
+pub mod replay;
+pub mod semconv;
+mod stats;
+mod suppress;
+#[cfg(feature = "logs")]
+mod span_log_mirror;
+mod resource;
+#[cfg(feature = "tokio-metrics")]
+mod runtime_metrics;
+mod sanitize;
+mod span_ext;
+pub mod tail_sampling;
+mod tenant_sampler;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod tracer;
+
+use opentelemetry::trace::SpanBuilder;
+use opentelemetry::Context;
+
+pub use adaptive::{AdaptiveSampler, AdaptiveSamplerBuilder};
+pub use backpressure::{BackpressureExporter, BackpressureSignal};
+pub use chrome_trace::ChromeTraceExporter;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use dynamic_filter::DynamicTargets;
+pub use feed::{SpanFeed, SpanLifecycle};
+pub use id_gen::{DeterministicIdGenerator, XrayIdGenerator};
+pub use intern::{intern_key, MAX_INTERNED_KEYS};
+pub use jaeger_remote::{JaegerRemoteSampler, JaegerRemoteSamplerBuilder};
+pub use json_attr::{json_attributes, json_attributes_with_depth, DEFAULT_JSON_DEPTH};
+pub use layer::{layer, DuplicateFieldPolicy, EventOverflowPolicy, OpenTelemetryLayer};
+pub use panic_hook::install_panic_hook;
+pub use live::{LiveSpans, OpenSpan};
+pub use log_sampling::TraceSampledFilter;
+#[cfg(feature = "metrics")]
+pub use metrics::{InstrumentKind, MetricSchema, MetricsLayer};
+pub use otlp_json::{dictionary_decode, dictionary_encode, OtlpHttpJsonExporter, OtlpJsonExporter};
+pub use pre_init::{LazySpan, LazyTracer};
+pub use redact::{RedactionAction, RedactionPolicy};
+pub use remote_config::{serve_filter_config, RemoteConfigServer};
+pub use resource::process_resource;
+pub use sanitize::sanitize_sql;
+pub use stats::{LayerStats, LayerStatsSnapshot};
+pub use suppress::{is_suppressed, suppress_tracing, SuppressionGuard};
+#[cfg(feature = "tokio-metrics")]
+pub use runtime_metrics::{observe_tokio_runtime, TokioRuntimeGauges};
+pub use tail_sampling::TraceSummary;
+pub use tenant_sampler::TenantSampler;
+pub use span_ext::OpenTelemetrySpanExt;
+pub use tracer::{scoped_tracer, PreSampledTracer};
+
+/// Per-span OpenTelemetry state tracked in the span's extensions while the
+/// `tracing` span is open.
+#[derive(Debug)]
+pub struct OtelData {
+    /// The parent OpenTelemetry context at the time the span was created.
+    pub parent_cx: Context,
+
+    /// The builder that accumulates span data until the span closes.
+    pub builder: SpanBuilder,
+
+    /// Events recorded inside the span, moved into `builder.events` at close.
+    ///
+    /// Events are buffered separately from the builder so overflow policies
+    /// can be applied cheaply (see
+    /// [`OpenTelemetryLayer::with_max_events_per_span`]).
+    pub(crate) events: std::collections::VecDeque<opentelemetry::trace::Event>,
+
+    /// Events discarded by the configured [`EventOverflowPolicy`].
+    pub(crate) dropped_event_count: u64,
+
+    /// Per-span override of the layer's event export filter, set via the
+    /// reserved `otel.capture_events` field and inherited by child spans.
+    pub(crate) capture_events: Option<bool>,
+
+    /// Fixed duration to export instead of measured wall time, set via
+    /// [`OpenTelemetrySpanExt::set_duration`].
+    ///
+    /// [`OpenTelemetrySpanExt::set_duration`]: crate::OpenTelemetrySpanExt::set_duration
+    pub(crate) duration_override: Option<std::time::Duration>,
+
+    /// Busy/idle timing state; per layer, since each layer owns its copy of
+    /// the span state.
+    pub(crate) timings: Option<crate::layer::Timings>,
+
+    /// Per-child-name (count, total duration) of children folded into this
+    /// span by the aggregation mode.
+    pub(crate) aggregated_children:
+        std::collections::HashMap<String, (u64, std::time::Duration)>,
+
+    /// Application-defined values scoped to this span's subtree, set via
+    /// [`OpenTelemetrySpanExt::set_context_value`] and shared with children
+    /// copy-on-write.
+    ///
+    /// [`OpenTelemetrySpanExt::set_context_value`]: crate::OpenTelemetrySpanExt::set_context_value
+    pub(crate) context_values:
+        Option<std::sync::Arc<std::collections::HashMap<String, opentelemetry::Value>>>,
+
+    /// Estimated bytes of buffered event data charged against the layer's
+    /// memory budget, refunded when the span closes.
+    pub(crate) buffered_bytes: usize,
+
+    /// Message of the last ERROR-level event seen in the span, applied to
+    /// the status at close when
+    /// [`OpenTelemetryLayer::with_error_events_to_status`] is on.
+    pub(crate) error_event_message: Option<String>,
+
+    /// Suppress export of this span (children stay parented to it), set via
+    /// the reserved `otel.drop` field.
+    pub(crate) drop_span: bool,
+
+    /// Explicit end timestamp, set via [`OpenTelemetrySpanExt::set_end_time`];
+    /// wins over both measured time and a duration override.
+    ///
+    /// [`OpenTelemetrySpanExt::set_end_time`]: crate::OpenTelemetrySpanExt::set_end_time
+    pub(crate) end_time_override: Option<std::time::SystemTime>,
+}
+
+/// Per-span storage keyed by layer instance, so several
+/// [`OpenTelemetryLayer`]s can coexist on one registry without clobbering
+/// each other's state (registry extensions are type-keyed).
+#[derive(Debug, Default)]
+pub(crate) struct OtelDataMap {
+    pub(crate) entries: Vec<(u64, OtelData)>,
+}
+
+impl OtelDataMap {
+    pub(crate) fn get_mut(&mut self, layer_id: u64) -> Option<&mut OtelData> {
+        self.entries
+            .iter_mut()
+            .find(|(id, _)| *id == layer_id)
+            .map(|(_, data)| data)
+    }
+
+    pub(crate) fn remove(&mut self, layer_id: u64) -> Option<OtelData> {
+        let index = self.entries.iter().position(|(id, _)| *id == layer_id)?;
+        Some(self.entries.swap_remove(index).1)
+    }
+}
+
+impl OtelData {
+    pub(crate) fn new(parent_cx: Context, builder: SpanBuilder) -> Self {
+        OtelData {
+            parent_cx,
+            builder,
+            events: crate::pool::take_event_buf(),
+            dropped_event_count: 0,
+            capture_events: None,
+            duration_override: None,
+            timings: None,
+            aggregated_children: std::collections::HashMap::new(),
+            context_values: None,
+            buffered_bytes: 0,
+            error_event_message: None,
+            drop_span: false,
+            end_time_override: None,
+        }
+    }
+}
+
+/// Stable read accessors over [`OtelData`].
+///
+/// # Semver guarantees
+///
+/// The fields of [`OtelData`] mirror the crate's internals and may gain
+/// semantics or move between minor versions. The methods on this trait are
+/// the supported read surface for external consumers: they will keep their
+/// signatures and meaning under semver, independent of how the underlying
+/// state is stored. Code written against `OtelDataAccess` will keep
+/// compiling when the internals change; code that reaches into the fields
+/// may not.
+pub trait OtelDataAccess {
+    /// The trace ID, if one has been allocated yet (see
+    /// [`with_otel_data`] on when allocation happens).
+    fn trace_id(&self) -> Option<opentelemetry::trace::TraceId>;
+
+    /// The span ID, if one has been allocated yet.
+    fn span_id(&self) -> Option<opentelemetry::trace::SpanId>;
+
+    /// The span name as it would currently export (after any `otel.name`
+    /// override).
+    fn name(&self) -> &str;
+
+    /// The span kind, if set via `otel.kind`.
+    fn kind(&self) -> Option<&opentelemetry::trace::SpanKind>;
+
+    /// The span status as currently recorded.
+    fn status(&self) -> &opentelemetry::trace::Status;
+
+    /// The attributes recorded so far, in recording order.
+    fn attributes(&self) -> &[opentelemetry::KeyValue];
+
+    /// The parent OpenTelemetry context the span will be built against.
+    fn parent_context(&self) -> &Context;
+
+    /// How many events the span has buffered (excluding ones already
+    /// discarded by an overflow policy).
+    fn event_count(&self) -> usize;
+}
+
+impl OtelDataAccess for OtelData {
+    fn trace_id(&self) -> Option<opentelemetry::trace::TraceId> {
+        self.builder.trace_id
+    }
+
+    fn span_id(&self) -> Option<opentelemetry::trace::SpanId> {
+        self.builder.span_id
+    }
+
+    fn name(&self) -> &str {
+        &self.builder.name
+    }
+
+    fn kind(&self) -> Option<&opentelemetry::trace::SpanKind> {
+        self.builder.span_kind.as_ref()
+    }
+
+    fn status(&self) -> &opentelemetry::trace::Status {
+        &self.builder.status
+    }
+
+    fn attributes(&self) -> &[opentelemetry::KeyValue] {
+        self.builder.attributes.as_deref().unwrap_or(&[])
+    }
+
+    fn parent_context(&self) -> &Context {
+        &self.parent_cx
+    }
+
+    fn event_count(&self) -> usize {
+        self.events.len()
+    }
+}
+
+static GLOBALLY_ENABLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+
+/// Enable or disable every [`OpenTelemetryLayer`] in the process at
+/// runtime.
+///
+/// The switch gates only the OTel layers' own processing — the check is a
+/// single relaxed-ordering atomic load at the top of each layer's
+/// `on_new_span`/`on_event` — so while off, span export stops but fmt and
+/// other layers on the same registry keep logging normally. Spans open
+/// across the flip may export incomplete.
+pub fn set_enabled(enabled: bool) {
+    GLOBALLY_ENABLED.store(enabled, std::sync::atomic::Ordering::Release);
+}
+
+/// Whether the layers are globally enabled (the default).
+pub fn is_enabled() -> bool {
+    GLOBALLY_ENABLED.load(std::sync::atomic::Ordering::Acquire)
+}
+
+/// The trace and span ID of the current span, read from the per-thread
+/// cache the layer maintains on enter/exit.
+///
+/// This is the hook for injecting trace correlation into *other* layers'
+/// output — a custom `fmt::FormatEvent` prefixing every log line. Because
+/// formatters run per line, the lookup is deliberately just a thread-local
+/// read: no extensions lock is taken and no ID allocation or sampling is
+/// forced. The cache requires the layer to run with
+/// [`with_profiling_correlation`] enabled (IDs are then allocated once,
+/// at span enter).
+///
+/// ```
+/// # fn write_prefix(out: &mut String) {
+/// if let Some((trace_id, span_id)) = n00_otel::current_trace_ids() {
+///     out.push_str(&format!(
+///         "[{} {}] ",
+///         n00_otel::hex::trace_id_hex(trace_id),
+///         n00_otel::hex::span_id_hex(span_id),
+///     ));
+/// }
+/// # }
+/// ```
+///
+/// [`with_profiling_correlation`]: OpenTelemetryLayer::with_profiling_correlation
+pub fn current_trace_ids() -> Option<(opentelemetry::trace::TraceId, opentelemetry::trace::SpanId)>
+{
+    profiling::current_thread_trace_ids()
+}
+
+/// Attach an OpenTelemetry context to the current thread, returning a
+/// guard that detaches on drop.
+///
+/// For code that is not running inside any `tracing` span — worker threads
+/// draining a queue, callbacks from C libraries — attaching the context
+/// makes it the parent of every root span the layer creates on this thread
+/// while the guard lives:
+///
+/// ```
+/// use n00_otel::OpenTelemetrySpanExt;
+///
+/// # fn handoff(span: &tracing::Span) {
+/// let cx = span.context();
+/// std::thread::scope(|scope| {
+///     scope.spawn(move || {
+///         let _guard = n00_otel::attach_otel_context(cx);
+///         // Root spans created here join the trace.
+///         tracing::info_span!("worker_step").in_scope(|| {});
+///     });
+/// });
+/// # }
+/// ```
+///
+/// The guard must stay on its thread (it is deliberately `!Send`); for
+/// async code, prefer passing the context and
+/// [`OpenTelemetrySpanExt::set_parent`].
+pub fn attach_otel_context(cx: Context) -> opentelemetry::ContextGuard {
+    cx.attach()
+}
+
+/// Read the [`OtelData`] of a span from another layer on the same registry.
+///
+/// This is the integration point for third-party layers that want to see the
+/// pending OpenTelemetry state of a span (IDs, pending attributes, parent
+/// context) from their own callbacks. It is a plain extensions read: no
+/// sampling is forced and no IDs are allocated, so peeking at a span the
+/// sampler may yet drop costs nothing. IDs are present once something —
+/// a child span, [`OpenTelemetrySpanExt::context`], the layer itself —
+/// needed them.
+///
+/// Returns `None` if no [`OpenTelemetryLayer`] is installed (or the span
+/// already closed).
+///
+/// ```
+/// use tracing_subscriber::layer::Context;
+/// use tracing_subscriber::registry::LookupSpan;
+///
+/// fn my_layer_on_event<S>(ctx: &Context<'_, S>, event: &tracing::Event<'_>)
+/// where
+///     S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+/// {
+///     if let Some(span) = ctx.event_span(event) {
+///         if let Some(trace_id) = n00_otel::with_otel_data(&span, |data| data.builder.trace_id) {
+///             let _ = trace_id;
+///         }
+///     }
+/// }
+/// ```
+/// With several [`OpenTelemetryLayer`]s installed, this reads the data of
+/// the one registered first.
+pub fn with_otel_data<R, F, Ret>(
+    span: &tracing_subscriber::registry::SpanRef<'_, R>,
+    f: F,
+) -> Option<Ret>
+where
+    R: for<'l> tracing_subscriber::registry::LookupSpan<'l>,
+    F: FnOnce(&OtelData) -> Ret,
+{
+    let extensions = span.extensions();
+    extensions
+        .get::<OtelDataMap>()
+        .and_then(|map| map.entries.first())
+        .map(|(_, data)| f(data))
+}
+
+/// Append attributes to the pending OpenTelemetry span from another layer.
+///
+/// The write-side companion of [`with_otel_data`]: enrichment layers
+/// (request metadata, tenant tags, build info) can attach attributes to the
+/// span being assembled without declaring `tracing` fields up front and
+/// without going through a [`tracing::Span`] handle. Appending never
+/// deduplicates; the last writer wins downstream, per OpenTelemetry
+/// semantics.
+///
+/// Does nothing (and returns `false`) if no [`OpenTelemetryLayer`] is
+/// installed or the span already closed.
+/// With several [`OpenTelemetryLayer`]s installed, the attributes are
+/// appended to every layer's pending span.
+pub fn append_otel_attributes<R>(
+    span: &tracing_subscriber::registry::SpanRef<'_, R>,
+    attributes: impl IntoIterator<Item = opentelemetry::KeyValue>,
+) -> bool
+where
+    R: for<'l> tracing_subscriber::registry::LookupSpan<'l>,
+{
+    let attributes: Vec<opentelemetry::KeyValue> = attributes.into_iter().collect();
+    let mut extensions = span.extensions_mut();
+    match extensions.get_mut::<OtelDataMap>() {
+        Some(map) if !map.entries.is_empty() => {
+            for (_, data) in &mut map.entries {
+                data.builder
+                    .attributes
+                    .get_or_insert_with(Vec::new)
+                    .extend(attributes.iter().cloned());
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Create a `Producer`-kind span for publishing to a messaging
+/// destination, named and attributed per the messaging semantic
+/// conventions (`{destination} publish`, `messaging.system`,
+/// `messaging.destination.name`, `messaging.operation.type`).
+///
+/// ```
+/// let span = n00_otel::producer_span!("kafka", "orders");
+/// span.in_scope(|| { /* publish */ });
+/// ```
+#[macro_export]
+macro_rules! producer_span {
+    ($system:expr, $destination:expr) => {
+        tracing::info_span!(
+            "publish",
+            otel.name = %format!("{} publish", $destination),
+            otel.kind = "producer",
+            messaging.system = $system,
+            messaging.destination.name = $destination,
+            messaging.operation.type = "publish",
+        )
+    };
+}
+
+/// Create a `Consumer`-kind span for processing a received message,
+/// counterpart of [`producer_span!`]. `$operation` is typically
+/// `"receive"` or `"process"`.
+#[macro_export]
+macro_rules! consumer_span {
+    ($system:expr, $destination:expr, $operation:expr) => {
+        tracing::info_span!(
+            "consume",
+            otel.name = %format!("{} {}", $destination, $operation),
+            otel.kind = "consumer",
+            messaging.system = $system,
+            messaging.destination.name = $destination,
+            messaging.operation.type = $operation,
+        )
+    };
+}
+
+/// Record an attribute on a span only when its trace is sampled, skipping
+/// the value expression entirely otherwise.
+///
+/// Serializing a request body or formatting a large structure for a span
+/// that the sampler will drop is pure waste; the value expression here is
+/// only evaluated after the sampling decision says the data will be kept.
+///
+/// ```
+/// # use n00_otel::sampled_field;
+/// # fn expensive_debug_dump() -> String { String::new() }
+/// let span = tracing::info_span!("handler");
+/// sampled_field!(span, "request.dump", expensive_debug_dump());
+/// ```
+#[macro_export]
+macro_rules! sampled_field {
+    ($span:expr, $key:expr, $value:expr) => {{
+        let span = &$span;
+        if $crate::OpenTelemetrySpanExt::is_sampled(span) {
+            $crate::OpenTelemetrySpanExt::set_attribute(span, $key, $value);
+        }
+    }};
+}
+
+pub(crate) mod time {
+    use std::time::SystemTime;
+
+    /// Monotonic clock for busy/idle accounting; `std::time::Instant`
+    /// panics on `wasm32-unknown-unknown`, so the browser build uses
+    /// `web-time`'s drop-in replacement.
+    #[cfg(not(all(target_arch = "wasm32", not(target_os = "wasi"))))]
+    pub(crate) use std::time::Instant;
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    pub(crate) use web_time::Instant;
+
+    #[cfg(not(all(target_arch = "wasm32", not(target_os = "wasi"))))]
+    pub(crate) fn now() -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// `SystemTime::now` is unavailable in the browser; derive wall time
+    /// from `Date.now()` instead.
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    pub(crate) fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(js_sys::Date::now() as u64)
+    }
+}
@@ -0,0 +1,127 @@
+//! Registry of currently open spans, feeding heartbeats, watchdogs and
+//! live introspection.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use opentelemetry::trace::{SpanId, TraceId};
+
+/// One currently open span.
+#[derive(Clone, Debug)]
+pub struct OpenSpan {
+    /// Span name as it would currently export.
+    pub name: String,
+    /// The span's `tracing` target.
+    pub target: &'static str,
+    /// When the span started.
+    pub start: SystemTime,
+    /// Allocated trace ID.
+    pub trace_id: TraceId,
+    /// Allocated span ID.
+    pub span_id: SpanId,
+    /// How many heartbeat exports this span has had.
+    pub(crate) heartbeats: u64,
+    /// Whether the stall watchdog already flagged this span.
+    pub(crate) budget_flagged: bool,
+}
+
+/// A shareable handle listing the spans currently open on a layer.
+///
+/// Create one, install it with
+/// [`OpenTelemetryLayer::with_live_spans`], and keep a clone wherever
+/// introspection is needed — a debug endpoint, a SIGQUIT handler, a REPL:
+///
+/// ```
+/// let live = n00_otel::LiveSpans::new();
+/// let layer = n00_otel::layer::<tracing_subscriber::Registry>()
+///     .with_live_spans(live.clone());
+/// // later: enumerate what is in flight
+/// for span in live.open_spans() {
+///     eprintln!("{} open since {:?}", span.name, span.start);
+/// }
+/// # drop(layer);
+/// ```
+///
+/// [`OpenTelemetryLayer::with_live_spans`]: crate::OpenTelemetryLayer::with_live_spans
+#[derive(Clone, Debug, Default)]
+pub struct LiveSpans {
+    pub(crate) registry: std::sync::Arc<LiveSpanRegistry>,
+}
+
+impl LiveSpans {
+    /// A new, empty handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All spans currently open, in no particular order.
+    pub fn open_spans(&self) -> Vec<OpenSpan> {
+        self.registry.snapshot()
+    }
+
+    /// How many spans are currently open.
+    pub fn open_count(&self) -> usize {
+        self.registry.entries.lock().unwrap().len()
+    }
+}
+
+/// The shared table of open spans, keyed by `tracing` span ID.
+#[derive(Debug, Default)]
+pub(crate) struct LiveSpanRegistry {
+    entries: Mutex<HashMap<u64, OpenSpan>>,
+}
+
+impl LiveSpanRegistry {
+    pub(crate) fn insert(&self, id: u64, span: OpenSpan) {
+        self.entries.lock().unwrap().insert(id, span);
+    }
+
+    pub(crate) fn remove(&self, id: u64) {
+        self.entries.lock().unwrap().remove(&id);
+    }
+
+    /// Snapshot of all open spans.
+    pub(crate) fn snapshot(&self) -> Vec<OpenSpan> {
+        self.entries.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Open spans older than `budget` that have not been flagged yet,
+    /// marking them flagged — each stalled span is reported once.
+    pub(crate) fn due_for_budget_flag(&self, budget: std::time::Duration) -> Vec<OpenSpan> {
+        let now = SystemTime::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .values_mut()
+            .filter(|span| {
+                !span.budget_flagged
+                    && now
+                        .duration_since(span.start)
+                        .map(|age| age >= budget)
+                        .unwrap_or(false)
+            })
+            .map(|span| {
+                span.budget_flagged = true;
+                span.clone()
+            })
+            .collect()
+    }
+
+    /// Open spans older than `min_age`, bumping their heartbeat counters.
+    pub(crate) fn due_for_heartbeat(&self, min_age: std::time::Duration) -> Vec<OpenSpan> {
+        let now = SystemTime::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .values_mut()
+            .filter(|span| {
+                now.duration_since(span.start)
+                    .map(|age| age >= min_age)
+                    .unwrap_or(false)
+            })
+            .map(|span| {
+                span.heartbeats += 1;
+                span.clone()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+//! Trace-aware sampling of log events for other layers.
+//!
+//! When a trace is sampled, its log events are the cheap part and the most
+//! valuable context; when it isn't, most of those events are noise. This
+//! [`Filter`] keeps every event that occurs inside a sampled trace and
+//! rate-limits the rest to one in `keep_one_in`, so e.g. a fmt layer logs
+//! everything trace-correlated and only a trickle otherwise:
+//!
+//! ```
+//! use tracing_subscriber::layer::{Layer as _, SubscriberExt};
+//! use tracing_subscriber::Registry;
+//!
+//! # let fmt_like_layer = n00_otel::layer::<Registry>();
+//! let subscriber = Registry::default().with(
+//!     fmt_like_layer.with_filter(n00_otel::TraceSampledFilter::new(100)),
+//! );
+//! # drop(subscriber);
+//! ```
+//!
+//! Spans always pass the filter — dropping spans would break the structure
+//! other layers rely on; only events are sampled.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use opentelemetry::trace::{SamplingDecision, TraceContextExt};
+use tracing_core::{Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::OtelDataMap;
+
+/// A per-layer [`Filter`] keeping events from sampled traces and a
+/// configurable fraction of the rest.
+#[derive(Debug)]
+pub struct TraceSampledFilter {
+    keep_one_in: u64,
+    counter: AtomicU64,
+}
+
+impl TraceSampledFilter {
+    /// Keep all events in sampled traces and one in `keep_one_in` others
+    /// (`0` drops all unsampled events).
+    pub fn new(keep_one_in: u64) -> Self {
+        TraceSampledFilter {
+            keep_one_in,
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    fn fallback_keep(&self) -> bool {
+        if self.keep_one_in == 0 {
+            return false;
+        }
+        self.counter.fetch_add(1, Ordering::Relaxed).is_multiple_of(self.keep_one_in)
+    }
+}
+
+impl<S> Filter<S> for TraceSampledFilter
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: &Context<'_, S>) -> bool {
+        if metadata.is_span() {
+            return true;
+        }
+        let sampled = ctx.lookup_current().and_then(|span| {
+            let extensions = span.extensions();
+            extensions.get::<OtelDataMap>().and_then(|map| {
+                map.entries.first().and_then(|(_, data)| {
+                    // A local decision wins; otherwise a sampled (remote or
+                    // synthesized) parent context implies the trace is kept.
+                    data.builder
+                        .sampling_result
+                        .as_ref()
+                        .map(|r| r.decision == SamplingDecision::RecordAndSample)
+                        .or_else(|| {
+                            data.parent_cx.has_active_span().then(|| {
+                                data.parent_cx.span().span_context().is_sampled()
+                            })
+                        })
+                })
+            })
+        });
+        match sampled {
+            Some(true) => true,
+            // Unsampled trace, or no decision/span context at all: trickle.
+            Some(false) | None => self.fallback_keep(),
+        }
+    }
+}
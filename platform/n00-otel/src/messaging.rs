@@ -0,0 +1,232 @@
+//! Context propagation carriers for message queues.
+//!
+//! HTTP propagation has `HeaderMap`; queues each have their own header
+//! shape. These adapters implement [`Injector`]/[`Extractor`] over the
+//! common ones so any [`TextMapPropagator`] — W3C, B3, X-Ray — works
+//! unchanged with Kafka record headers, AMQP tables and NATS header maps.
+//!
+//! ```
+//! use n00_otel::messaging::KafkaHeaders;
+//! use opentelemetry::propagation::TextMapPropagator;
+//! use opentelemetry_sdk::propagation::TraceContextPropagator;
+//!
+//! let propagator = TraceContextPropagator::new();
+//! let mut headers: Vec<(String, Vec<u8>)> = Vec::new();
+//! propagator.inject_context(
+//!     &opentelemetry::Context::new(),
+//!     &mut KafkaHeaders::new(&mut headers),
+//! );
+//! let cx = propagator.extract(&KafkaHeaders::new(&mut headers));
+//! # drop(cx);
+//! ```
+//!
+//! [`TextMapPropagator`]: opentelemetry::propagation::TextMapPropagator
+
+use std::collections::HashMap;
+
+use opentelemetry::propagation::{Extractor, Injector};
+
+/// Adapter over Kafka record headers (`Vec<(key, bytes)>`).
+///
+/// Kafka header values are raw bytes; extraction only sees values that are
+/// valid UTF-8 (trace context always is). Injection replaces an existing
+/// header of the same key.
+pub struct KafkaHeaders<'a> {
+    headers: &'a mut Vec<(String, Vec<u8>)>,
+    /// Owned UTF-8 copies handed out by `Extractor::get`.
+    decoded: HashMap<String, String>,
+}
+
+impl<'a> KafkaHeaders<'a> {
+    /// Wrap a mutable Kafka header list.
+    pub fn new(headers: &'a mut Vec<(String, Vec<u8>)>) -> Self {
+        let decoded = headers
+            .iter()
+            .filter_map(|(key, value)| {
+                Some((key.clone(), String::from_utf8(value.clone()).ok()?))
+            })
+            .collect();
+        KafkaHeaders { headers, decoded }
+    }
+}
+
+impl Injector for KafkaHeaders<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.headers.retain(|(existing, _)| existing != key);
+        self.headers.push((key.to_string(), value.into_bytes()));
+    }
+}
+
+impl Extractor for KafkaHeaders<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.decoded.get(key).map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.decoded.keys().map(String::as_str).collect()
+    }
+}
+
+/// Adapter over an AMQP table (string-valued application headers).
+///
+/// AMQP 0-9-1 header tables are maps; only string values participate in
+/// propagation. A plain `HashMap<String, String>` also works directly with
+/// the propagator API — this type exists for symmetry and to document the
+/// convention.
+pub struct AmqpTable<'a>(pub &'a mut HashMap<String, String>);
+
+impl Injector for AmqpTable<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+impl Extractor for AmqpTable<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+/// Adapter over NATS-style multi-value header maps.
+///
+/// Injection replaces the value list; extraction reads the first value,
+/// per NATS header semantics.
+pub struct NatsHeaders<'a>(pub &'a mut HashMap<String, Vec<String>>);
+
+impl Injector for NatsHeaders<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), vec![value]);
+    }
+}
+
+impl Extractor for NatsHeaders<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key)?.first().map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+/// Typed builder for messaging span attributes beyond what the span macros
+/// cover (message keys, batch sizes, consumer groups).
+#[derive(Clone, Debug, Default)]
+pub struct MessagingAttributes {
+    system: Option<String>,
+    destination: Option<String>,
+    operation: Option<String>,
+    message_id: Option<String>,
+    batch_size: Option<i64>,
+}
+
+impl MessagingAttributes {
+    /// Attributes for an operation against the given messaging system and
+    /// destination.
+    pub fn operation(
+        system: impl Into<String>,
+        destination: impl Into<String>,
+        operation: impl Into<String>,
+    ) -> Self {
+        MessagingAttributes {
+            system: Some(system.into()),
+            destination: Some(destination.into()),
+            operation: Some(operation.into()),
+            ..Default::default()
+        }
+    }
+
+    /// The message identifier.
+    pub fn with_message_id(mut self, message_id: impl Into<String>) -> Self {
+        self.message_id = Some(message_id.into());
+        self
+    }
+
+    /// The number of messages in the batch.
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    /// The attribute list, following the messaging semantic conventions.
+    pub fn into_attributes(self) -> Vec<opentelemetry::KeyValue> {
+        use crate::semconv;
+        let mut attrs = Vec::new();
+        if let Some(system) = self.system {
+            attrs.push(opentelemetry::KeyValue::new(semconv::MESSAGING_SYSTEM, system));
+        }
+        if let Some(destination) = self.destination {
+            attrs.push(opentelemetry::KeyValue::new(
+                semconv::MESSAGING_DESTINATION_NAME,
+                destination,
+            ));
+        }
+        if let Some(operation) = self.operation {
+            attrs.push(opentelemetry::KeyValue::new(
+                semconv::MESSAGING_OPERATION_TYPE,
+                operation,
+            ));
+        }
+        if let Some(message_id) = self.message_id {
+            attrs.push(opentelemetry::KeyValue::new("messaging.message.id", message_id));
+        }
+        if let Some(batch_size) = self.batch_size {
+            attrs.push(opentelemetry::KeyValue::new(
+                "messaging.batch.message_count",
+                batch_size,
+            ));
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+    fn sample_context() -> opentelemetry::Context {
+        opentelemetry::Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_bytes(0xfeed_u128.to_be_bytes()),
+            SpanId::from_bytes(0xbeef_u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            true,
+            Default::default(),
+        ))
+    }
+
+    #[test]
+    fn kafka_headers_round_trip() {
+        let propagator = TraceContextPropagator::new();
+        let mut headers: Vec<(String, Vec<u8>)> = vec![("payload-id".into(), vec![0xff])];
+        propagator.inject_context(&sample_context(), &mut KafkaHeaders::new(&mut headers));
+
+        let extracted = propagator.extract(&KafkaHeaders::new(&mut headers));
+        let sc = extracted.span().span_context().clone();
+        assert_eq!(sc.trace_id(), TraceId::from_bytes(0xfeed_u128.to_be_bytes()));
+        assert!(sc.is_sampled());
+        // Unrelated binary headers survive untouched.
+        assert!(headers.iter().any(|(k, v)| k == "payload-id" && v == &[0xff]));
+    }
+
+    #[test]
+    fn amqp_and_nats_round_trip() {
+        let propagator = TraceContextPropagator::new();
+
+        let mut table = HashMap::new();
+        propagator.inject_context(&sample_context(), &mut AmqpTable(&mut table));
+        let cx = propagator.extract(&AmqpTable(&mut table));
+        assert!(cx.span().span_context().is_valid());
+
+        let mut nats = HashMap::new();
+        propagator.inject_context(&sample_context(), &mut NatsHeaders(&mut nats));
+        let cx = propagator.extract(&NatsHeaders(&mut nats));
+        assert!(cx.span().span_context().is_valid());
+    }
+}
@@ -0,0 +1,466 @@
+//! A layer exporting metrics from conventionally named event fields.
+//!
+//! Events carrying fields with the reserved prefixes become metric
+//! recordings, keyed by the rest of the field name:
+//!
+//! * `monotonic_counter.*` — a [`Counter`] add
+//! * `counter.*` — an [`UpDownCounter`] add (may go negative)
+//! * `histogram.*` — a [`Histogram`] recording
+//! * `gauge.*` — a [`Gauge`] record (last value wins)
+//!
+//! ```
+//! # use opentelemetry::metrics::MeterProvider as _;
+//! # let provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder().build();
+//! use tracing_subscriber::layer::SubscriberExt;
+//! use tracing_subscriber::Registry;
+//!
+//! let subscriber = Registry::default()
+//!     .with(n00_otel::MetricsLayer::new(provider.meter("my-service")));
+//! tracing::subscriber::with_default(subscriber, || {
+//!     tracing::info!(monotonic_counter.requests_total = 1);
+//!     tracing::info!(histogram.request_duration_ms = 23.7);
+//!     tracing::info!(counter.active_connections = -1);
+//!     tracing::info!(gauge.queue_depth = 42);
+//! });
+//! ```
+//!
+//! Enabled with the `metrics` cargo feature.
+//!
+//! [`Counter`]: opentelemetry::metrics::Counter
+//! [`UpDownCounter`]: opentelemetry::metrics::UpDownCounter
+//! [`Histogram`]: opentelemetry::metrics::Histogram
+//! [`Gauge`]: opentelemetry::metrics::Gauge
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter, ObservableGauge, UpDownCounter};
+use tracing_core::{field, Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// The instrument kind of a declared metric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstrumentKind {
+    /// Monotonic counter (`monotonic_counter.*`).
+    Counter,
+    /// Up-down counter (`counter.*`).
+    UpDownCounter,
+    /// Histogram (`histogram.*`).
+    Histogram,
+    /// Gauge (`gauge.*`).
+    Gauge,
+}
+
+#[derive(Clone, Debug)]
+struct SchemaEntry {
+    kind: InstrumentKind,
+    unit: Option<String>,
+    description: Option<String>,
+}
+
+/// An up-front declaration of the metrics a service emits.
+///
+/// Installed via [`MetricsLayer::with_schema`], the schema becomes the
+/// contract: declared metrics get their unit and description attached at
+/// instrument creation, while recordings of undeclared names or with the
+/// wrong instrument kind are dropped and counted in
+/// [`violation_count`](Self::violation_count) — a typo'd metric name
+/// surfaces as a violation counter instead of a phantom series.
+#[derive(Clone, Debug, Default)]
+pub struct MetricSchema {
+    entries: std::sync::Arc<Mutex<HashMap<String, SchemaEntry>>>,
+    violations: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl MetricSchema {
+    /// An empty schema; add declarations with [`declare`](Self::declare).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a metric with its kind and optional unit/description.
+    pub fn declare(
+        self,
+        kind: InstrumentKind,
+        name: impl Into<String>,
+        unit: Option<&str>,
+        description: Option<&str>,
+    ) -> Self {
+        self.entries.lock().unwrap().insert(
+            name.into(),
+            SchemaEntry {
+                kind,
+                unit: unit.map(str::to_string),
+                description: description.map(str::to_string),
+            },
+        );
+        self
+    }
+
+    /// How many recordings were rejected for violating the schema.
+    pub fn violation_count(&self) -> u64 {
+        self.violations.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// `Ok` with the declaration when the recording matches, `Err` when it
+    /// violates the schema (counted).
+    fn check(&self, name: &str, kind: InstrumentKind) -> Result<Option<SchemaEntry>, ()> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(name) {
+            Some(entry) if entry.kind == kind => Ok(Some(entry.clone())),
+            Some(_) | None => {
+                self.violations
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Err(())
+            }
+        }
+    }
+}
+
+const MONOTONIC_COUNTER_PREFIX: &str = "monotonic_counter.";
+const COUNTER_PREFIX: &str = "counter.";
+const HISTOGRAM_PREFIX: &str = "histogram.";
+const GAUGE_PREFIX: &str = "gauge.";
+
+/// A [`Layer`] turning conventionally named event fields into metrics.
+///
+/// Non-metric primitive fields of the same event become the recording's
+/// attributes: `info!(monotonic_counter.requests_total = 1, region = "eu")`
+/// records with `region = "eu"`.
+pub struct MetricsLayer {
+    meter: Meter,
+    instruments: Mutex<Instruments>,
+    schema: Option<MetricSchema>,
+    cardinality_limit: Option<usize>,
+    seen_attribute_sets: Mutex<HashMap<String, std::collections::HashSet<u64>>>,
+    /// Keeps closure-backed gauges (and their callbacks) registered for
+    /// the layer's lifetime.
+    observable_gauges: Mutex<Vec<ObservableGauge<f64>>>,
+}
+
+#[derive(Default)]
+struct Instruments {
+    counters: HashMap<String, Counter<f64>>,
+    up_down_counters: HashMap<String, UpDownCounter<f64>>,
+    histograms: HashMap<String, Histogram<f64>>,
+    gauges: HashMap<String, Gauge<f64>>,
+}
+
+impl MetricsLayer {
+    /// A metrics layer recording through the given meter.
+    pub fn new(meter: Meter) -> Self {
+        MetricsLayer {
+            meter,
+            instruments: Mutex::new(Instruments::default()),
+            schema: None,
+            cardinality_limit: None,
+            seen_attribute_sets: Mutex::new(HashMap::new()),
+            observable_gauges: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Register an observable gauge backed by a closure, polled by the
+    /// meter provider's reader on every collection.
+    ///
+    /// The event-field prefixes cover push-style recordings; sampled state
+    /// (pool sizes, queue depths, cache occupancy) is pull-style, and this
+    /// keeps it on the same bridge instead of forcing callers down to the
+    /// raw [`Meter`] API. The instrument stays registered for the layer's
+    /// lifetime.
+    pub fn register_observable_gauge<F>(
+        &self,
+        name: impl Into<std::borrow::Cow<'static, str>>,
+        unit: Option<&str>,
+        description: Option<&str>,
+        callback: F,
+    ) where
+        F: Fn() -> f64 + Send + Sync + 'static,
+    {
+        let mut builder = self.meter.f64_observable_gauge(name.into());
+        if let Some(unit) = unit {
+            builder = builder.with_unit(unit.to_string());
+        }
+        if let Some(description) = description {
+            builder = builder.with_description(description.to_string());
+        }
+        let gauge = builder
+            .with_callback(move |observer| observer.observe(callback(), &[]))
+            .build();
+        self.observable_gauges.lock().unwrap().push(gauge);
+    }
+
+    /// Cap the number of distinct attribute sets per metric.
+    ///
+    /// A `user_id` attribute sneaking into a counter creates a time series
+    /// per user and melts the metrics backend. Beyond `limit` distinct
+    /// sets, further recordings keep their value but collapse onto the
+    /// spec's `otel.metric.overflow = true` attribute set.
+    pub fn with_cardinality_limit(mut self, limit: usize) -> Self {
+        self.cardinality_limit = Some(limit);
+        self
+    }
+
+    /// Apply the cardinality guard, possibly replacing the attribute set.
+    fn guard_attributes(
+        &self,
+        name: &str,
+        attributes: Vec<opentelemetry::KeyValue>,
+    ) -> Vec<opentelemetry::KeyValue> {
+        let Some(limit) = self.cardinality_limit else {
+            return attributes;
+        };
+        use std::hash::{Hash, Hasher};
+        let mut sorted: Vec<(&str, String)> = attributes
+            .iter()
+            .map(|kv| (kv.key.as_str(), kv.value.to_string()))
+            .collect();
+        sorted.sort();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        sorted.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        let mut seen = self.seen_attribute_sets.lock().unwrap();
+        let sets = seen.entry(name.to_string()).or_default();
+        if sets.contains(&fingerprint) {
+            return attributes;
+        }
+        if sets.len() < limit {
+            sets.insert(fingerprint);
+            return attributes;
+        }
+        vec![opentelemetry::KeyValue::new("otel.metric.overflow", true)]
+    }
+
+    /// Validate recordings against a declared [`MetricSchema`]; see the
+    /// schema type for semantics.
+    pub fn with_schema(mut self, schema: MetricSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// The declared unit/description for a metric, or `Err` if the schema
+    /// rejects the recording.
+    fn schema_entry(
+        &self,
+        name: &str,
+        kind: InstrumentKind,
+    ) -> Result<Option<SchemaEntry>, ()> {
+        match &self.schema {
+            Some(schema) => schema.check(name, kind),
+            None => Ok(None),
+        }
+    }
+
+    fn record(&self, field_name: &str, value: f64, attributes: &[opentelemetry::KeyValue]) {
+        let mut instruments = self.instruments.lock().unwrap();
+        if let Some(name) = field_name.strip_prefix(MONOTONIC_COUNTER_PREFIX) {
+            let Ok(entry) = self.schema_entry(name, InstrumentKind::Counter) else {
+                return;
+            };
+            instruments
+                .counters
+                .entry(name.to_string())
+                .or_insert_with(|| {
+                    let mut builder = self.meter.f64_counter(name.to_string());
+                    if let Some(entry) = &entry {
+                        if let Some(unit) = &entry.unit {
+                            builder = builder.with_unit(unit.clone());
+                        }
+                        if let Some(description) = &entry.description {
+                            builder = builder.with_description(description.clone());
+                        }
+                    }
+                    builder.build()
+                })
+                .add(value, attributes);
+        } else if let Some(name) = field_name.strip_prefix(COUNTER_PREFIX) {
+            let Ok(entry) = self.schema_entry(name, InstrumentKind::UpDownCounter) else {
+                return;
+            };
+            instruments
+                .up_down_counters
+                .entry(name.to_string())
+                .or_insert_with(|| {
+                    let mut builder = self.meter.f64_up_down_counter(name.to_string());
+                    if let Some(entry) = &entry {
+                        if let Some(unit) = &entry.unit {
+                            builder = builder.with_unit(unit.clone());
+                        }
+                        if let Some(description) = &entry.description {
+                            builder = builder.with_description(description.clone());
+                        }
+                    }
+                    builder.build()
+                })
+                .add(value, attributes);
+        } else if let Some(name) = field_name.strip_prefix(HISTOGRAM_PREFIX) {
+            let Ok(entry) = self.schema_entry(name, InstrumentKind::Histogram) else {
+                return;
+            };
+            instruments
+                .histograms
+                .entry(name.to_string())
+                .or_insert_with(|| {
+                    let mut builder = self.meter.f64_histogram(name.to_string());
+                    if let Some(entry) = &entry {
+                        if let Some(unit) = &entry.unit {
+                            builder = builder.with_unit(unit.clone());
+                        }
+                        if let Some(description) = &entry.description {
+                            builder = builder.with_description(description.clone());
+                        }
+                    }
+                    builder.build()
+                })
+                .record(value, attributes);
+        } else if let Some(name) = field_name.strip_prefix(GAUGE_PREFIX) {
+            let Ok(entry) = self.schema_entry(name, InstrumentKind::Gauge) else {
+                return;
+            };
+            instruments
+                .gauges
+                .entry(name.to_string())
+                .or_insert_with(|| {
+                    let mut builder = self.meter.f64_gauge(name.to_string());
+                    if let Some(entry) = &entry {
+                        if let Some(unit) = &entry.unit {
+                            builder = builder.with_unit(unit.clone());
+                        }
+                        if let Some(description) = &entry.description {
+                            builder = builder.with_description(description.clone());
+                        }
+                    }
+                    builder.build()
+                })
+                .record(value, attributes);
+        }
+    }
+}
+
+#[derive(Default)]
+struct MetricVisitor {
+    recordings: Vec<(&'static str, f64)>,
+    attributes: Vec<opentelemetry::KeyValue>,
+}
+
+impl MetricVisitor {
+    fn is_metric_field(name: &str) -> bool {
+        name.starts_with(MONOTONIC_COUNTER_PREFIX)
+            || name.starts_with(COUNTER_PREFIX)
+            || name.starts_with(HISTOGRAM_PREFIX)
+            || name.starts_with(GAUGE_PREFIX)
+    }
+}
+
+impl field::Visit for MetricVisitor {
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        if Self::is_metric_field(field.name()) {
+            self.recordings.push((field.name(), value));
+        } else {
+            self.attributes
+                .push(opentelemetry::KeyValue::new(field.name(), value));
+        }
+    }
+
+    fn record_i64(&mut self, field: &field::Field, value: i64) {
+        if Self::is_metric_field(field.name()) {
+            self.recordings.push((field.name(), value as f64));
+        } else {
+            self.attributes
+                .push(opentelemetry::KeyValue::new(field.name(), value));
+        }
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        self.record_i64(field, value as i64)
+    }
+
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        if !Self::is_metric_field(field.name()) {
+            self.attributes
+                .push(opentelemetry::KeyValue::new(field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        if !Self::is_metric_field(field.name()) {
+            self.attributes
+                .push(opentelemetry::KeyValue::new(field.name(), value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, _field: &field::Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl<S: Subscriber> Layer<S> for MetricsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // Cheap pre-filter: most events carry no metric fields.
+        if !event.metadata().fields().iter().any(|field| {
+            let name = field.name();
+            name.starts_with(MONOTONIC_COUNTER_PREFIX)
+                || name.starts_with(COUNTER_PREFIX)
+                || name.starts_with(HISTOGRAM_PREFIX)
+                || name.starts_with(GAUGE_PREFIX)
+        }) {
+            return;
+        }
+        let mut visitor = MetricVisitor::default();
+        event.record(&mut visitor);
+        for (field_name, value) in visitor.recordings {
+            let name = field_name
+                .split_once('.')
+                .map(|(_, name)| name)
+                .unwrap_or(field_name);
+            let attributes = self.guard_attributes(name, visitor.attributes.clone());
+            self.record(field_name, value, &attributes);
+        }
+    }
+}
+
+/// RED metrics derived from finished spans (see
+/// [`OpenTelemetryLayer::with_span_metrics`]): a `traces.span.calls`
+/// counter and a `traces.span.duration` histogram (milliseconds), both
+/// attributed by `span.name`, `span.kind` and `status`.
+///
+/// [`OpenTelemetryLayer::with_span_metrics`]: crate::OpenTelemetryLayer::with_span_metrics
+pub(crate) struct SpanMetrics {
+    calls: Counter<u64>,
+    duration_ms: Histogram<f64>,
+}
+
+impl SpanMetrics {
+    pub(crate) fn new(meter: &Meter, bucket_boundaries: Option<Vec<f64>>) -> Self {
+        let mut duration = meter
+            .f64_histogram("traces.span.duration")
+            .with_unit("ms")
+            .with_description("Duration of finished spans");
+        if let Some(boundaries) = bucket_boundaries {
+            duration = duration.with_boundaries(boundaries);
+        }
+        SpanMetrics {
+            calls: meter
+                .u64_counter("traces.span.calls")
+                .with_description("Number of finished spans")
+                .build(),
+            duration_ms: duration.build(),
+        }
+    }
+
+    pub(crate) fn record(
+        &self,
+        name: &str,
+        kind: &opentelemetry::trace::SpanKind,
+        is_error: bool,
+        duration: std::time::Duration,
+    ) {
+        let attributes = [
+            opentelemetry::KeyValue::new("span.name", name.to_string()),
+            opentelemetry::KeyValue::new("span.kind", format!("{kind:?}").to_lowercase()),
+            opentelemetry::KeyValue::new("status", if is_error { "error" } else { "ok" }),
+        ];
+        self.calls.add(1, &attributes);
+        self.duration_ms
+            .record(duration.as_secs_f64() * 1_000.0, &attributes);
+    }
+}
@@ -0,0 +1,448 @@
+//! A debug exporter writing spans as OTLP/JSON lines.
+//!
+//! One `resourceSpans` document per batch, one line per export, in the
+//! standard OTLP JSON encoding (hex IDs, stringified unix-nano timestamps,
+//! tagged values) — so the output can be eyeballed, diffed, or replayed
+//! into any OTLP-JSON-speaking collector.
+//!
+//! ```no_run
+//! use opentelemetry_sdk::trace::SdkTracerProvider;
+//!
+//! let provider = SdkTracerProvider::builder()
+//!     .with_simple_exporter(n00_otel::OtlpJsonExporter::stdout())
+//!     .build();
+//! # drop(provider);
+//! ```
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use opentelemetry::trace::{SpanKind, Status};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+/// A [`SpanExporter`] writing OTLP/JSON lines to a writer.
+#[derive(Debug)]
+pub struct OtlpJsonExporter<W> {
+    writer: Mutex<W>,
+    resource: Mutex<Resource>,
+    batch_dictionary: bool,
+}
+
+impl OtlpJsonExporter<std::io::Stdout> {
+    /// An exporter writing to standard output.
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl<W: Write + Send + Sync + std::fmt::Debug> OtlpJsonExporter<W> {
+    /// An exporter writing one OTLP/JSON document per line to `writer`.
+    pub fn new(writer: W) -> Self {
+        OtlpJsonExporter {
+            writer: Mutex::new(writer),
+            resource: Mutex::new(Resource::builder_empty().build()),
+            batch_dictionary: false,
+        }
+    }
+
+    /// Dictionary-encode each batch before writing (see
+    /// [`dictionary_encode`]): repeated attribute keys and common string
+    /// values across the batch are written once and referenced, which cuts
+    /// the payload severalfold for attribute-heavy batches. The output is
+    /// the `{"dictionary": ..., "data": ...}` envelope; consumers restore
+    /// standard OTLP/JSON with [`dictionary_decode`].
+    pub fn with_batch_dictionary(mut self, enabled: bool) -> Self {
+        self.batch_dictionary = enabled;
+        self
+    }
+}
+
+impl<W: Write + Send + Sync + std::fmt::Debug> SpanExporter for OtlpJsonExporter<W> {
+    fn export(
+        &self,
+        batch: Vec<SpanData>,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let mut document = {
+            let resource = self.resource.lock().unwrap();
+            batch_json(&resource, &batch)
+        };
+        if self.batch_dictionary {
+            document = dictionary_encode(document);
+        }
+        let result = (|| {
+            let mut writer = self
+                .writer
+                .lock()
+                .map_err(|_| OTelSdkError::InternalFailure("writer poisoned".into()))?;
+            serde_json::to_writer(&mut *writer, &document)
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+            writer
+                .write_all(b"\n")
+                .and_then(|()| writer.flush())
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+        })();
+        std::future::ready(result)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        *self.resource.lock().unwrap() = resource.clone();
+    }
+}
+
+/// A [`SpanExporter`] POSTing OTLP/JSON batches to an OTLP/HTTP endpoint
+/// (`.../v1/traces`), for integration tests against a real collector.
+///
+/// Plain HTTP only, one blocking request per batch; this is a test helper,
+/// not a production exporter. See
+/// [`testing::TestHarness::against_otlp`](crate::testing::TestHarness::against_otlp)
+/// for the harness entry point.
+#[derive(Debug)]
+pub struct OtlpHttpJsonExporter {
+    endpoint: String,
+    resource: Mutex<Resource>,
+}
+
+impl OtlpHttpJsonExporter {
+    /// An exporter POSTing to `endpoint`, e.g.
+    /// `http://localhost:4318/v1/traces`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        OtlpHttpJsonExporter {
+            endpoint: endpoint.into(),
+            resource: Mutex::new(Resource::builder_empty().build()),
+        }
+    }
+
+    fn post(&self, body: &str) -> Result<(), String> {
+        use std::io::Read;
+
+        let rest = self
+            .endpoint
+            .strip_prefix("http://")
+            .ok_or_else(|| "only http:// endpoints are supported".to_string())?;
+        let (host_port, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/v1/traces"),
+        };
+        let host = host_port.split(':').next().unwrap_or(host_port);
+        let addr = if host_port.contains(':') {
+            host_port.to_string()
+        } else {
+            format!("{host_port}:4318")
+        };
+
+        let mut stream =
+            std::net::TcpStream::connect(&addr).map_err(|e| format!("connect {addr}: {e}"))?;
+        stream
+            .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+            .map_err(|e| e.to_string())?;
+        write!(
+            stream,
+            "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .map_err(|e| e.to_string())?;
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => response.extend_from_slice(&chunk[..n]),
+                Err(_) if !response.is_empty() => break,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        if status_line.contains(" 200 ") || status_line.ends_with(" 200") || status_line.contains(" 2") {
+            Ok(())
+        } else {
+            Err(format!("endpoint rejected batch: {status_line}"))
+        }
+    }
+}
+
+impl SpanExporter for OtlpHttpJsonExporter {
+    fn export(
+        &self,
+        batch: Vec<SpanData>,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let document = {
+            let resource = self.resource.lock().unwrap();
+            batch_json(&resource, &batch)
+        };
+        let result = self
+            .post(&document.to_string())
+            .map_err(OTelSdkError::InternalFailure);
+        std::future::ready(result)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        *self.resource.lock().unwrap() = resource.clone();
+    }
+}
+
+/// Marker prefix for dictionary references inside encoded documents.
+/// Printable so a reference costs `1 + digits` bytes on the wire; literal
+/// strings starting with it are escaped by doubling it.
+const DICT_REF_PREFIX: char = '#';
+
+/// Strings shorter than this are cheaper inline than as a reference.
+const DICT_MIN_LEN: usize = 4;
+
+/// Compress an OTLP/JSON document by extracting strings that repeat across
+/// the batch — attribute keys above all, but also common values like
+/// status messages and enum-ish attributes — into a shared dictionary.
+///
+/// The result is `{"dictionary": [..strings..], "data": <document>}` where
+/// every extracted occurrence is replaced by a `"#<index>"` reference
+/// (object keys included; literal strings starting with `#` are escaped by
+/// doubling it). [`dictionary_decode`] restores the original document
+/// byte-for-byte; the envelope is this crate's framing, not standard OTLP,
+/// so both ends must opt in.
+pub fn dictionary_encode(document: serde_json::Value) -> serde_json::Value {
+    use std::collections::HashMap;
+
+    fn count(value: &serde_json::Value, counts: &mut HashMap<String, usize>) {
+        match value {
+            serde_json::Value::String(s) => *counts.entry(s.clone()).or_default() += 1,
+            serde_json::Value::Array(items) => items.iter().for_each(|v| count(v, counts)),
+            serde_json::Value::Object(map) => {
+                for (key, v) in map {
+                    *counts.entry(key.clone()).or_default() += 1;
+                    count(v, counts);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut counts = HashMap::new();
+    count(&document, &mut counts);
+    // Deterministic dictionary order: most savings first, then lexical.
+    let mut entries: Vec<(String, usize)> = counts
+        .into_iter()
+        .filter(|(text, n)| *n >= 2 && text.len() >= DICT_MIN_LEN)
+        .collect();
+    entries.sort_by(|a, b| (b.0.len() * b.1).cmp(&(a.0.len() * a.1)).then(a.0.cmp(&b.0)));
+    let dictionary: Vec<String> = entries.into_iter().map(|(text, _)| text).collect();
+    let index: HashMap<&str, usize> = dictionary
+        .iter()
+        .enumerate()
+        .map(|(i, text)| (text.as_str(), i))
+        .collect();
+
+    fn encode_str(text: &str, index: &HashMap<&str, usize>) -> String {
+        match index.get(text) {
+            Some(i) => format!("{DICT_REF_PREFIX}{i}"),
+            // Escape literals that could read as references.
+            None if text.starts_with(DICT_REF_PREFIX) => format!("{DICT_REF_PREFIX}{text}"),
+            None => text.to_string(),
+        }
+    }
+
+    fn encode(value: serde_json::Value, index: &HashMap<&str, usize>) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(s) => serde_json::Value::String(encode_str(&s, index)),
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.into_iter().map(|v| encode(v, index)).collect())
+            }
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.into_iter()
+                    .map(|(key, v)| (encode_str(&key, index), encode(v, index)))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    let data = encode(document, &index);
+    serde_json::json!({"dictionary": dictionary, "data": data})
+}
+
+/// Reverse [`dictionary_encode`], restoring a standard OTLP/JSON document.
+/// Returns `None` if the envelope is malformed or a reference is out of
+/// range.
+pub fn dictionary_decode(envelope: serde_json::Value) -> Option<serde_json::Value> {
+    let serde_json::Value::Object(mut envelope) = envelope else {
+        return None;
+    };
+    let dictionary: Vec<String> = match envelope.remove("dictionary")? {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => Some(s),
+                _ => None,
+            })
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let data = envelope.remove("data")?;
+
+    fn decode_str(text: &str, dictionary: &[String]) -> Option<String> {
+        let Some(rest) = text.strip_prefix(DICT_REF_PREFIX) else {
+            return Some(text.to_string());
+        };
+        if let Some(escaped) = rest.strip_prefix(DICT_REF_PREFIX) {
+            return Some(format!("{DICT_REF_PREFIX}{escaped}"));
+        }
+        dictionary.get(rest.parse::<usize>().ok()?).cloned()
+    }
+
+    fn decode(value: serde_json::Value, dictionary: &[String]) -> Option<serde_json::Value> {
+        Some(match value {
+            serde_json::Value::String(s) => {
+                serde_json::Value::String(decode_str(&s, dictionary)?)
+            }
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items
+                    .into_iter()
+                    .map(|v| decode(v, dictionary))
+                    .collect::<Option<_>>()?,
+            ),
+            serde_json::Value::Object(map) => {
+                let mut decoded = serde_json::Map::with_capacity(map.len());
+                for (key, v) in map {
+                    decoded.insert(decode_str(&key, dictionary)?, decode(v, dictionary)?);
+                }
+                serde_json::Value::Object(decoded)
+            }
+            other => other,
+        })
+    }
+
+    decode(data, &dictionary)
+}
+
+fn batch_json(resource: &Resource, batch: &[SpanData]) -> serde_json::Value {
+    // Group by instrumentation scope name, preserving batch order.
+    let mut scopes: Vec<(String, Vec<&SpanData>)> = Vec::new();
+    for span in batch {
+        let name = span.instrumentation_scope.name().to_string();
+        match scopes.iter_mut().find(|(scope, _)| *scope == name) {
+            Some((_, spans)) => spans.push(span),
+            None => scopes.push((name, vec![span])),
+        }
+    }
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": resource
+                    .iter()
+                    .map(|(key, value)| attribute_json(&KeyValue::new(key.clone(), value.clone())))
+                    .collect::<Vec<_>>(),
+            },
+            "scopeSpans": scopes
+                .into_iter()
+                .map(|(scope, spans)| {
+                    serde_json::json!({
+                        "scope": {"name": scope},
+                        "spans": spans.into_iter().map(span_json).collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }],
+    })
+}
+
+fn span_json(span: &SpanData) -> serde_json::Value {
+    serde_json::json!({
+        "traceId": crate::hex::trace_id_hex(span.span_context.trace_id()).as_str(),
+        "spanId": crate::hex::span_id_hex(span.span_context.span_id()).as_str(),
+        "parentSpanId": crate::hex::span_id_hex(span.parent_span_id).as_str(),
+        "name": span.name.as_ref(),
+        "kind": span_kind_json(&span.span_kind),
+        "startTimeUnixNano": unix_nanos(span.start_time),
+        "endTimeUnixNano": unix_nanos(span.end_time),
+        "attributes": span.attributes.iter().map(attribute_json).collect::<Vec<_>>(),
+        "droppedAttributesCount": span.dropped_attributes_count,
+        "events": span
+            .events
+            .iter()
+            .map(|event| {
+                serde_json::json!({
+                    "name": event.name.as_ref(),
+                    "timeUnixNano": unix_nanos(event.timestamp),
+                    "attributes": event.attributes.iter().map(attribute_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+        "links": span
+            .links
+            .iter()
+            .map(|link| {
+                serde_json::json!({
+                    "traceId": crate::hex::trace_id_hex(link.span_context.trace_id()).as_str(),
+                    "spanId": crate::hex::span_id_hex(link.span_context.span_id()).as_str(),
+                    "attributes": link.attributes.iter().map(attribute_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+        "status": status_json(&span.status),
+    })
+}
+
+fn span_kind_json(kind: &SpanKind) -> u8 {
+    match kind {
+        SpanKind::Internal => 1,
+        SpanKind::Server => 2,
+        SpanKind::Client => 3,
+        SpanKind::Producer => 4,
+        SpanKind::Consumer => 5,
+    }
+}
+
+fn status_json(status: &Status) -> serde_json::Value {
+    match status {
+        Status::Unset => serde_json::json!({}),
+        Status::Ok => serde_json::json!({"code": 1}),
+        Status::Error { description } => {
+            serde_json::json!({"code": 2, "message": description.as_ref()})
+        }
+    }
+}
+
+fn unix_nanos(time: std::time::SystemTime) -> String {
+    time.duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos().to_string())
+        .unwrap_or_else(|_| "0".to_string())
+}
+
+fn attribute_json(kv: &KeyValue) -> serde_json::Value {
+    serde_json::json!({"key": kv.key.as_str(), "value": value_json(&kv.value)})
+}
+
+fn value_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => serde_json::json!({"boolValue": b}),
+        // OTLP/JSON renders 64-bit integers as strings.
+        Value::I64(i) => serde_json::json!({"intValue": i.to_string()}),
+        Value::F64(f) => serde_json::json!({"doubleValue": f}),
+        Value::String(s) => serde_json::json!({"stringValue": s.as_str()}),
+        Value::Array(array) => {
+            let values: Vec<serde_json::Value> = match array {
+                opentelemetry::Array::Bool(items) => {
+                    items.iter().map(|b| serde_json::json!({"boolValue": b})).collect()
+                }
+                opentelemetry::Array::I64(items) => items
+                    .iter()
+                    .map(|i| serde_json::json!({"intValue": i.to_string()}))
+                    .collect(),
+                opentelemetry::Array::F64(items) => items
+                    .iter()
+                    .map(|f| serde_json::json!({"doubleValue": f}))
+                    .collect(),
+                opentelemetry::Array::String(items) => items
+                    .iter()
+                    .map(|s| serde_json::json!({"stringValue": s.as_str()}))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            serde_json::json!({"arrayValue": {"values": values}})
+        }
+        _ => serde_json::json!({"stringValue": value.to_string()}),
+    }
+}
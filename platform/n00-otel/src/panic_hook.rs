@@ -0,0 +1,48 @@
+//! Panic hook that records panics into the current trace.
+
+use crate::OpenTelemetrySpanExt;
+
+/// Install a panic hook that records the panic on the current span before
+/// delegating to the previously installed hook.
+///
+/// A panic inside an instrumented request otherwise leaves no trace — the
+/// span just closes (or never does). With this hook the current span gets a
+/// `panic` event carrying the message, location and thread, and its status
+/// is set to [`Status::Error`], so panicking requests show up as failed
+/// traces with their context attached.
+///
+/// Chains rather than replaces: the default stderr backtrace (or whatever
+/// hook was installed before) still runs. Install once at startup, after
+/// the subscriber is set up.
+///
+/// [`Status::Error`]: opentelemetry::trace::Status
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            (*s).to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let thread = std::thread::current();
+
+        // The event parents to the current span like any other `tracing`
+        // event; the status upgrade makes the failure visible to samplers
+        // and backends that key on span status.
+        tracing::error!(
+            panic.message = %message,
+            panic.location = location.as_deref().unwrap_or("unknown"),
+            thread.name = thread.name().unwrap_or("unnamed"),
+            "panic"
+        );
+        let span = tracing::Span::current();
+        span.set_status(opentelemetry::trace::Status::error(message));
+
+        previous(info);
+    }));
+}
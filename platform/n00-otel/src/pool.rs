@@ -0,0 +1,118 @@
+//! Reuse of per-span attribute and event storage.
+//!
+//! Every span allocates a `Vec<KeyValue>` for its attributes and a
+//! `VecDeque` for buffered events; under span-heavy load those buffers
+//! churn through the allocator. This arena-style pool keeps them on small
+//! thread-local freelists and hands them back out at span creation:
+//! dropped spans (rate limits, `otel.drop`, sampling, shedding) recycle
+//! both buffers, and exported spans recycle their event deque (drained at
+//! close; the attribute `Vec` itself is handed to the SDK and cannot be
+//! reclaimed). The `span_close_dropped_pooled` benchmark in
+//! `benches/hot_paths.rs` measures the recycled path against the
+//! exporting one.
+
+use std::cell::RefCell;
+
+use opentelemetry::KeyValue;
+
+/// Per-thread freelist bound: enough for bursts, small enough to ignore.
+const MAX_POOLED: usize = 32;
+
+/// Don't retain buffers that grew pathologically large.
+const MAX_POOLED_CAPACITY: usize = 256;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<KeyValue>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An empty attribute buffer, reusing a pooled allocation when available.
+pub(crate) fn take_attr_vec(capacity_hint: usize) -> Vec<KeyValue> {
+    POOL.with(|pool| {
+        match pool.borrow_mut().pop() {
+            Some(vec) => vec,
+            None => Vec::with_capacity(capacity_hint),
+        }
+    })
+}
+
+/// Return a span's attribute buffer to the pool.
+pub(crate) fn recycle_attr_vec(mut vec: Vec<KeyValue>) {
+    if vec.capacity() == 0 || vec.capacity() > MAX_POOLED_CAPACITY {
+        return;
+    }
+    vec.clear();
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < MAX_POOLED {
+            pool.push(vec);
+        }
+    });
+}
+
+thread_local! {
+    static EVENT_POOL: RefCell<Vec<std::collections::VecDeque<opentelemetry::trace::Event>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// An empty event buffer, reusing a pooled allocation when available.
+pub(crate) fn take_event_buf() -> std::collections::VecDeque<opentelemetry::trace::Event> {
+    EVENT_POOL.with(|pool| pool.borrow_mut().pop().unwrap_or_default())
+}
+
+/// Return a span's (drained or abandoned) event buffer to the pool.
+pub(crate) fn recycle_event_buf(mut buf: std::collections::VecDeque<opentelemetry::trace::Event>) {
+    if buf.capacity() == 0 || buf.capacity() > MAX_POOLED_CAPACITY {
+        return;
+    }
+    buf.clear();
+    EVENT_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < MAX_POOLED {
+            pool.push(buf);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycled_buffers_are_reused() {
+        // Drain anything other tests left behind.
+        POOL.with(|pool| pool.borrow_mut().clear());
+
+        let mut vec = take_attr_vec(8);
+        vec.push(KeyValue::new("k", 1));
+        let ptr = vec.as_ptr();
+        recycle_attr_vec(vec);
+
+        let reused = take_attr_vec(8);
+        assert!(reused.is_empty());
+        assert_eq!(reused.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn event_buffers_are_reused() {
+        EVENT_POOL.with(|pool| pool.borrow_mut().clear());
+        let mut buf = take_event_buf();
+        buf.push_back(opentelemetry::trace::Event::new(
+            "e",
+            std::time::SystemTime::UNIX_EPOCH,
+            vec![],
+            0,
+        ));
+        let capacity = buf.capacity();
+        recycle_event_buf(buf);
+        let reused = take_event_buf();
+        assert!(reused.is_empty());
+        assert_eq!(reused.capacity(), capacity);
+    }
+
+    #[test]
+    fn oversized_buffers_are_not_retained() {
+        POOL.with(|pool| pool.borrow_mut().clear());
+        recycle_attr_vec(Vec::with_capacity(MAX_POOLED_CAPACITY + 1));
+        POOL.with(|pool| assert!(pool.borrow().is_empty()));
+    }
+}
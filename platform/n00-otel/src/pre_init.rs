@@ -0,0 +1,225 @@
+//! Graceful degradation before a tracer is configured: buffer, then flush.
+//!
+//! Spans created between process start and telemetry initialization are
+//! usually lost — exactly the spans that cover config loading and the other
+//! things that fail at startup. [`LazyTracer`] stands in for the real tracer
+//! from the first line of `main`, buffers spans that close before
+//! initialization (up to a bound), and replays them once
+//! [`initialize`](LazyTracer::initialize) is called.
+//!
+//! ```
+//! use opentelemetry::trace::TracerProvider as _;
+//! use tracing_subscriber::layer::SubscriberExt;
+//! use tracing_subscriber::Registry;
+//!
+//! let lazy = n00_otel::LazyTracer::buffering(1024);
+//! let subscriber = Registry::default().with(n00_otel::layer().with_tracer(lazy.clone()));
+//! // ... spans may be created and closed here ...
+//! # let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+//! lazy.initialize(provider.tracer("my-service")); // flushes the buffer
+//! # drop(subscriber);
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+
+use opentelemetry::trace::{
+    SamplingDecision, SamplingResult, SpanBuilder, SpanContext, SpanId, TraceContextExt,
+    TraceFlags, TraceId, Tracer,
+};
+use opentelemetry::Context;
+use opentelemetry_sdk::trace::{IdGenerator, RandomIdGenerator, SdkTracer};
+
+use crate::tracer::SynthesizedSpan;
+use crate::{OtelData, PreSampledTracer};
+
+/// A tracer that buffers spans until a real [`SdkTracer`] is installed.
+///
+/// Pre-init spans are treated as sampled (IDs are allocated eagerly so
+/// parent/child relationships survive the buffer); whatever closes before
+/// [`initialize`](Self::initialize) is replayed through the real tracer in
+/// close order, with original timestamps intact.
+#[derive(Clone)]
+pub struct LazyTracer {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    inner: RwLock<Option<SdkTracer>>,
+    /// Spans that closed before initialization, oldest first.
+    buffer: Mutex<VecDeque<(SpanBuilder, Context)>>,
+    capacity: usize,
+    id_generator: RandomIdGenerator,
+}
+
+impl std::fmt::Debug for LazyTracer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyTracer")
+            .field("initialized", &self.shared.inner.read().unwrap().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LazyTracer {
+    /// A lazy tracer buffering at most `capacity` pre-init spans; beyond
+    /// that, the oldest buffered span is dropped.
+    pub fn buffering(capacity: usize) -> Self {
+        LazyTracer {
+            shared: Arc::new(Shared {
+                inner: RwLock::new(None),
+                buffer: Mutex::new(VecDeque::new()),
+                capacity,
+                id_generator: RandomIdGenerator::default(),
+            }),
+        }
+    }
+
+    /// Install the real tracer and flush every buffered span through it.
+    ///
+    /// Subsequent spans bypass the buffer entirely. Calling twice replaces
+    /// the tracer; the buffer is only flushed once.
+    pub fn initialize(&self, tracer: SdkTracer) {
+        // Install first, then drain: a span closing concurrently either
+        // sees the tracer (and exports directly) or lands in the buffer
+        // before the drain below picks it up. Draining first would leave a
+        // window where such a span is buffered and never flushed.
+        *self.shared.inner.write().unwrap() = Some(tracer.clone());
+        let buffered: Vec<(SpanBuilder, Context)> = {
+            let mut buffer = self.shared.buffer.lock().unwrap();
+            buffer.drain(..).collect()
+        };
+        for (mut builder, parent_cx) in buffered {
+            // Honor the sampled contexts we handed out pre-init.
+            builder.sampling_result = Some(SamplingResult {
+                decision: SamplingDecision::RecordAndSample,
+                attributes: Vec::new(),
+                trace_state: Default::default(),
+            });
+            drop(tracer.build_with_context(builder, &parent_cx));
+        }
+    }
+}
+
+/// A span handle from [`LazyTracer`]; inert for buffered pre-init spans.
+#[derive(Debug)]
+pub struct LazySpan(Option<opentelemetry_sdk::trace::Span>);
+
+impl opentelemetry::trace::Span for LazySpan {
+    fn add_event_with_timestamp<T>(
+        &mut self,
+        name: T,
+        timestamp: std::time::SystemTime,
+        attributes: Vec<opentelemetry::KeyValue>,
+    ) where
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+        if let Some(span) = &mut self.0 {
+            span.add_event_with_timestamp(name, timestamp, attributes);
+        }
+    }
+
+    fn span_context(&self) -> &SpanContext {
+        match &self.0 {
+            Some(span) => span.span_context(),
+            None => &SpanContext::NONE,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.0.as_ref().is_some_and(|span| span.is_recording())
+    }
+
+    fn set_attribute(&mut self, attribute: opentelemetry::KeyValue) {
+        if let Some(span) = &mut self.0 {
+            span.set_attribute(attribute);
+        }
+    }
+
+    fn set_status(&mut self, status: opentelemetry::trace::Status) {
+        if let Some(span) = &mut self.0 {
+            span.set_status(status);
+        }
+    }
+
+    fn update_name<T>(&mut self, new_name: T)
+    where
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+        if let Some(span) = &mut self.0 {
+            span.update_name(new_name);
+        }
+    }
+
+    fn add_link(&mut self, span_context: SpanContext, attributes: Vec<opentelemetry::KeyValue>) {
+        if let Some(span) = &mut self.0 {
+            span.add_link(span_context, attributes);
+        }
+    }
+
+    fn end_with_timestamp(&mut self, timestamp: std::time::SystemTime) {
+        if let Some(span) = &mut self.0 {
+            span.end_with_timestamp(timestamp);
+        }
+    }
+}
+
+impl Tracer for LazyTracer {
+    type Span = LazySpan;
+
+    fn build_with_context(&self, builder: SpanBuilder, parent_cx: &Context) -> Self::Span {
+        if let Some(tracer) = &*self.shared.inner.read().unwrap() {
+            return LazySpan(Some(tracer.build_with_context(builder, parent_cx)));
+        }
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        if buffer.len() >= self.shared.capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back((builder, parent_cx.clone()));
+        LazySpan(None)
+    }
+}
+
+impl PreSampledTracer for LazyTracer {
+    fn sampled_context(&self, data: &mut OtelData) -> Context {
+        if let Some(tracer) = &*self.shared.inner.read().unwrap() {
+            return tracer.sampled_context(data);
+        }
+
+        // Pre-init: allocate IDs ourselves and advertise "sampled" so the
+        // whole startup trace stays consistent once flushed.
+        let parent_sc = data
+            .parent_cx
+            .has_active_span()
+            .then(|| data.parent_cx.span().span_context().clone())
+            .filter(|sc| sc.is_valid());
+        let trace_id = parent_sc
+            .as_ref()
+            .map(|sc| sc.trace_id())
+            .or(data.builder.trace_id)
+            .unwrap_or_else(|| self.shared.id_generator.new_trace_id());
+        data.builder.trace_id = Some(trace_id);
+        let span_id = *data
+            .builder
+            .span_id
+            .get_or_insert_with(|| self.shared.id_generator.new_span_id());
+
+        let span_context = SpanContext::new(
+            trace_id,
+            span_id,
+            TraceFlags::SAMPLED,
+            false,
+            parent_sc
+                .map(|sc| sc.trace_state().clone())
+                .unwrap_or_default(),
+        );
+        data.parent_cx.with_span(SynthesizedSpan(span_context))
+    }
+
+    fn new_trace_id(&self) -> TraceId {
+        self.shared.id_generator.new_trace_id()
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        self.shared.id_generator.new_span_id()
+    }
+}
@@ -0,0 +1,48 @@
+//! Thread-context trace IDs for profiler correlation.
+//!
+//! Sampling profilers (pprof, perf, eBPF agents) capture stacks per thread;
+//! correlating a hot stack with the request that caused it needs the trace
+//! context readable *from the profiled thread* at sample time. With
+//! [`OpenTelemetryLayer::with_profiling_correlation`] enabled, the layer
+//! maintains a per-thread stack of the entered spans' IDs, and
+//! [`current_thread_trace_ids`] exposes the innermost one as plain
+//! integers — a `Cell` read, no locks, safe enough for signal-handler use.
+//!
+//! [`OpenTelemetryLayer::with_profiling_correlation`]: crate::OpenTelemetryLayer::with_profiling_correlation
+
+use std::cell::RefCell;
+
+use opentelemetry::trace::{SpanId, TraceId};
+
+thread_local! {
+    /// Stack of (trace_id, span_id) for spans entered on this thread.
+    static THREAD_SPAN_STACK: RefCell<Vec<(u128, u64)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The trace and span ID of the innermost span entered on this thread, if
+/// any. Requires the layer to run with profiling correlation enabled.
+pub fn current_thread_trace_ids() -> Option<(TraceId, SpanId)> {
+    THREAD_SPAN_STACK.with(|stack| {
+        stack.borrow().last().map(|(trace_id, span_id)| {
+            (
+                TraceId::from_bytes(trace_id.to_be_bytes()),
+                SpanId::from_bytes(span_id.to_be_bytes()),
+            )
+        })
+    })
+}
+
+pub(crate) fn push(trace_id: TraceId, span_id: SpanId) {
+    THREAD_SPAN_STACK.with(|stack| {
+        stack.borrow_mut().push((
+            u128::from_be_bytes(trace_id.to_bytes()),
+            u64::from_be_bytes(span_id.to_bytes()),
+        ))
+    });
+}
+
+pub(crate) fn pop() {
+    THREAD_SPAN_STACK.with(|stack| {
+        stack.borrow_mut().pop();
+    });
+}
@@ -0,0 +1,541 @@
+//! B3 (Zipkin) and AWS X-Ray context propagation.
+//!
+//! Both types implement [`TextMapPropagator`], so they slot into
+//! [`OpenTelemetryLayer::with_propagator`], the global propagator, or direct
+//! use with [`OpenTelemetrySpanExt::set_parent_from`] /
+//! [`inject_context`].
+//!
+//! [`OpenTelemetryLayer::with_propagator`]: crate::OpenTelemetryLayer::with_propagator
+//! [`OpenTelemetrySpanExt::set_parent_from`]: crate::OpenTelemetrySpanExt::set_parent_from
+//! [`inject_context`]: crate::OpenTelemetrySpanExt::inject_context
+
+use std::sync::OnceLock;
+
+use opentelemetry::propagation::text_map_propagator::FieldIter;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{
+    SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::Context;
+
+const B3_SINGLE_HEADER: &str = "b3";
+const B3_TRACE_ID_HEADER: &str = "x-b3-traceid";
+const B3_SPAN_ID_HEADER: &str = "x-b3-spanid";
+const B3_SAMPLED_HEADER: &str = "x-b3-sampled";
+const XRAY_HEADER: &str = "x-amzn-trace-id";
+
+/// Whether [`B3Propagator`] writes the single `b3` header or the multi
+/// `X-B3-*` header set. Extraction always accepts both, single first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum B3Encoding {
+    /// One `b3: {trace_id}-{span_id}-{sampled}` header.
+    #[default]
+    Single,
+    /// Separate `X-B3-TraceId`/`X-B3-SpanId`/`X-B3-Sampled` headers.
+    Multi,
+}
+
+/// A [`TextMapPropagator`] speaking Zipkin's B3 format.
+#[derive(Clone, Debug, Default)]
+pub struct B3Propagator {
+    encoding: B3Encoding,
+}
+
+impl B3Propagator {
+    /// A propagator writing the single `b3` header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A propagator writing the given encoding.
+    pub fn with_encoding(encoding: B3Encoding) -> Self {
+        B3Propagator { encoding }
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        if let Some(single) = extractor.get(B3_SINGLE_HEADER) {
+            return parse_b3_single(single);
+        }
+        let trace_id = TraceId::from_hex(extractor.get(B3_TRACE_ID_HEADER)?.trim()).ok()?;
+        let span_id = SpanId::from_hex(extractor.get(B3_SPAN_ID_HEADER)?.trim()).ok()?;
+        let sampled = matches!(
+            extractor.get(B3_SAMPLED_HEADER).map(str::trim),
+            Some("1") | Some("true") | Some("d")
+        );
+        Some(span_context(trace_id, span_id, sampled))
+    }
+}
+
+fn parse_b3_single(value: &str) -> Option<SpanContext> {
+    let mut parts = value.trim().split('-');
+    let trace_id = crate::hex::parse_trace_id(parts.next()?)?;
+    let span_id = crate::hex::parse_span_id(parts.next()?)?;
+    let sampled = matches!(parts.next(), Some("1") | Some("d"));
+    Some(span_context(trace_id, span_id, sampled))
+}
+
+fn span_context(trace_id: TraceId, span_id: SpanId, sampled: bool) -> SpanContext {
+    let flags = if sampled {
+        TraceFlags::SAMPLED
+    } else {
+        TraceFlags::default()
+    };
+    SpanContext::new(trace_id, span_id, flags, true, TraceState::default())
+}
+
+impl TextMapPropagator for B3Propagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span_context = cx.span().span_context().clone();
+        if !span_context.is_valid() {
+            return;
+        }
+        let sampled = if span_context.is_sampled() { "1" } else { "0" };
+        let trace_id = crate::hex::trace_id_hex(span_context.trace_id());
+        let span_id = crate::hex::span_id_hex(span_context.span_id());
+        match self.encoding {
+            B3Encoding::Single => injector.set(
+                B3_SINGLE_HEADER,
+                format!("{trace_id}-{span_id}-{sampled}"),
+            ),
+            B3Encoding::Multi => {
+                injector.set(B3_TRACE_ID_HEADER, trace_id.as_str().to_string());
+                injector.set(B3_SPAN_ID_HEADER, span_id.as_str().to_string());
+                injector.set(B3_SAMPLED_HEADER, sampled.to_string());
+            }
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        match self.extract_span_context(extractor) {
+            Some(sc) if sc.is_valid() => cx.with_remote_span_context(sc),
+            _ => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        static FIELDS: OnceLock<Vec<String>> = OnceLock::new();
+        FieldIter::new(FIELDS.get_or_init(|| {
+            vec![
+                B3_SINGLE_HEADER.to_string(),
+                B3_TRACE_ID_HEADER.to_string(),
+                B3_SPAN_ID_HEADER.to_string(),
+                B3_SAMPLED_HEADER.to_string(),
+            ]
+        }))
+    }
+}
+
+/// A [`TextMapPropagator`] speaking the AWS X-Ray `X-Amzn-Trace-Id` format:
+/// `Root=1-{epoch8}-{unique24};Parent={span16};Sampled={0|1}`.
+#[derive(Clone, Debug, Default)]
+pub struct XrayPropagator;
+
+impl XrayPropagator {
+    /// A new X-Ray propagator.
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn extract_span_context(extractor: &dyn Extractor) -> Option<SpanContext> {
+        let header = extractor.get(XRAY_HEADER)?;
+        let mut trace_id = None;
+        let mut parent = None;
+        let mut sampled = false;
+        for part in header.split(';') {
+            let (key, value) = part.trim().split_once('=')?;
+            match key {
+                "Root" => {
+                    // 1-{8 hex}-{24 hex} -> 32 hex trace id
+                    let mut segments = value.splitn(3, '-');
+                    let _version = segments.next()?;
+                    let epoch = segments.next()?;
+                    let unique = segments.next()?;
+                    trace_id = TraceId::from_hex(&format!("{epoch}{unique}")).ok();
+                }
+                "Parent" => parent = SpanId::from_hex(value).ok(),
+                "Sampled" => sampled = value == "1",
+                _ => {}
+            }
+        }
+        Some(span_context(trace_id?, parent?, sampled))
+    }
+}
+
+impl TextMapPropagator for XrayPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span_context = cx.span().span_context().clone();
+        if !span_context.is_valid() {
+            return;
+        }
+        let trace_id = crate::hex::trace_id_hex(span_context.trace_id());
+        let (epoch, unique) = trace_id.split_at(8);
+        injector.set(
+            XRAY_HEADER,
+            format!(
+                "Root=1-{epoch}-{unique};Parent={};Sampled={}",
+                crate::hex::span_id_hex(span_context.span_id()),
+                if span_context.is_sampled() { "1" } else { "0" }
+            ),
+        );
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        match Self::extract_span_context(extractor) {
+            Some(sc) if sc.is_valid() => cx.with_remote_span_context(sc),
+            _ => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        static FIELDS: OnceLock<Vec<String>> = OnceLock::new();
+        FieldIter::new(FIELDS.get_or_init(|| vec![XRAY_HEADER.to_string()]))
+    }
+}
+
+/// Render a span context as a W3C `traceparent` value
+/// (`00-{trace}-{span}-{flags}`).
+pub fn traceparent_string(span_context: &SpanContext) -> String {
+    format!(
+        "00-{}-{}-{:02x}",
+        crate::hex::trace_id_hex(span_context.trace_id()),
+        crate::hex::span_id_hex(span_context.span_id()),
+        span_context.trace_flags().to_u8()
+    )
+}
+
+/// Parse a W3C `traceparent` value into a (remote) span context.
+pub fn parse_traceparent(value: &str) -> Option<SpanContext> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    if version.len() != 2 || version == "ff" {
+        return None;
+    }
+    let trace_id = crate::hex::parse_trace_id(parts.next()?)?;
+    let span_id = crate::hex::parse_span_id(parts.next()?)?;
+    let flags = u8::from_str_radix(parts.next()?, 16).ok()?;
+    let span_context = SpanContext::new(
+        trace_id,
+        span_id,
+        TraceFlags::new(flags),
+        true,
+        TraceState::default(),
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+/// The length of the binary span-context encoding: 16 trace-ID bytes,
+/// 8 span-ID bytes, 1 flags byte.
+pub const SPAN_CONTEXT_BYTES: usize = 25;
+
+/// Encode a span context into its compact binary form, for message
+/// headers and shared-memory handoffs where 25 bytes beat 55 characters.
+pub fn span_context_to_bytes(span_context: &SpanContext) -> [u8; SPAN_CONTEXT_BYTES] {
+    let mut bytes = [0u8; SPAN_CONTEXT_BYTES];
+    bytes[..16].copy_from_slice(&span_context.trace_id().to_bytes());
+    bytes[16..24].copy_from_slice(&span_context.span_id().to_bytes());
+    bytes[24] = span_context.trace_flags().to_u8();
+    bytes
+}
+
+/// Decode the binary form produced by [`span_context_to_bytes`]. Returns
+/// `None` for wrong lengths or an invalid (all-zero) context.
+pub fn span_context_from_bytes(bytes: &[u8]) -> Option<SpanContext> {
+    let bytes: &[u8; SPAN_CONTEXT_BYTES] = bytes.try_into().ok()?;
+    let span_context = SpanContext::new(
+        TraceId::from_bytes(bytes[..16].try_into().expect("16 trace-id bytes")),
+        SpanId::from_bytes(bytes[16..24].try_into().expect("8 span-id bytes")),
+        TraceFlags::new(bytes[24]),
+        true,
+        TraceState::default(),
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+/// Environment variable carrying the `traceparent` value across process
+/// boundaries.
+pub const TRACEPARENT_ENV: &str = "TRACEPARENT";
+
+/// Environment variable carrying the W3C `tracestate` value alongside
+/// [`TRACEPARENT_ENV`].
+pub const TRACESTATE_ENV: &str = "TRACESTATE";
+
+/// Read a span context from the `TRACEPARENT`/`TRACESTATE` environment
+/// variables, the conventional handoff from a parent process.
+pub fn span_context_from_env() -> Option<SpanContext> {
+    let parsed = parse_traceparent(&std::env::var(TRACEPARENT_ENV).ok()?)?;
+    let trace_state = std::env::var(TRACESTATE_ENV)
+        .ok()
+        .and_then(|ts| ts.parse::<TraceState>().ok())
+        .unwrap_or_default();
+    Some(SpanContext::new(
+        parsed.trace_id(),
+        parsed.span_id(),
+        parsed.trace_flags(),
+        true,
+        trace_state,
+    ))
+}
+
+/// Environment variable carrying W3C baggage alongside the trace context.
+pub const BAGGAGE_ENV: &str = "BAGGAGE";
+
+/// Inject a full OpenTelemetry context — active span *and* baggage — into a
+/// [`std::process::Command`]'s environment as
+/// `TRACEPARENT`/`TRACESTATE`/`BAGGAGE`.
+///
+/// The child recovers everything with [`context_from_env`]. For a bare
+/// span context without baggage, [`inject_subprocess_env`] remains.
+pub fn inject_subprocess_context(cx: &opentelemetry::Context, command: &mut std::process::Command) {
+    use opentelemetry::baggage::BaggageExt as _;
+    use opentelemetry::trace::TraceContextExt as _;
+
+    inject_subprocess_env(cx.span().span_context(), command);
+    let baggage = cx.baggage();
+    if baggage.is_empty() {
+        command.env_remove(BAGGAGE_ENV);
+    } else {
+        // `Baggage` displays in the W3C header encoding.
+        command.env(BAGGAGE_ENV, baggage.to_string());
+    }
+}
+
+/// Read a full context from the `TRACEPARENT`/`TRACESTATE`/`BAGGAGE`
+/// environment variables: the remote span context (if present and valid)
+/// plus any baggage entries. Returns an empty context when nothing usable
+/// is set.
+pub fn context_from_env() -> opentelemetry::Context {
+    use opentelemetry::baggage::BaggageExt as _;
+
+    let cx = match span_context_from_env() {
+        Some(span_context) => Context::new().with_remote_span_context(span_context),
+        None => Context::new(),
+    };
+    let Ok(baggage) = std::env::var(BAGGAGE_ENV) else {
+        return cx;
+    };
+    let entries: Vec<opentelemetry::KeyValue> = baggage
+        .split(',')
+        .filter_map(|entry| {
+            // Strip W3C properties (";key=value" suffixes) and whitespace.
+            let entry = entry.split(';').next()?.trim();
+            let (key, value) = entry.split_once('=')?;
+            (!key.is_empty()).then(|| opentelemetry::KeyValue::new(key.to_string(), value.to_string()))
+        })
+        .collect();
+    if entries.is_empty() {
+        cx
+    } else {
+        cx.with_baggage(entries)
+    }
+}
+
+/// Inject a span context into a [`std::process::Command`]'s environment as
+/// `TRACEPARENT`/`TRACESTATE`, so the child process joins the trace:
+///
+/// ```no_run
+/// use n00_otel::OpenTelemetrySpanExt;
+/// use opentelemetry::trace::TraceContextExt;
+///
+/// let span = tracing::info_span!("run_migration");
+/// let cx = span.context();
+/// let mut command = std::process::Command::new("migrate");
+/// n00_otel::propagation::inject_subprocess_env(
+///     TraceContextExt::span(&cx).span_context(),
+///     &mut command,
+/// );
+/// ```
+///
+/// The child recovers the context with [`span_context_from_env`] (or any
+/// W3C-aware tooling reading the same variables).
+pub fn inject_subprocess_env(span_context: &SpanContext, command: &mut std::process::Command) {
+    if !span_context.is_valid() {
+        return;
+    }
+    command.env(TRACEPARENT_ENV, traceparent_string(span_context));
+    let trace_state = span_context.trace_state().header();
+    if trace_state.is_empty() {
+        command.env_remove(TRACESTATE_ENV);
+    } else {
+        command.env(TRACESTATE_ENV, trace_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn carrier(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn b3_single_round_trip() {
+        let propagator = B3Propagator::new();
+        let carrier = carrier(&[(
+            "b3",
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1",
+        )]);
+        let cx = propagator.extract(&carrier);
+        let sc = cx.span().span_context().clone();
+        assert!(sc.is_valid() && sc.is_sampled() && sc.is_remote());
+
+        let mut out: HashMap<String, String> = HashMap::new();
+        propagator.inject_context(&cx, &mut out);
+        assert_eq!(
+            out["b3"],
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1"
+        );
+    }
+
+    #[test]
+    fn b3_multi_headers_extract_and_inject() {
+        let propagator = B3Propagator::with_encoding(B3Encoding::Multi);
+        let carrier = carrier(&[
+            ("x-b3-traceid", "80f198ee56343ba864fe8b2a57d3eff7"),
+            ("x-b3-spanid", "e457b5a2e4d86bd1"),
+            ("x-b3-sampled", "1"),
+        ]);
+        let cx = propagator.extract(&carrier);
+        assert!(cx.span().span_context().is_sampled());
+
+        let mut out: HashMap<String, String> = HashMap::new();
+        propagator.inject_context(&cx, &mut out);
+        assert_eq!(out["x-b3-traceid"], "80f198ee56343ba864fe8b2a57d3eff7");
+        assert_eq!(out["x-b3-sampled"], "1");
+    }
+
+    #[test]
+    fn xray_round_trip() {
+        let propagator = XrayPropagator::new();
+        let carrier = carrier(&[(
+            "x-amzn-trace-id",
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1",
+        )]);
+        let cx = propagator.extract(&carrier);
+        let sc = cx.span().span_context().clone();
+        assert_eq!(
+            sc.trace_id().to_string(),
+            "5759e988bd862e3fe1be46a994272793"
+        );
+        assert_eq!(sc.span_id().to_string(), "53995c3f42cd8ad8");
+        assert!(sc.is_sampled());
+
+        let mut out: HashMap<String, String> = HashMap::new();
+        propagator.inject_context(&cx, &mut out);
+        assert_eq!(
+            out["x-amzn-trace-id"],
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1"
+        );
+    }
+
+    #[test]
+    fn traceparent_and_bytes_round_trip() {
+        let span_context = SpanContext::new(
+            TraceId::from_bytes(0x0af7651916cd43dd8448eb211c80319c_u128.to_be_bytes()),
+            SpanId::from_bytes(0xb7ad6b7169203331_u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+
+        let header = traceparent_string(&span_context);
+        assert_eq!(
+            header,
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+        );
+        let parsed = parse_traceparent(&header).unwrap();
+        assert_eq!(parsed.trace_id(), span_context.trace_id());
+        assert_eq!(parsed.span_id(), span_context.span_id());
+        assert!(parsed.is_sampled() && parsed.is_remote());
+
+        let bytes = span_context_to_bytes(&span_context);
+        let decoded = span_context_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.trace_id(), span_context.trace_id());
+        assert_eq!(decoded.span_id(), span_context.span_id());
+        assert!(decoded.is_sampled());
+
+        assert!(parse_traceparent("00-bad").is_none());
+        assert!(span_context_from_bytes(&bytes[..20]).is_none());
+        assert!(span_context_from_bytes(&[0u8; SPAN_CONTEXT_BYTES]).is_none());
+    }
+
+    #[test]
+    fn subprocess_context_injection_carries_baggage() {
+        use opentelemetry::baggage::BaggageExt as _;
+
+        let cx = Context::new()
+            .with_remote_span_context(SpanContext::new(
+                TraceId::from_bytes(0xabcd_u128.to_be_bytes()),
+                SpanId::from_bytes(0x1234_u64.to_be_bytes()),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ))
+            .with_baggage([opentelemetry::KeyValue::new("tenant.id", "acme")]);
+        let mut command = std::process::Command::new("true");
+        inject_subprocess_context(&cx, &mut command);
+
+        let envs: std::collections::HashMap<_, _> = command
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?.to_string(), v?.to_str()?.to_string())))
+            .collect();
+        assert!(envs.contains_key("TRACEPARENT"));
+        assert_eq!(envs["BAGGAGE"], "tenant.id=acme");
+
+        // Without baggage the variable is cleared rather than left stale.
+        let mut bare = std::process::Command::new("true");
+        inject_subprocess_context(
+            &Context::new().with_remote_span_context(SpanContext::new(
+                TraceId::from_bytes(1u128.to_be_bytes()),
+                SpanId::from_bytes(1u64.to_be_bytes()),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            )),
+            &mut bare,
+        );
+        // `env_remove` shows up as a None entry: cleared, not stale.
+        assert!(bare
+            .get_envs()
+            .all(|(k, v)| k.to_str() != Some("BAGGAGE") || v.is_none()));
+    }
+
+    #[test]
+    fn subprocess_env_injection_sets_w3c_variables() {
+        let span_context = SpanContext::new(
+            TraceId::from_bytes(0xabcd_u128.to_be_bytes()),
+            SpanId::from_bytes(0x1234_u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::from_key_value([("myvendor", "debug:1")]).unwrap(),
+        );
+        let mut command = std::process::Command::new("true");
+        inject_subprocess_env(&span_context, &mut command);
+
+        let envs: std::collections::HashMap<_, _> = command
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?.to_string(), v?.to_str()?.to_string())))
+            .collect();
+        assert_eq!(
+            envs["TRACEPARENT"],
+            traceparent_string(&span_context)
+        );
+        assert_eq!(envs["TRACESTATE"], "myvendor=debug:1");
+
+        // Invalid contexts leave the environment untouched.
+        let mut clean = std::process::Command::new("true");
+        inject_subprocess_env(&SpanContext::empty_context(), &mut clean);
+        assert_eq!(clean.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn invalid_headers_leave_context_untouched() {
+        let propagator = B3Propagator::new();
+        let cx = propagator.extract(&carrier(&[("b3", "not-a-context")]));
+        assert!(!cx.has_active_span());
+    }
+}
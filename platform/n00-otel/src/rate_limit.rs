@@ -0,0 +1,78 @@
+//! Per-callsite token-bucket rate limiting of span export (see
+//! [`OpenTelemetryLayer::with_span_rate_limit`]).
+//!
+//! [`OpenTelemetryLayer::with_span_rate_limit`]: crate::OpenTelemetryLayer::with_span_rate_limit
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tracing_core::callsite;
+
+pub(crate) struct SpanRateLimiter {
+    /// Sustained spans/second refill rate per callsite.
+    per_second: f64,
+    /// Bucket capacity: how many spans a callsite may export back-to-back.
+    burst: f64,
+    buckets: Mutex<HashMap<callsite::Identifier, TokenBucket>>,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl SpanRateLimiter {
+    pub(crate) fn new(per_second: f64, burst: u32) -> Self {
+        SpanRateLimiter {
+            per_second: per_second.max(0.0),
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a span from this callsite may be exported now. Suppressed
+    /// spans are counted against nothing; the bucket refills with time.
+    pub(crate) fn allow(&self, callsite: callsite::Identifier) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(callsite).or_insert(TokenBucket {
+            tokens: self.burst,
+            last_refill: now,
+        });
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.last_refill).as_secs_f64() * self.per_second)
+            .min(self.burst);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestCallsite;
+    impl callsite::Callsite for TestCallsite {
+        fn set_interest(&self, _: tracing_core::subscriber::Interest) {}
+        fn metadata(&self) -> &tracing_core::Metadata<'_> {
+            unimplemented!("only used as an identifier")
+        }
+    }
+    static CALLSITE: TestCallsite = TestCallsite;
+
+    #[test]
+    fn burst_then_throttle() {
+        let limiter = SpanRateLimiter::new(0.0, 3);
+        let id = || callsite::Identifier(&CALLSITE);
+        assert!(limiter.allow(id()));
+        assert!(limiter.allow(id()));
+        assert!(limiter.allow(id()));
+        assert!(!limiter.allow(id()));
+    }
+}
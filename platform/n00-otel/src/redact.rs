@@ -0,0 +1,254 @@
+//! PII redaction applied to attributes and events before export.
+//!
+//! Scope: the policy covers everything this layer exports — span and event
+//! attributes. Baggage is deliberately *not* covered: it is a propagation
+//! mechanism written by the application into outgoing headers, not data
+//! this layer exports, and rewriting it here would silently desynchronize
+//! the process from what it already sent downstream. Keep PII out of
+//! baggage at the call sites that set it.
+
+use opentelemetry::{KeyValue, Value};
+use regex::Regex;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// What happens to an attribute whose key matches a key rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedactionAction {
+    /// Replace the value with `[REDACTED]`. The default: shows the field
+    /// existed without leaking it.
+    #[default]
+    Mask,
+    /// Replace the value with a stable FNV-1a hash of its text, so equal
+    /// inputs stay joinable across spans and services without being
+    /// readable.
+    Hash,
+    /// Remove the attribute entirely; nothing in the export hints that the
+    /// field was recorded.
+    Drop,
+}
+
+/// A set of redaction rules applied to every span (and span event)
+/// attribute at export time; see
+/// [`OpenTelemetryLayer::with_redaction`].
+///
+/// Two rule shapes exist: key rules replace the whole value of matching
+/// keys, value rules rewrite matching substrings wherever they appear. Key
+/// rules are cheap (no scan of the value); value rules catch PII hiding in
+/// free-form text.
+///
+/// [`OpenTelemetryLayer::with_redaction`]: crate::OpenTelemetryLayer::with_redaction
+#[derive(Clone, Debug, Default)]
+pub struct RedactionPolicy {
+    key_rules: Vec<(String, RedactionAction)>,
+    value_patterns: Vec<Regex>,
+}
+
+impl RedactionPolicy {
+    /// An empty policy; add rules with the `redact_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A starter policy for the usual suspects: email addresses, bearer
+    /// tokens, and card-like digit runs in attribute values, plus any key
+    /// containing `password`, `secret` or `token`.
+    pub fn common_pii() -> Self {
+        Self::new()
+            .redact_keys("*password*")
+            .redact_keys("*secret*")
+            .redact_keys("*token*")
+            .redact_values(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}")
+            .redact_values(r"(?i)bearer\s+[a-z0-9._~+/-]+=*")
+            .redact_values(r"\b(?:\d[ -]?){13,19}\b")
+    }
+
+    /// Mask the whole value of attributes whose key matches `glob`
+    /// (`*` wildcards, case-sensitive). Shorthand for
+    /// [`redact_keys_with`](Self::redact_keys_with) with
+    /// [`RedactionAction::Mask`].
+    pub fn redact_keys(self, glob: impl Into<String>) -> Self {
+        self.redact_keys_with(glob, RedactionAction::Mask)
+    }
+
+    /// Apply `action` to attributes whose key matches `glob`. The first
+    /// matching rule wins.
+    pub fn redact_keys_with(mut self, glob: impl Into<String>, action: RedactionAction) -> Self {
+        self.key_rules.push((glob.into(), action));
+        self
+    }
+
+    /// Rewrite substrings matching `pattern` (a regex) to `[REDACTED]`
+    /// inside every string attribute value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid regex — redaction rules are
+    /// config, and silently dropping a broken one would leak the data it
+    /// was meant to cover.
+    pub fn redact_values(mut self, pattern: &str) -> Self {
+        self.value_patterns
+            .push(Regex::new(pattern).expect("invalid redaction value pattern"));
+        self
+    }
+
+    /// Whether any rules exist.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.key_rules.is_empty() && self.value_patterns.is_empty()
+    }
+
+    /// Apply the policy to an attribute list in place. Key rules run first
+    /// (first match wins); value patterns then rewrite the survivors.
+    pub(crate) fn apply(&self, attributes: &mut Vec<KeyValue>) {
+        attributes.retain_mut(|kv| {
+            if let Some((_, action)) = self
+                .key_rules
+                .iter()
+                .find(|(glob, _)| glob_matches(glob, kv.key.as_str()))
+            {
+                match action {
+                    RedactionAction::Mask => kv.value = Value::String(REDACTED.into()),
+                    RedactionAction::Hash => {
+                        kv.value = Value::String(fnv1a_hex(&kv.value.to_string()).into())
+                    }
+                    RedactionAction::Drop => return false,
+                }
+                return true;
+            }
+            if let Value::String(text) = &kv.value {
+                let mut redacted = std::borrow::Cow::Borrowed(text.as_str());
+                for pattern in &self.value_patterns {
+                    if pattern.is_match(&redacted) {
+                        redacted = std::borrow::Cow::Owned(
+                            pattern.replace_all(&redacted, REDACTED).into_owned(),
+                        );
+                    }
+                }
+                if let std::borrow::Cow::Owned(redacted) = redacted {
+                    kv.value = Value::String(redacted.into());
+                }
+            }
+            true
+        });
+    }
+}
+
+/// Stable 64-bit FNV-1a of the value text, hex-encoded. Deliberately not a
+/// keyed hash: the point is cross-span/cross-service joinability of equal
+/// inputs without readability.
+fn fnv1a_hex(text: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for byte in text.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(PRIME);
+    }
+    format!("{hash:016x}")
+}
+
+/// Minimal `*` glob match, anchored at both ends.
+fn glob_matches(glob: &str, key: &str) -> bool {
+    let segments: Vec<&str> = glob.split('*').collect();
+    let (first, rest) = segments.split_first().expect("split yields one segment");
+    if !key.starts_with(first) {
+        return false;
+    }
+    let mut position = first.len();
+    for (index, segment) in rest.iter().enumerate() {
+        let is_last = index == rest.len() - 1;
+        if segment.is_empty() {
+            // A trailing '*' (or consecutive '*') matches anything left.
+            if is_last {
+                return true;
+            }
+            continue;
+        }
+        if is_last {
+            // Without a trailing '*', the last segment must end the key.
+            return key[position..].ends_with(segment);
+        }
+        match key[position..].find(segment) {
+            Some(found) => position += found + segment.len(),
+            None => return false,
+        }
+    }
+    position == key.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_of(attrs: &[KeyValue], key: &str) -> Value {
+        attrs
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .unwrap()
+            .value
+            .clone()
+    }
+
+    #[test]
+    fn hash_and_drop_actions() {
+        let policy = RedactionPolicy::new()
+            .redact_keys_with("user.email", RedactionAction::Hash)
+            .redact_keys_with("session.cookie", RedactionAction::Drop);
+        let mut attrs = vec![
+            KeyValue::new("user.email", "a@example.com"),
+            KeyValue::new("session.cookie", "s3cr3t"),
+            KeyValue::new("kept", "plain"),
+        ];
+        policy.apply(&mut attrs);
+
+        // Drop removes the attribute entirely.
+        assert!(!attrs.iter().any(|kv| kv.key.as_str() == "session.cookie"));
+        // Hash is stable and unreadable, so equal inputs stay joinable.
+        let hashed = value_of(&attrs, "user.email");
+        assert_ne!(hashed, "a@example.com".into());
+        let mut again = vec![KeyValue::new("user.email", "a@example.com")];
+        RedactionPolicy::new()
+            .redact_keys_with("user.email", RedactionAction::Hash)
+            .apply(&mut again);
+        assert_eq!(value_of(&again, "user.email"), hashed);
+        assert_eq!(value_of(&attrs, "kept"), "plain".into());
+    }
+
+    #[test]
+    fn key_globs_redact_whole_values() {
+        let policy = RedactionPolicy::new().redact_keys("*password*");
+        let mut attrs = vec![
+            KeyValue::new("db.password", "hunter2"),
+            KeyValue::new("db.host", "localhost"),
+        ];
+        policy.apply(&mut attrs);
+        assert_eq!(value_of(&attrs, "db.password"), REDACTED.into());
+        assert_eq!(value_of(&attrs, "db.host"), "localhost".into());
+    }
+
+    #[test]
+    fn value_patterns_redact_substrings() {
+        let policy = RedactionPolicy::common_pii();
+        let mut attrs = vec![KeyValue::new(
+            "note",
+            "contact alice@example.com re: card 4111 1111 1111 1111",
+        )];
+        policy.apply(&mut attrs);
+        let Value::String(text) = value_of(&attrs, "note") else {
+            panic!("string expected");
+        };
+        assert!(!text.as_str().contains("alice@example.com"));
+        assert!(!text.as_str().contains("4111"));
+        assert!(text.as_str().contains("[REDACTED]"));
+    }
+
+    #[test]
+    fn globs_are_anchored() {
+        assert!(glob_matches("user.*", "user.email"));
+        assert!(!glob_matches("user.*x", "user.email"));
+        assert!(glob_matches("*.token", "auth.token"));
+        assert!(!glob_matches("*.token", "auth.token.kind"));
+        assert!(glob_matches("exact", "exact"));
+        assert!(!glob_matches("exact", "exactly"));
+    }
+}
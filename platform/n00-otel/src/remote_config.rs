@@ -0,0 +1,187 @@
+//! Remote configuration endpoint for the layer's dynamic filters.
+//!
+//! Exposes a [`DynamicTargets`] handle over a minimal HTTP/JSON service so
+//! operators can flip per-target levels on a running process:
+//!
+//! ```text
+//! GET  /v1/filters            -> {"default":"info","targets":{"app::db":"trace"}}
+//! PUT  /v1/filters            <- same document; replaces the configuration
+//! ```
+//!
+//! The wire surface is deliberately protocol-shaped like a service
+//! definition (one resource, get/replace) so a gRPC transport can be
+//! layered on without changing the model once the workspace adopts a gRPC
+//! stack; today the transport is plain HTTP/1.1 on a background thread,
+//! matching the crate's other endpoints.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tracing::level_filters::LevelFilter;
+
+use crate::DynamicTargets;
+
+/// A running remote-config endpoint; dropping it stops the server.
+pub struct RemoteConfigServer {
+    local_addr: std::net::SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl RemoteConfigServer {
+    /// The address the server is listening on (useful with port 0).
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for RemoteConfigServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Poke the listener out of `accept`.
+        let _ = std::net::TcpStream::connect(self.local_addr);
+    }
+}
+
+/// Serve the filter configuration of `targets` on `addr`.
+///
+/// ```no_run
+/// use tracing::level_filters::LevelFilter;
+///
+/// let targets = n00_otel::DynamicTargets::new(LevelFilter::INFO);
+/// let server = n00_otel::serve_filter_config("127.0.0.1:9099", targets).unwrap();
+/// println!("filter config on {}", server.local_addr());
+/// ```
+pub fn serve_filter_config(
+    addr: impl ToSocketAddrs,
+    targets: DynamicTargets,
+) -> std::io::Result<RemoteConfigServer> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let server_shutdown = shutdown.clone();
+
+    std::thread::Builder::new()
+        .name("n00-otel-remote-config".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                if server_shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(mut stream) = stream else { continue };
+                let _ = handle_connection(&mut stream, &targets);
+            }
+        })?;
+
+    Ok(RemoteConfigServer {
+        local_addr,
+        shutdown,
+    })
+}
+
+fn handle_connection(stream: &mut std::net::TcpStream, targets: &DynamicTargets) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    // Read until the headers (and, per content-length, the body) are in.
+    let (request, body_start) = loop {
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = find_header_end(&buffer) {
+            let headers = String::from_utf8_lossy(&buffer[..pos]).to_string();
+            let expected = content_length(&headers);
+            if buffer.len() - (pos + 4) >= expected {
+                break (headers, pos + 4);
+            }
+        }
+    };
+
+    let mut lines = request.lines();
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/v1/filters") => ("200 OK", snapshot_json(targets)),
+        ("PUT", "/v1/filters") | ("POST", "/v1/filters") => {
+            match apply_config(targets, &buffer[body_start..]) {
+                Ok(()) => ("200 OK", snapshot_json(targets)),
+                Err(message) => ("400 Bad Request", serde_json::json!({"error": message}).to_string()),
+            }
+        }
+        _ => ("404 Not Found", serde_json::json!({"error": "not found"}).to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    let _ = stream.shutdown(std::net::Shutdown::Write);
+    while matches!(stream.read(&mut chunk), Ok(n) if n > 0) {}
+    Ok(())
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|w| w == b"\r\n\r\n")
+}
+
+fn content_length(headers: &str) -> usize {
+    headers
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())
+                .flatten()
+        })
+        .unwrap_or(0)
+}
+
+fn snapshot_json(targets: &DynamicTargets) -> String {
+    let (default, entries) = targets.snapshot();
+    serde_json::json!({
+        "default": default.to_string().to_lowercase(),
+        "targets": entries
+            .into_iter()
+            .map(|(prefix, level)| {
+                (prefix, serde_json::Value::String(level.to_string().to_lowercase()))
+            })
+            .collect::<serde_json::Map<_, _>>(),
+    })
+    .to_string()
+}
+
+fn apply_config(targets: &DynamicTargets, body: &[u8]) -> Result<(), String> {
+    #[derive(serde::Deserialize)]
+    struct Config {
+        #[serde(default)]
+        default: Option<String>,
+        #[serde(default)]
+        targets: std::collections::HashMap<String, String>,
+    }
+
+    let config: Config =
+        serde_json::from_slice(body).map_err(|e| format!("invalid config document: {e}"))?;
+    let parse = |level: &str| -> Result<LevelFilter, String> {
+        level
+            .parse()
+            .map_err(|_| format!("unknown level {level:?}"))
+    };
+
+    // Validate everything before applying anything.
+    let default = config.default.as_deref().map(parse).transpose()?;
+    let entries: Vec<(String, LevelFilter)> = config
+        .targets
+        .into_iter()
+        .map(|(prefix, level)| Ok((prefix, parse(&level)?)))
+        .collect::<Result<_, String>>()?;
+
+    targets.replace(default, entries);
+    Ok(())
+}
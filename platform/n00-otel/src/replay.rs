@@ -0,0 +1,134 @@
+//! Synthesize OpenTelemetry spans from structured records.
+//!
+//! Some work cannot be instrumented while it runs — a batch job reporting
+//! per-item timings after the fact, a sidecar translating another system's
+//! execution log. [`SpanRecord`] describes such work as plain data and
+//! [`SpanRecord::replay`] exports it as real spans, with parenting, explicit
+//! timestamps, events and status intact.
+//!
+//! ```
+//! # use std::time::{Duration, SystemTime};
+//! use n00_otel::replay::SpanRecord;
+//! use opentelemetry::trace::{Status, TracerProvider as _};
+//! use opentelemetry_sdk::trace::SdkTracerProvider;
+//!
+//! # let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+//! let record = SpanRecord::new("import", start, start + Duration::from_secs(2))
+//!     .with_attribute(opentelemetry::KeyValue::new("items", 128))
+//!     .with_child(
+//!         SpanRecord::new("parse", start, start + Duration::from_millis(300))
+//!             .with_status(Status::error("bad row")),
+//!     );
+//!
+//! let provider = SdkTracerProvider::builder().build();
+//! record.replay(&provider.tracer("importer"), &opentelemetry::Context::new());
+//! ```
+
+use std::borrow::Cow;
+use std::time::SystemTime;
+
+use opentelemetry::trace::{
+    Event, SpanBuilder, SpanKind, Status, TraceContextExt, Tracer,
+};
+use opentelemetry::{Context, KeyValue};
+
+/// A span described as data, to be exported via [`replay`](Self::replay).
+#[derive(Clone, Debug)]
+pub struct SpanRecord {
+    /// Span name.
+    pub name: Cow<'static, str>,
+    /// Explicit start timestamp.
+    pub start_time: SystemTime,
+    /// Explicit end timestamp.
+    pub end_time: SystemTime,
+    /// Span kind; defaults to internal.
+    pub kind: Option<SpanKind>,
+    /// Span status; defaults to unset.
+    pub status: Status,
+    /// Span attributes.
+    pub attributes: Vec<KeyValue>,
+    /// Span events with their own timestamps.
+    pub events: Vec<Event>,
+    /// Child spans, replayed under this span's context.
+    pub children: Vec<SpanRecord>,
+}
+
+impl SpanRecord {
+    /// A record with the mandatory fields; everything else is added with
+    /// the `with_*` builders or directly on the public fields.
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        start_time: SystemTime,
+        end_time: SystemTime,
+    ) -> Self {
+        SpanRecord {
+            name: name.into(),
+            start_time,
+            end_time,
+            kind: None,
+            status: Status::Unset,
+            attributes: Vec::new(),
+            events: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Set the span kind.
+    pub fn with_kind(mut self, kind: SpanKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set the span status.
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a span attribute.
+    pub fn with_attribute(mut self, attribute: KeyValue) -> Self {
+        self.attributes.push(attribute);
+        self
+    }
+
+    /// Add an event with its own timestamp.
+    pub fn with_event(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        timestamp: SystemTime,
+        attributes: Vec<KeyValue>,
+    ) -> Self {
+        self.events.push(Event::new(name, timestamp, attributes, 0));
+        self
+    }
+
+    /// Add a child record, replayed under this span.
+    pub fn with_child(mut self, child: SpanRecord) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Export this record (and its children, recursively) through `tracer`
+    /// under `parent_cx`. IDs and sampling come from the tracer, so replayed
+    /// spans join live traces when given a live parent context.
+    pub fn replay<T>(&self, tracer: &T, parent_cx: &Context)
+    where
+        T: Tracer,
+        T::Span: Send + Sync + 'static,
+    {
+        let mut builder = SpanBuilder::from_name(self.name.clone())
+            .with_start_time(self.start_time)
+            .with_end_time(self.end_time)
+            .with_attributes(self.attributes.clone())
+            .with_status(self.status.clone());
+        builder.span_kind = self.kind.clone();
+        builder.events = Some(self.events.clone());
+
+        let span = tracer.build_with_context(builder, parent_cx);
+        let cx = parent_cx.with_span(span);
+        for child in &self.children {
+            child.replay(tracer, &cx);
+        }
+        // Dropping `cx` ends the span with the explicit end timestamp.
+    }
+}
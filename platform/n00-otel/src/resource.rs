@@ -0,0 +1,112 @@
+//! Process and system resource detection.
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::Resource;
+
+/// Detect process and host attributes into a [`Resource`], following the
+/// OpenTelemetry resource semantic conventions:
+///
+/// * `process.pid`, `process.executable.name`, `process.executable.path`,
+///   `process.command_args`
+/// * `process.runtime.name` (`rust`) and `process.runtime.version` (the
+///   compiler that built this binary)
+/// * `os.type`, `host.arch`, `host.name` (when discoverable)
+///
+/// Built on top of the SDK's default resource (so `OTEL_RESOURCE_ATTRIBUTES`
+/// and `OTEL_SERVICE_NAME` still apply); pass the result to the provider:
+///
+/// ```
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+///
+/// let provider = SdkTracerProvider::builder()
+///     .with_resource(n00_otel::process_resource("checkout"))
+///     .build();
+/// # drop(provider);
+/// ```
+pub fn process_resource(service_name: impl Into<opentelemetry::Value>) -> Resource {
+    let mut attributes = vec![KeyValue::new("process.pid", i64::from(std::process::id()))];
+
+    let exe = std::env::current_exe().ok();
+    if let Some(path) = exe.as_deref().and_then(|p| p.to_str()) {
+        attributes.push(KeyValue::new("process.executable.path", path.to_string()));
+    }
+    if let Some(name) = exe
+        .as_deref()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+    {
+        attributes.push(KeyValue::new("process.executable.name", name.to_string()));
+    }
+    let args: Vec<String> = std::env::args().collect();
+    if !args.is_empty() {
+        attributes.push(KeyValue::new("process.command_args", args.join(" ")));
+    }
+
+    attributes.push(KeyValue::new("process.runtime.name", "rust"));
+    attributes.push(KeyValue::new(
+        "process.runtime.version",
+        env!("N00_OTEL_RUSTC_VERSION"),
+    ));
+
+    attributes.push(KeyValue::new("os.type", std::env::consts::OS));
+    attributes.push(KeyValue::new("host.arch", std::env::consts::ARCH));
+    if let Some(host) = hostname() {
+        attributes.push(KeyValue::new("host.name", host));
+    }
+
+    Resource::builder()
+        .with_service_name(service_name)
+        .with_attributes(attributes)
+        .build()
+}
+
+/// Best-effort hostname: the conventional environment variables first, then
+/// `/etc/hostname` on unix.
+fn hostname() -> Option<String> {
+    for var in ["HOSTNAME", "COMPUTERNAME"] {
+        if let Ok(name) = std::env::var(var) {
+            if !name.is_empty() {
+                return Some(name);
+            }
+        }
+    }
+    if cfg!(unix) {
+        if let Ok(name) = std::fs::read_to_string("/etc/hostname") {
+            let name = name.trim();
+            if !name.is_empty() {
+                return Some(name.to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::Value;
+
+    #[test]
+    fn detects_process_attributes() {
+        let resource = process_resource("resource-test");
+        assert_eq!(
+            resource.get(&"service.name".into()),
+            Some(Value::from("resource-test"))
+        );
+        assert_eq!(
+            resource.get(&"process.pid".into()),
+            Some(Value::I64(i64::from(std::process::id())))
+        );
+        assert_eq!(
+            resource.get(&"os.type".into()),
+            Some(Value::from(std::env::consts::OS))
+        );
+        assert_eq!(
+            resource.get(&"process.runtime.name".into()),
+            Some(Value::from("rust"))
+        );
+        assert!(resource.get(&"process.runtime.version".into()).is_some());
+        // The test binary always has an executable name.
+        assert!(resource.get(&"process.executable.name".into()).is_some());
+    }
+}
@@ -0,0 +1,66 @@
+//! Tokio runtime metrics exported as OpenTelemetry observable gauges.
+//!
+//! [`observe_tokio_runtime`] registers callbacks on a [`Meter`] that sample
+//! [`tokio::runtime::RuntimeMetrics`] whenever the meter provider's reader
+//! collects, so a periodic reader turns them into periodic gauges with no
+//! polling task of our own.
+//!
+//! Enabled with the `tokio-metrics` cargo feature.
+//!
+//! ```no_run
+//! use opentelemetry::metrics::MeterProvider as _;
+//! # fn with_provider(provider: opentelemetry_sdk::metrics::SdkMeterProvider) {
+//! let meter = provider.meter("n00-otel");
+//! let _gauges = n00_otel::observe_tokio_runtime(&meter, tokio::runtime::Handle::current());
+//! # }
+//! ```
+
+use opentelemetry::metrics::{Meter, ObservableGauge};
+
+/// Keeps the registered gauges (and their sampling callbacks) alive.
+///
+/// Hold on to this for as long as the runtime should be observed.
+pub struct TokioRuntimeGauges {
+    _workers: ObservableGauge<u64>,
+    _alive_tasks: ObservableGauge<u64>,
+    _global_queue_depth: ObservableGauge<u64>,
+}
+
+/// Register gauges for the stable Tokio runtime metrics on `meter`:
+/// `tokio.runtime.workers`, `tokio.runtime.alive_tasks` and
+/// `tokio.runtime.global_queue_depth`.
+///
+/// The values are read from `handle` inside the meter's collection cycle;
+/// with a periodic reader this samples once per export interval.
+pub fn observe_tokio_runtime(meter: &Meter, handle: tokio::runtime::Handle) -> TokioRuntimeGauges {
+    let metrics = handle.metrics();
+    let workers = meter
+        .u64_observable_gauge("tokio.runtime.workers")
+        .with_description("Number of worker threads in the Tokio runtime")
+        .with_callback({
+            let metrics = metrics.clone();
+            move |observer| observer.observe(metrics.num_workers() as u64, &[])
+        })
+        .build();
+    let metrics = handle.metrics();
+    let alive_tasks = meter
+        .u64_observable_gauge("tokio.runtime.alive_tasks")
+        .with_description("Number of tasks currently alive in the Tokio runtime")
+        .with_callback({
+            let metrics = metrics.clone();
+            move |observer| observer.observe(metrics.num_alive_tasks() as u64, &[])
+        })
+        .build();
+    let metrics = handle.metrics();
+    let global_queue_depth = meter
+        .u64_observable_gauge("tokio.runtime.global_queue_depth")
+        .with_description("Number of tasks waiting in the runtime's global queue")
+        .with_callback(move |observer| observer.observe(metrics.global_queue_depth() as u64, &[]))
+        .build();
+
+    TokioRuntimeGauges {
+        _workers: workers,
+        _alive_tasks: alive_tasks,
+        _global_queue_depth: global_queue_depth,
+    }
+}
@@ -0,0 +1,69 @@
+//! Statement sanitization for database attributes.
+
+/// Replace SQL literals with `?` placeholders: quoted strings, and numbers
+/// that stand alone as values. Keywords, identifiers and structure are
+/// preserved, so the sanitized text still reads as the query shape.
+///
+/// ```
+/// assert_eq!(
+///     n00_otel::sanitize_sql("SELECT * FROM users WHERE email = 'a@b.c' AND age > 41"),
+///     "SELECT * FROM users WHERE email = ? AND age > ?"
+/// );
+/// ```
+pub fn sanitize_sql(statement: &str) -> String {
+    let mut out = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                // Consume the quoted literal, honoring doubled quotes.
+                let quote = c;
+                while let Some(inner) = chars.next() {
+                    if inner == quote {
+                        if chars.peek() == Some(&quote) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                out.push('?');
+            }
+            c if c.is_ascii_digit()
+                && !out
+                    .chars()
+                    .last()
+                    .is_some_and(|prev| prev.is_ascii_alphanumeric() || prev == '_') =>
+            {
+                // A number starting a token: swallow the rest of it.
+                while chars
+                    .peek()
+                    .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.' || *n == '_')
+                {
+                    chars.next();
+                }
+                out.push('?');
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_string_and_numeric_literals() {
+        assert_eq!(
+            sanitize_sql("INSERT INTO t (a, b) VALUES ('it''s', 42.5)"),
+            "INSERT INTO t (a, b) VALUES (?, ?)"
+        );
+        // Identifiers containing digits survive.
+        assert_eq!(
+            sanitize_sql("SELECT col2 FROM t2 WHERE x = 7"),
+            "SELECT col2 FROM t2 WHERE x = ?"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Compile-time constants for OpenTelemetry semantic convention attribute
+//! keys, plus typed builders for the common instrumentation shapes.
+//!
+//! Stringly-typed attribute keys drift (`http.method` vs
+//! `http.request.method`) and typos ship silently; constants make the
+//! compiler catch both. The subset here covers the conventions this
+//! workspace instruments — HTTP, database, RPC and messaging — not the full
+//! registry.
+
+use opentelemetry::KeyValue;
+
+/// HTTP request method (`GET`, `POST`, ...).
+pub const HTTP_REQUEST_METHOD: &str = "http.request.method";
+/// HTTP response status code.
+pub const HTTP_RESPONSE_STATUS_CODE: &str = "http.response.status_code";
+/// Matched route template (`/users/:id`).
+pub const HTTP_ROUTE: &str = "http.route";
+/// Full request URL.
+pub const URL_FULL: &str = "url.full";
+/// URL path component.
+pub const URL_PATH: &str = "url.path";
+/// Logical server hostname.
+pub const SERVER_ADDRESS: &str = "server.address";
+/// Logical server port.
+pub const SERVER_PORT: &str = "server.port";
+/// Value of the `User-Agent` header.
+pub const USER_AGENT_ORIGINAL: &str = "user_agent.original";
+/// Low-cardinality error class (`timeout`, exception type, status code).
+pub const ERROR_TYPE: &str = "error.type";
+
+/// Database system identifier (`postgresql`, `redis`, ...).
+pub const DB_SYSTEM: &str = "db.system";
+/// Database name.
+pub const DB_NAMESPACE: &str = "db.namespace";
+/// The database query text.
+pub const DB_QUERY_TEXT: &str = "db.query.text";
+/// The database operation (`SELECT`, `INSERT`, ...).
+pub const DB_OPERATION_NAME: &str = "db.operation.name";
+
+/// RPC system identifier (`grpc`, `jsonrpc`, ...).
+pub const RPC_SYSTEM: &str = "rpc.system";
+/// Full logical service name.
+pub const RPC_SERVICE: &str = "rpc.service";
+/// Method being called.
+pub const RPC_METHOD: &str = "rpc.method";
+/// Numeric gRPC status code.
+pub const RPC_GRPC_STATUS_CODE: &str = "rpc.grpc.status_code";
+
+/// Messaging system identifier (`kafka`, `rabbitmq`, `nats`, ...).
+pub const MESSAGING_SYSTEM: &str = "messaging.system";
+/// Destination (topic/queue) name.
+pub const MESSAGING_DESTINATION_NAME: &str = "messaging.destination.name";
+/// The messaging operation (`publish`, `receive`, `process`).
+pub const MESSAGING_OPERATION_TYPE: &str = "messaging.operation.type";
+
+/// Typed builder for HTTP span attributes.
+///
+/// ```
+/// use n00_otel::semconv::HttpAttributes;
+///
+/// let attrs = HttpAttributes::request("GET", "/users/42")
+///     .with_route("/users/:id")
+///     .with_status_code(200)
+///     .into_attributes();
+/// assert_eq!(attrs.len(), 4);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HttpAttributes {
+    method: Option<String>,
+    path: Option<String>,
+    route: Option<String>,
+    status_code: Option<i64>,
+    server_address: Option<String>,
+    server_port: Option<i64>,
+    user_agent: Option<String>,
+}
+
+impl HttpAttributes {
+    /// Attributes for a request with the given method and path.
+    pub fn request(method: impl Into<String>, path: impl Into<String>) -> Self {
+        HttpAttributes {
+            method: Some(method.into()),
+            path: Some(path.into()),
+            ..Default::default()
+        }
+    }
+
+    /// The matched (low-cardinality) route template.
+    pub fn with_route(mut self, route: impl Into<String>) -> Self {
+        self.route = Some(route.into());
+        self
+    }
+
+    /// The response status code.
+    pub fn with_status_code(mut self, status_code: u16) -> Self {
+        self.status_code = Some(i64::from(status_code));
+        self
+    }
+
+    /// The logical server address and port.
+    pub fn with_server(mut self, address: impl Into<String>, port: u16) -> Self {
+        self.server_address = Some(address.into());
+        self.server_port = Some(i64::from(port));
+        self
+    }
+
+    /// The original user agent string.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// The attribute list, ready for a span or
+    /// [`append_otel_attributes`](crate::append_otel_attributes).
+    pub fn into_attributes(self) -> Vec<KeyValue> {
+        let mut attrs = Vec::new();
+        if let Some(method) = self.method {
+            attrs.push(KeyValue::new(HTTP_REQUEST_METHOD, method));
+        }
+        if let Some(path) = self.path {
+            attrs.push(KeyValue::new(URL_PATH, path));
+        }
+        if let Some(route) = self.route {
+            attrs.push(KeyValue::new(HTTP_ROUTE, route));
+        }
+        if let Some(status_code) = self.status_code {
+            attrs.push(KeyValue::new(HTTP_RESPONSE_STATUS_CODE, status_code));
+        }
+        if let Some(address) = self.server_address {
+            attrs.push(KeyValue::new(SERVER_ADDRESS, address));
+        }
+        if let Some(port) = self.server_port {
+            attrs.push(KeyValue::new(SERVER_PORT, port));
+        }
+        if let Some(user_agent) = self.user_agent {
+            attrs.push(KeyValue::new(USER_AGENT_ORIGINAL, user_agent));
+        }
+        attrs
+    }
+}
+
+/// Typed builder for database client span attributes.
+#[derive(Clone, Debug, Default)]
+pub struct DbAttributes {
+    system: Option<String>,
+    namespace: Option<String>,
+    operation: Option<String>,
+    query_text: Option<String>,
+}
+
+impl DbAttributes {
+    /// Attributes for a statement against the given database system.
+    pub fn statement(system: impl Into<String>, query_text: impl Into<String>) -> Self {
+        DbAttributes {
+            system: Some(system.into()),
+            query_text: Some(query_text.into()),
+            ..Default::default()
+        }
+    }
+
+    /// The database (schema/keyspace) name.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// The operation name (`SELECT`, `INSERT`, ...).
+    pub fn with_operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    /// The attribute list.
+    pub fn into_attributes(self) -> Vec<KeyValue> {
+        let mut attrs = Vec::new();
+        if let Some(system) = self.system {
+            attrs.push(KeyValue::new(DB_SYSTEM, system));
+        }
+        if let Some(namespace) = self.namespace {
+            attrs.push(KeyValue::new(DB_NAMESPACE, namespace));
+        }
+        if let Some(operation) = self.operation {
+            attrs.push(KeyValue::new(DB_OPERATION_NAME, operation));
+        }
+        if let Some(query_text) = self.query_text {
+            attrs.push(KeyValue::new(DB_QUERY_TEXT, query_text));
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_builder_emits_semconv_keys() {
+        let attrs = HttpAttributes::request("GET", "/users/42")
+            .with_route("/users/:id")
+            .with_status_code(404)
+            .with_server("api.internal", 443)
+            .into_attributes();
+        let keys: Vec<&str> = attrs.iter().map(|kv| kv.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                HTTP_REQUEST_METHOD,
+                URL_PATH,
+                HTTP_ROUTE,
+                HTTP_RESPONSE_STATUS_CODE,
+                SERVER_ADDRESS,
+                SERVER_PORT,
+            ]
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use std::borrow::Cow;
+
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::{SpanContext, Status, TraceContextExt, TraceState};
+use opentelemetry::{Context, Key, KeyValue, Value};
+
+use crate::layer::WithContext;
+
+/// OpenTelemetry operations on a [`tracing::Span`].
+///
+/// These methods are no-ops unless an [`OpenTelemetryLayer`] is installed on
+/// the span's subscriber.
+///
+/// # Async usage
+///
+/// Every method here resolves the span through its subscriber and span ID,
+/// never through the thread-local "current span". In particular
+/// [`context`](Self::context) is safe to call on a span held across an
+/// `.await` without entering it first — entering a span inside a future is
+/// exactly the pattern that corrupts the current-span stack when the future
+/// is polled on another thread, and nothing in this trait requires it.
+///
+/// [`OpenTelemetryLayer`]: crate::OpenTelemetryLayer
+pub trait OpenTelemetrySpanExt {
+    /// Use the given OpenTelemetry context as the parent of this span,
+    /// replacing the parent recorded at creation. Typically used with a
+    /// context extracted from incoming request headers.
+    fn set_parent(&self, cx: Context);
+
+    /// The OpenTelemetry context of this span, with IDs allocated and the
+    /// sampling decision made if that has not happened yet.
+    ///
+    /// Does not require the span to be (or ever have been) entered, so it
+    /// can be called from any thread or task, including across `.await`
+    /// points; see the [trait docs](OpenTelemetrySpanExt#async-usage).
+    fn context(&self) -> Context;
+
+    /// Set an attribute on the OpenTelemetry span, bypassing `tracing`'s
+    /// static field registration.
+    fn set_attribute(&self, key: impl Into<Key>, value: impl Into<Value>);
+
+    /// Set the status of the OpenTelemetry span.
+    fn set_status(&self, status: Status);
+
+    /// Add a link to another span.
+    fn add_link(&self, cx: SpanContext);
+
+    /// Add a link to another span, with attributes describing the link.
+    fn add_link_with_attributes(&self, cx: SpanContext, attributes: Vec<KeyValue>);
+
+    /// Record an OpenTelemetry event on this span without going through a
+    /// `tracing` event.
+    fn add_event(&self, name: impl Into<Cow<'static, str>>, attributes: Vec<KeyValue>);
+
+    /// The W3C `tracestate` this span currently carries (forcing ID
+    /// allocation and sampling, like [`context`](Self::context)).
+    fn tracestate(&self) -> TraceState;
+
+    /// Attach an application-defined value to this span's subtree.
+    ///
+    /// Unlike attributes, context values are not exported; they are carried
+    /// in-process and inherited by child spans (copy-on-write), readable
+    /// anywhere via [`context_value`](Self::context_value) — per-request
+    /// state like a feature-flag set or an internal priority, without
+    /// threading it through call signatures.
+    fn set_context_value(&self, key: impl Into<String>, value: impl Into<Value>);
+
+    /// Read a context value visible to this span (its own or inherited).
+    fn context_value(&self, key: &str) -> Option<Value>;
+
+    /// Whether this span's trace is sampled, forcing the sampling decision
+    /// if it has not been made yet. `false` when no layer is installed.
+    ///
+    /// The cheap guard for expensive attribute computation; see
+    /// [`sampled_field!`](crate::sampled_field).
+    fn is_sampled(&self) -> bool;
+
+    /// Record a structured JSON value as attributes on this span, using the
+    /// flattening rules of [`json_attributes`](crate::json_attributes).
+    fn set_json_attribute(&self, key: &str, value: &serde_json::Value);
+
+    /// Extract a parent context from a carrier (e.g. incoming request
+    /// headers) and use it as this span's parent.
+    ///
+    /// Uses the subscriber's propagator when one was configured via
+    /// [`OpenTelemetryLayer::with_propagator`], falling back to the global
+    /// propagator otherwise.
+    ///
+    /// [`OpenTelemetryLayer::with_propagator`]: crate::OpenTelemetryLayer::with_propagator
+    fn set_parent_from(&self, carrier: &dyn Extractor);
+
+    /// Inject this span's context into a carrier (e.g. outgoing request
+    /// headers), using the subscriber's propagator when configured and the
+    /// global one otherwise.
+    fn inject_context(&self, carrier: &mut dyn Injector);
+
+    /// Replace the span's start timestamp.
+    ///
+    /// For spans that represent work which began before the span could be
+    /// created — replaying a queue entry, adopting an operation from another
+    /// system — so the exported timing reflects the operation rather than
+    /// the instrumentation.
+    fn set_start_time(&self, start: std::time::SystemTime);
+
+    /// Replace the span's end timestamp.
+    ///
+    /// Wins over both the measured close time and
+    /// [`set_duration`](Self::set_duration). Meant for close pipelines that
+    /// know when the work actually finished — e.g. a response was written
+    /// long before a slow exporter hook let the span close.
+    fn set_end_time(&self, end: std::time::SystemTime);
+
+    /// Export a fixed duration instead of the measured wall time.
+    ///
+    /// The exported end time becomes `start + duration` regardless of when
+    /// the span actually closes. Overrides measured timing but not an
+    /// explicit [`set_start_time`](Self::set_start_time).
+    fn set_duration(&self, duration: std::time::Duration);
+
+    /// Insert (or update) a `tracestate` entry on this span.
+    ///
+    /// The updated state is carried by the span's own context, inherited by
+    /// children created afterwards, and propagated on export. Returns
+    /// `false` if the key or value is not valid per the W3C spec, or if no
+    /// [`OpenTelemetryLayer`] is installed; the existing state is left
+    /// untouched in either case.
+    ///
+    /// [`OpenTelemetryLayer`]: crate::OpenTelemetryLayer
+    fn set_tracestate_entry(&self, key: &str, value: &str) -> bool;
+}
+
+impl OpenTelemetrySpanExt for tracing::Span {
+    fn set_parent(&self, cx: Context) {
+        let mut cx = Some(cx);
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some(cx) = cx.take() {
+                        data.parent_cx = cx;
+                        // Any previously made decision was based on the old
+                        // parent and must be redone.
+                        data.builder.trace_id = None;
+                        data.builder.sampling_result = None;
+                    }
+                });
+            }
+        });
+    }
+
+    fn context(&self) -> Context {
+        let mut cx = None;
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, tracer| {
+                    cx = Some(tracer.sampled_context(data));
+                });
+            }
+        });
+        cx.unwrap_or_default()
+    }
+
+    fn set_attribute(&self, key: impl Into<Key>, value: impl Into<Value>) {
+        let mut kv = Some(KeyValue::new(key.into(), value.into()));
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some(kv) = kv.take() {
+                        data.builder
+                            .attributes
+                            .get_or_insert_with(Vec::new)
+                            .push(kv);
+                    }
+                });
+            }
+        });
+    }
+
+    fn set_status(&self, status: Status) {
+        let mut status = Some(status);
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some(status) = status.take() {
+                        data.builder.status = status;
+                    }
+                });
+            }
+        });
+    }
+
+    fn add_link(&self, cx: SpanContext) {
+        self.add_link_with_attributes(cx, Vec::new())
+    }
+
+    fn add_link_with_attributes(&self, cx: SpanContext, attributes: Vec<KeyValue>) {
+        if !cx.is_valid() {
+            return;
+        }
+        let mut link = Some(opentelemetry::trace::Link::new(cx, attributes, 0));
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some(link) = link.take() {
+                        data.builder.links.get_or_insert_with(Vec::new).push(link);
+                    }
+                });
+            }
+        });
+    }
+
+    fn set_context_value(&self, key: impl Into<String>, value: impl Into<Value>) {
+        let mut pair = Some((key.into(), value.into()));
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some((key, value)) = pair.take() {
+                        let values = std::sync::Arc::make_mut(
+                            data.context_values.get_or_insert_with(Default::default),
+                        );
+                        values.insert(key, value);
+                    }
+                });
+            }
+        });
+    }
+
+    fn context_value(&self, key: &str) -> Option<Value> {
+        let mut found = None;
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, _tracer| {
+                    found = data
+                        .context_values
+                        .as_ref()
+                        .and_then(|values| values.get(key).cloned());
+                });
+            }
+        });
+        found
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.context().span().span_context().is_sampled()
+    }
+
+    fn set_json_attribute(&self, key: &str, value: &serde_json::Value) {
+        let mut attributes = Some(crate::json_attributes(key, value));
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, move |data, _tracer| {
+                    if let Some(attributes) = attributes.take() {
+                        data.builder
+                            .attributes
+                            .get_or_insert_with(Vec::new)
+                            .extend(attributes);
+                    }
+                });
+            }
+        });
+    }
+
+    fn set_parent_from(&self, carrier: &dyn Extractor) {
+        let mut propagator = None;
+        self.with_subscriber(|(_id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                propagator = get_context.propagator(subscriber);
+            }
+        });
+        let cx = match propagator {
+            Some(propagator) => propagator.extract(carrier),
+            None => opentelemetry::global::get_text_map_propagator(|p| p.extract(carrier)),
+        };
+        self.set_parent(cx);
+    }
+
+    fn inject_context(&self, carrier: &mut dyn Injector) {
+        let mut propagator = None;
+        self.with_subscriber(|(_id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                propagator = get_context.propagator(subscriber);
+            }
+        });
+        let cx = self.context();
+        match propagator {
+            Some(propagator) => propagator.inject_context(&cx, carrier),
+            None => {
+                opentelemetry::global::get_text_map_propagator(|p| p.inject_context(&cx, carrier))
+            }
+        }
+    }
+
+    fn set_start_time(&self, start: std::time::SystemTime) {
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, _tracer| {
+                    data.builder.start_time = Some(start);
+                });
+            }
+        });
+    }
+
+    fn set_end_time(&self, end: std::time::SystemTime) {
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, _tracer| {
+                    data.end_time_override = Some(end);
+                });
+            }
+        });
+    }
+
+    fn set_duration(&self, duration: std::time::Duration) {
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, _tracer| {
+                    data.duration_override = Some(duration);
+                });
+            }
+        });
+    }
+
+    fn tracestate(&self) -> TraceState {
+        self.context().span().span_context().trace_state().clone()
+    }
+
+    fn set_tracestate_entry(&self, key: &str, value: &str) -> bool {
+        let mut updated = false;
+        self.with_subscriber(|(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                get_context.with_context(subscriber, id, |data, tracer| {
+                    // Sampling owns the trace state; make sure it has run so
+                    // there is a state to edit.
+                    let _ = tracer.sampled_context(data);
+                    if let Some(sampling_result) = data.builder.sampling_result.as_mut() {
+                        if let Ok(trace_state) = sampling_result.trace_state.insert(
+                            key.to_string(),
+                            value.to_string(),
+                        ) {
+                            sampling_result.trace_state = trace_state;
+                            updated = true;
+                        }
+                    }
+                });
+            }
+        });
+        updated
+    }
+
+    fn add_event(&self, name: impl Into<Cow<'static, str>>, attributes: Vec<KeyValue>) {
+        let event =
+            opentelemetry::trace::Event::new(name.into(), crate::time::now(), attributes, 0);
+        self.with_subscriber(move |(id, subscriber)| {
+            if let Some(get_context) = subscriber.downcast_ref::<WithContext>() {
+                // Goes through the layer's event policies (caps, budget,
+                // backpressure) like any tracing event.
+                get_context.record_event(subscriber, id, event);
+            }
+        });
+    }
+}
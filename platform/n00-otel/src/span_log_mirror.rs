@@ -0,0 +1,67 @@
+//! Mirror exported spans onto the OpenTelemetry Logs signal.
+//!
+//! Some pipelines (SIEMs, long-retention archives) consume logs but not
+//! traces. With [`OpenTelemetryLayer::with_span_log_mirror`] every exported
+//! span additionally emits one log record carrying the span's name, status,
+//! duration and attributes, correlated to the trace via the record's trace
+//! context. Enabled with the `logs` cargo feature.
+//!
+//! [`OpenTelemetryLayer::with_span_log_mirror`]: crate::OpenTelemetryLayer::with_span_log_mirror
+
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger, LoggerProvider, Severity};
+use opentelemetry::trace::{SpanBuilder, Status, TraceFlags};
+use opentelemetry::Value;
+use opentelemetry_sdk::logs::{SdkLogger, SdkLoggerProvider};
+
+/// Emits one log record per exported span.
+pub(crate) struct SpanLogMirror {
+    logger: SdkLogger,
+}
+
+impl SpanLogMirror {
+    pub(crate) fn new(provider: &SdkLoggerProvider) -> Self {
+        SpanLogMirror {
+            logger: provider.logger("n00-otel-span-mirror"),
+        }
+    }
+
+    /// Emit the log mirror of a finished span builder.
+    pub(crate) fn emit(&self, builder: &SpanBuilder, target: &str) {
+        let mut record = self.logger.create_log_record();
+        record.set_event_name("span");
+        if let Some(end_time) = builder.end_time {
+            record.set_timestamp(end_time);
+        }
+        let severity = match &builder.status {
+            Status::Error { .. } => Severity::Error,
+            _ => Severity::Info,
+        };
+        record.set_severity_number(severity);
+        record.set_body(AnyValue::from(builder.name.to_string()));
+        record.add_attribute("target", target.to_string());
+        if let (Some(start), Some(end)) = (builder.start_time, builder.end_time) {
+            let elapsed = end.duration_since(start).unwrap_or_default();
+            record.add_attribute("span.duration_ms", elapsed.as_secs_f64() * 1_000.0);
+        }
+        if let Status::Error { description } = &builder.status {
+            record.add_attribute("span.status_message", description.to_string());
+        }
+        for kv in builder.attributes.as_deref().unwrap_or(&[]) {
+            record.add_attribute(kv.key.clone(), any_value(&kv.value));
+        }
+        if let (Some(trace_id), Some(span_id)) = (builder.trace_id, builder.span_id) {
+            record.set_trace_context(trace_id, span_id, Some(TraceFlags::SAMPLED));
+        }
+        self.logger.emit(record);
+    }
+}
+
+fn any_value(value: &Value) -> AnyValue {
+    match value {
+        Value::Bool(b) => AnyValue::Boolean(*b),
+        Value::I64(i) => AnyValue::Int(*i),
+        Value::F64(f) => AnyValue::Double(*f),
+        Value::String(s) => AnyValue::String(s.clone()),
+        other => AnyValue::String(other.to_string().into()),
+    }
+}
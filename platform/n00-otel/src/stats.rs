@@ -0,0 +1,84 @@
+//! Self-telemetry: counters about the layer's own behavior.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Shareable counters describing what the layer is doing, installed with
+/// [`OpenTelemetryLayer::with_stats`].
+///
+/// When spans stop arriving at the backend, the first question is whether
+/// the layer dropped them (rate limit, tail sampling, `otel.drop`) or the
+/// export path lost them; these counters answer the first half from inside
+/// the process. All increments are relaxed atomics on paths that already
+/// lock span extensions, so the overhead is noise.
+///
+/// [`OpenTelemetryLayer::with_stats`]: crate::OpenTelemetryLayer::with_stats
+#[derive(Clone, Debug, Default)]
+pub struct LayerStats {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    spans_started: AtomicU64,
+    spans_exported: AtomicU64,
+    spans_suppressed: AtomicU64,
+    events_recorded: AtomicU64,
+    events_dropped: AtomicU64,
+}
+
+/// A point-in-time copy of the counters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerStatsSnapshot {
+    /// Spans created.
+    pub spans_started: u64,
+    /// Spans handed to the tracer for export (heartbeats not included).
+    pub spans_exported: u64,
+    /// Spans finished but not exported (rate limit, tail sampling verdict,
+    /// `otel.drop`, aggregation fold).
+    pub spans_suppressed: u64,
+    /// Events buffered onto spans.
+    pub events_recorded: u64,
+    /// Events discarded by overflow policies.
+    pub events_dropped: u64,
+}
+
+impl LayerStats {
+    /// Fresh counters, all zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read all counters at once.
+    pub fn snapshot(&self) -> LayerStatsSnapshot {
+        LayerStatsSnapshot {
+            spans_started: self.inner.spans_started.load(Ordering::Relaxed),
+            spans_exported: self.inner.spans_exported.load(Ordering::Relaxed),
+            spans_suppressed: self.inner.spans_suppressed.load(Ordering::Relaxed),
+            events_recorded: self.inner.events_recorded.load(Ordering::Relaxed),
+            events_dropped: self.inner.events_dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn span_started(&self) {
+        self.inner.spans_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn span_exported(&self) {
+        self.inner.spans_exported.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn span_suppressed(&self) {
+        self.inner.spans_suppressed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "events")]
+    pub(crate) fn event_recorded(&self) {
+        self.inner.events_recorded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "events")]
+    pub(crate) fn event_dropped(&self) {
+        self.inner.events_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,51 @@
+//! Scoped suppression of instrumentation, for telemetry plumbing code.
+//!
+//! An exporter that logs through `tracing`, or an HTTP client shared
+//! between application and exporter, otherwise instruments its own export
+//! — every batch creates spans that create batches. While a
+//! [`suppress_tracing`] guard is alive on a thread, the layers ignore
+//! everything that thread does, and the OpenTelemetry SDK's own
+//! suppression flag is set so `build_with_context` is inert too.
+
+use std::cell::Cell;
+
+thread_local! {
+    static SUPPRESSION_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Whether instrumentation is suppressed on the current thread.
+pub fn is_suppressed() -> bool {
+    SUPPRESSION_DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// Suppress this thread's instrumentation until the guard drops.
+///
+/// Nests: suppression ends when the outermost guard drops.
+///
+/// ```
+/// fn export_batch() {
+///     let _guard = n00_otel::suppress_tracing();
+///     // Spans and events created here (e.g. by an HTTP client) are
+///     // ignored by the layer instead of re-entering the exporter.
+/// }
+/// ```
+pub fn suppress_tracing() -> SuppressionGuard {
+    SUPPRESSION_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    SuppressionGuard {
+        _otel: opentelemetry::Context::enter_telemetry_suppressed_scope(),
+        _not_send: std::marker::PhantomData,
+    }
+}
+
+/// Guard returned by [`suppress_tracing`]; releases suppression on drop.
+pub struct SuppressionGuard {
+    _otel: opentelemetry::ContextGuard,
+    /// Suppression is per-thread; the guard must not migrate.
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl Drop for SuppressionGuard {
+    fn drop(&mut self) {
+        SUPPRESSION_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
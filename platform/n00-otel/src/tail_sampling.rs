@@ -0,0 +1,203 @@
+//! Tail sampling: buffer finished spans per trace and decide at trace end
+//! whether to export the whole trace.
+//!
+//! Head sampling has to decide before anything has happened; tail sampling
+//! decides once the outcome is known, which is how "keep every failed trace
+//! but only 1% of successes" policies are expressed. The layer buffers spans
+//! as they close and flushes or discards the buffer when the trace's local
+//! root closes (see [`OpenTelemetryLayer::with_tail_sampling`]).
+//!
+//! [`OpenTelemetryLayer::with_tail_sampling`]: crate::OpenTelemetryLayer::with_tail_sampling
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::trace::{SpanBuilder, Status, TraceId};
+use opentelemetry::Context;
+
+/// What a tail-sampling policy gets to look at when a trace completes.
+///
+/// The summary is computed over the locally buffered spans of the trace;
+/// spans owned by other processes are not visible here.
+#[derive(Debug)]
+pub struct TraceSummary {
+    /// Name of the local root span that completed the trace.
+    pub root_name: String,
+
+    /// Number of locally buffered spans, the root included.
+    pub span_count: usize,
+
+    /// How many of those spans ended with [`Status::Error`].
+    pub error_count: usize,
+
+    /// Wall-clock duration of the local root span.
+    pub root_duration: std::time::Duration,
+}
+
+/// Policy deciding whether a completed trace is exported.
+pub type TailSamplingPolicy = Arc<dyn Fn(&TraceSummary) -> bool + Send + Sync>;
+
+/// A span held back until its trace completes.
+pub(crate) struct BufferedSpan {
+    pub(crate) parent_cx: Context,
+    pub(crate) builder: SpanBuilder,
+    /// The `tracing` target, for per-target tracer scoping at export.
+    pub(crate) target: &'static str,
+}
+
+/// The verdict for a span reaching the end of `on_close` while tail sampling
+/// is active.
+pub(crate) enum TailVerdict {
+    /// The span was buffered; nothing to export yet.
+    Buffered,
+    /// The trace completed (or the span is a post-decision straggler) and
+    /// these spans should be exported now.
+    Export(Vec<BufferedSpan>),
+    /// The policy rejected the trace (or rejected it earlier); drop the span.
+    Drop,
+}
+
+/// How many recently decided traces to remember, so spans that close after
+/// their local root (e.g. detached tasks) follow the trace's verdict instead
+/// of being buffered forever.
+const DECISION_CACHE_CAPACITY: usize = 1024;
+
+pub(crate) struct TailSamplingState {
+    policy: TailSamplingPolicy,
+    /// Upper bound across all per-trace buffers; when exceeded, the
+    /// largest buffered trace is flushed as-is ("fail open") to bound
+    /// memory.
+    max_buffered_spans: usize,
+    buffered_spans: AtomicUsize,
+    buffers: Mutex<HashMap<TraceId, Vec<BufferedSpan>>>,
+    decisions: Mutex<DecisionCache>,
+}
+
+struct DecisionCache {
+    order: VecDeque<TraceId>,
+    verdicts: HashMap<TraceId, bool>,
+}
+
+impl DecisionCache {
+    fn record(&mut self, trace_id: TraceId, sampled: bool) {
+        if self.verdicts.insert(trace_id, sampled).is_none() {
+            self.order.push_back(trace_id);
+            if self.order.len() > DECISION_CACHE_CAPACITY {
+                if let Some(evicted) = self.order.pop_front() {
+                    self.verdicts.remove(&evicted);
+                }
+            }
+        }
+    }
+
+    fn get(&self, trace_id: &TraceId) -> Option<bool> {
+        self.verdicts.get(trace_id).copied()
+    }
+}
+
+impl TailSamplingState {
+    pub(crate) fn new(policy: TailSamplingPolicy, max_buffered_spans: usize) -> Self {
+        TailSamplingState {
+            policy,
+            max_buffered_spans,
+            buffered_spans: AtomicUsize::new(0),
+            buffers: Mutex::new(HashMap::new()),
+            decisions: Mutex::new(DecisionCache {
+                order: VecDeque::new(),
+                verdicts: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Handle a finished span. `is_local_root` marks the span that completes
+    /// the trace from this process's point of view.
+    pub(crate) fn offer(
+        &self,
+        trace_id: TraceId,
+        span: BufferedSpan,
+        is_local_root: bool,
+    ) -> TailVerdict {
+        // Stragglers closing after the trace was decided follow the verdict.
+        if let Some(sampled) = self.decisions.lock().unwrap().get(&trace_id) {
+            return if sampled {
+                TailVerdict::Export(vec![span])
+            } else {
+                TailVerdict::Drop
+            };
+        }
+
+        if !is_local_root {
+            let mut buffers = self.buffers.lock().unwrap();
+            buffers.entry(trace_id).or_default().push(span);
+            drop(buffers);
+            if self.buffered_spans.fetch_add(1, Ordering::Relaxed) + 1 > self.max_buffered_spans {
+                return self.flush_largest();
+            }
+            return TailVerdict::Buffered;
+        }
+
+        let mut spans = self
+            .buffers
+            .lock()
+            .unwrap()
+            .remove(&trace_id)
+            .unwrap_or_default();
+        self.buffered_spans
+            .fetch_sub(spans.len(), Ordering::Relaxed);
+
+        let summary = TraceSummary {
+            root_name: span.builder.name.to_string(),
+            span_count: spans.len() + 1,
+            error_count: spans
+                .iter()
+                .map(|s| &s.builder)
+                .chain(std::iter::once(&span.builder))
+                .filter(|b| matches!(b.status, Status::Error { .. }))
+                .count(),
+            root_duration: root_duration(&span.builder),
+        };
+        let sampled = (self.policy)(&summary);
+        self.decisions.lock().unwrap().record(trace_id, sampled);
+
+        if sampled {
+            spans.push(span);
+            TailVerdict::Export(spans)
+        } else {
+            TailVerdict::Drop
+        }
+    }
+
+    /// Flush the largest buffered trace to get back under the memory
+    /// bound. Exporting (rather than discarding) keeps an overloaded
+    /// buffer from silently eating traces, and the largest trace frees the
+    /// most memory per eviction.
+    fn flush_largest(&self) -> TailVerdict {
+        let mut buffers = self.buffers.lock().unwrap();
+        let Some(trace_id) = buffers
+            .iter()
+            .max_by_key(|(_, spans)| spans.len())
+            .map(|(trace_id, _)| *trace_id)
+        else {
+            return TailVerdict::Buffered;
+        };
+        let spans = buffers.remove(&trace_id).unwrap_or_default();
+        drop(buffers);
+        self.buffered_spans
+            .fetch_sub(spans.len(), Ordering::Relaxed);
+        self.decisions.lock().unwrap().record(trace_id, true);
+        TailVerdict::Export(spans)
+    }
+}
+
+fn root_duration(builder: &SpanBuilder) -> std::time::Duration {
+    match (builder.start_time, builder.end_time) {
+        (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+        _ => std::time::Duration::ZERO,
+    }
+}
+
+/// Shorthand policy: keep traces that contain at least one error span.
+pub fn errors_only() -> impl Fn(&TraceSummary) -> bool + Send + Sync {
+    |summary: &TraceSummary| summary.error_count > 0
+}
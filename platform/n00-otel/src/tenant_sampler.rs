@@ -0,0 +1,136 @@
+//! Per-tenant sampling keyed by baggage or a span attribute.
+
+use std::collections::HashMap;
+
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+/// A [`ShouldSample`] choosing the sampling ratio per tenant.
+///
+/// The tenant is read from the span's attributes under the configured key
+/// (a `tenant.id = "acme"` span field ends up there) or, failing that, from
+/// the parent context's baggage under the same key — so a propagated
+/// `baggage: tenant.id=acme` header drives the decision without any local
+/// field. Unknown tenants get the default ratio.
+///
+/// ```
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+///
+/// let sampler = n00_otel::TenantSampler::new("tenant.id")
+///     .with_tenant_ratio("acme", 1.0)       // keep everything for acme
+///     .with_tenant_ratio("megacorp", 0.001) // megacorp is chatty
+///     .with_default_ratio(0.05);
+/// let provider = SdkTracerProvider::builder().with_sampler(sampler).build();
+/// # drop(provider);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TenantSampler {
+    key: String,
+    ratios: HashMap<String, f64>,
+    default_ratio: f64,
+}
+
+impl TenantSampler {
+    /// A sampler reading the tenant from attribute/baggage entry `key`,
+    /// with a default ratio of `1.0` until configured otherwise.
+    pub fn new(key: impl Into<String>) -> Self {
+        TenantSampler {
+            key: key.into(),
+            ratios: HashMap::new(),
+            default_ratio: 1.0,
+        }
+    }
+
+    /// Set the sampling ratio for one tenant.
+    pub fn with_tenant_ratio(mut self, tenant: impl Into<String>, ratio: f64) -> Self {
+        self.ratios.insert(tenant.into(), ratio);
+        self
+    }
+
+    /// Set the ratio applied to tenants without an explicit entry (and to
+    /// spans without any tenant information).
+    pub fn with_default_ratio(mut self, ratio: f64) -> Self {
+        self.default_ratio = ratio;
+        self
+    }
+
+    fn tenant_of(&self, parent_context: Option<&Context>, attributes: &[KeyValue]) -> Option<String> {
+        if let Some(kv) = attributes.iter().find(|kv| kv.key.as_str() == self.key) {
+            return Some(kv.value.to_string());
+        }
+        parent_context
+            .and_then(|cx| cx.baggage().get(self.key.as_str()))
+            .map(|value| value.to_string())
+    }
+}
+
+impl ShouldSample for TenantSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let ratio = self
+            .tenant_of(parent_context, attributes)
+            .and_then(|tenant| self.ratios.get(&tenant).copied())
+            .unwrap_or(self.default_ratio);
+        Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio))).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::SamplingDecision;
+
+    fn decide(sampler: &TenantSampler, attrs: &[KeyValue], cx: Option<&Context>) -> SamplingDecision {
+        sampler
+            .should_sample(
+                cx,
+                TraceId::from_bytes(7u128.to_be_bytes()),
+                "op",
+                &SpanKind::Internal,
+                attrs,
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn attribute_tenant_selects_ratio() {
+        let sampler = TenantSampler::new("tenant.id")
+            .with_tenant_ratio("acme", 1.0)
+            .with_default_ratio(0.0);
+        assert_eq!(
+            decide(&sampler, &[KeyValue::new("tenant.id", "acme")], None),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(
+            decide(&sampler, &[KeyValue::new("tenant.id", "other")], None),
+            SamplingDecision::Drop
+        );
+        assert_eq!(decide(&sampler, &[], None), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn baggage_tenant_is_a_fallback() {
+        let sampler = TenantSampler::new("tenant.id")
+            .with_tenant_ratio("acme", 1.0)
+            .with_default_ratio(0.0);
+        let cx = Context::new().with_baggage([KeyValue::new("tenant.id", "acme")]);
+        assert_eq!(decide(&sampler, &[], Some(&cx)), SamplingDecision::RecordAndSample);
+    }
+}
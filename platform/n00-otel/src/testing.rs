@@ -0,0 +1,265 @@
+//! Test harness for asserting on exported spans.
+//!
+//! Wires the pieces every trace test needs — an [`InMemorySpanExporter`], a
+//! simple-processor provider that stays alive for the harness's lifetime,
+//! and a ready-made subscriber — and adds lookup/assertion helpers over the
+//! exported [`SpanData`].
+//!
+//! ```
+//! use n00_otel::testing::{SpanDataExt, TestHarness};
+//!
+//! let harness = TestHarness::new();
+//! tracing::subscriber::with_default(harness.subscriber(), || {
+//!     tracing::info_span!("request", answer = 42).in_scope(|| {});
+//! });
+//!
+//! let span = harness.span("request");
+//! assert!(span.has_attribute("answer", 42));
+//! ```
+//!
+//! Enabled with the `testing` cargo feature.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::Value;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracer, SdkTracerProvider, SpanData};
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+use crate::{DeterministicIdGenerator, OpenTelemetryLayer};
+
+/// An in-memory trace pipeline for tests.
+///
+/// The harness owns the provider handle: dropping the last provider handle
+/// shuts down span processing even while tracers hold clones, so keep the
+/// harness alive until after the assertions.
+pub struct TestHarness {
+    exporter: InMemorySpanExporter,
+    provider: SdkTracerProvider,
+}
+
+impl Default for TestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestHarness {
+    /// A harness with the provider defaults (random IDs, always-on
+    /// sampling) and a simple span processor.
+    pub fn new() -> Self {
+        Self::with_provider(|builder| builder)
+    }
+
+    /// A harness whose provider uses a [`DeterministicIdGenerator`], for
+    /// golden tests that compare IDs.
+    pub fn deterministic() -> Self {
+        Self::with_provider(|builder| builder.with_id_generator(DeterministicIdGenerator::new()))
+    }
+
+    /// A harness exporting to a real OTLP/HTTP endpoint instead of memory,
+    /// for end-to-end tests against a collector. The in-memory view stays
+    /// attached too, so assertions keep working locally while the endpoint
+    /// receives the same spans.
+    ///
+    /// Typically driven by an environment variable so the test is a no-op
+    /// locally:
+    ///
+    /// ```no_run
+    /// use n00_otel::testing::TestHarness;
+    ///
+    /// let Some(endpoint) = std::env::var("N00_OTEL_TEST_OTLP_ENDPOINT").ok() else {
+    ///     return; // no collector in this environment
+    /// };
+    /// let harness = TestHarness::against_otlp(&endpoint);
+    /// # drop(harness);
+    /// ```
+    pub fn against_otlp(endpoint: &str) -> Self {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_simple_exporter(crate::OtlpHttpJsonExporter::new(endpoint))
+            .build();
+        TestHarness { exporter, provider }
+    }
+
+    /// A harness with extra provider configuration (sampler, limits, ...)
+    /// applied on top of the in-memory exporter.
+    pub fn with_provider<F>(configure: F) -> Self
+    where
+        F: FnOnce(
+            opentelemetry_sdk::trace::TracerProviderBuilder,
+        ) -> opentelemetry_sdk::trace::TracerProviderBuilder,
+    {
+        let exporter = InMemorySpanExporter::default();
+        let provider = configure(
+            SdkTracerProvider::builder().with_simple_exporter(exporter.clone()),
+        )
+        .build();
+        TestHarness { exporter, provider }
+    }
+
+    /// A tracer from the harness provider.
+    pub fn tracer(&self) -> SdkTracer {
+        self.provider_tracer("n00-otel-test")
+    }
+
+    /// A tracer from the harness provider under a specific scope name, for
+    /// tests exercising instrumentation scoping.
+    pub fn provider_tracer(&self, scope_name: &'static str) -> SdkTracer {
+        self.provider.tracer(scope_name)
+    }
+
+    /// The harness's provider, for building custom tracers against it.
+    pub fn provider(&self) -> &SdkTracerProvider {
+        &self.provider
+    }
+
+    /// An [`OpenTelemetryLayer`] wired to the harness tracer, for composing
+    /// into a custom subscriber stack (and for applying layer options).
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        crate::layer().with_tracer(self.tracer())
+    }
+
+    /// A ready-made `Registry` subscriber with the harness layer attached.
+    pub fn subscriber(&self) -> impl Subscriber + for<'span> LookupSpan<'span> {
+        Registry::default().with(self.layer())
+    }
+
+    /// All spans exported so far, in end order.
+    pub fn finished_spans(&self) -> Vec<SpanData> {
+        self.exporter
+            .get_finished_spans()
+            .expect("in-memory exporter lock poisoned")
+    }
+
+    /// The single exported span with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the list of exported span names if no span (or more than
+    /// one) matches — the failure output a test actually wants.
+    pub fn span(&self, name: &str) -> SpanData {
+        let spans = self.finished_spans();
+        let mut matching = spans.iter().filter(|s| s.name == name);
+        match (matching.next(), matching.next()) {
+            (Some(span), None) => span.clone(),
+            (None, _) => panic!(
+                "no span named {name:?} was exported; exported spans: {:?}",
+                spans.iter().map(|s| s.name.as_ref()).collect::<Vec<_>>()
+            ),
+            (Some(_), Some(_)) => panic!("multiple spans named {name:?} were exported"),
+        }
+    }
+
+    /// Clear the exported spans, e.g. between phases of one test.
+    pub fn reset(&self) {
+        self.exporter.reset();
+    }
+
+    /// A [`snapshot_json`] of everything exported so far.
+    pub fn snapshot(&self) -> String {
+        snapshot_json(&self.finished_spans())
+    }
+}
+
+/// Serialize exported spans into a stable JSON document for golden tests.
+///
+/// The output is deterministic across runs: trace/span IDs are replaced by
+/// `trace-N`/`span-N` placeholders in first-seen order, attributes are
+/// sorted by key, and timestamps are omitted. Parent links are expressed
+/// through the placeholder IDs, so topology changes show up in the diff.
+///
+/// Pretty-printed with a trailing newline, ready to compare against a
+/// checked-in `.json` fixture. Attribute *values* are taken as-is, so
+/// disable inherently unstable ones (e.g.
+/// `with_tracked_inactivity(false)` for `busy_ns`/`idle_ns`) in the layer
+/// under test.
+pub fn snapshot_json(spans: &[SpanData]) -> String {
+    use std::collections::HashMap;
+
+    let mut trace_ids = HashMap::new();
+    let mut span_ids = HashMap::new();
+    let mut trace_placeholder = |id: opentelemetry::trace::TraceId| {
+        let next = trace_ids.len() + 1;
+        trace_ids.entry(id).or_insert_with(|| format!("trace-{next}")).clone()
+    };
+    let mut span_placeholder = |id: opentelemetry::trace::SpanId| {
+        if id == opentelemetry::trace::SpanId::INVALID {
+            return "none".to_string();
+        }
+        let next = span_ids.len() + 1;
+        span_ids.entry(id).or_insert_with(|| format!("span-{next}")).clone()
+    };
+
+    let attrs_json = |attrs: &[opentelemetry::KeyValue]| {
+        let mut sorted: Vec<_> = attrs.iter().collect();
+        sorted.sort_by(|a, b| a.key.as_str().cmp(b.key.as_str()));
+        sorted
+            .into_iter()
+            .map(|kv| (kv.key.as_str().to_string(), serde_json::json!(kv.value.to_string())))
+            .collect::<serde_json::Map<_, _>>()
+    };
+
+    let spans_json: Vec<serde_json::Value> = spans
+        .iter()
+        .map(|span| {
+            serde_json::json!({
+                "name": span.name.as_ref(),
+                "trace_id": trace_placeholder(span.span_context.trace_id()),
+                "span_id": span_placeholder(span.span_context.span_id()),
+                "parent_span_id": span_placeholder(span.parent_span_id),
+                "kind": format!("{:?}", span.span_kind),
+                "status": format!("{:?}", span.status),
+                "attributes": attrs_json(&span.attributes),
+                "events": span
+                    .events
+                    .iter()
+                    .map(|event| {
+                        serde_json::json!({
+                            "name": event.name.as_ref(),
+                            "attributes": attrs_json(&event.attributes),
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let mut snapshot = serde_json::to_string_pretty(&spans_json).expect("spans serialize");
+    snapshot.push('\n');
+    snapshot
+}
+
+/// Assertion helpers over exported [`SpanData`].
+pub trait SpanDataExt {
+    /// The value of the attribute with the given key, if present.
+    fn attribute(&self, key: &str) -> Option<&Value>;
+
+    /// Whether the span carries the given attribute key/value pair.
+    fn has_attribute(&self, key: &str, value: impl Into<Value>) -> bool;
+
+    /// The names of the span's events, in order.
+    fn event_names(&self) -> Vec<&str>;
+}
+
+impl SpanDataExt for SpanData {
+    fn attribute(&self, key: &str) -> Option<&Value> {
+        self.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    fn has_attribute(&self, key: &str, value: impl Into<Value>) -> bool {
+        self.attribute(key) == Some(&value.into())
+    }
+
+    fn event_names(&self) -> Vec<&str> {
+        self.events.iter().map(|e| e.name.as_ref()).collect()
+    }
+}
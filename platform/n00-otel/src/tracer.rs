@@ -0,0 +1,164 @@
+use opentelemetry::trace::{
+    noop, SamplingDecision, SpanContext, SpanId, SpanKind, TraceContextExt, TraceFlags, TraceId,
+};
+use opentelemetry::Context;
+use opentelemetry_sdk::trace::SdkTracer;
+
+use crate::OtelData;
+
+/// A [`Tracer`] that can allocate span IDs and make sampling decisions before
+/// a span is built.
+///
+/// Spans are only handed to the underlying tracer when they close, but a
+/// span's context must be observable earlier: child spans need a parent
+/// context and [`OpenTelemetrySpanExt::context`] may be called at any time.
+/// Implementations pre-allocate IDs and run the sampler on demand, recording
+/// the results in the span's [`SpanBuilder`] so the eventual build reuses
+/// them.
+///
+/// [`Tracer`]: opentelemetry::trace::Tracer
+/// [`SpanBuilder`]: opentelemetry::trace::SpanBuilder
+/// [`OpenTelemetrySpanExt::context`]: crate::OpenTelemetrySpanExt::context
+pub trait PreSampledTracer {
+    /// Produce the OpenTelemetry context for the given (possibly unfinished)
+    /// span data, allocating IDs and sampling if that has not happened yet.
+    fn sampled_context(&self, data: &mut OtelData) -> Context;
+
+    /// Generate a new trace ID.
+    fn new_trace_id(&self) -> TraceId;
+
+    /// Generate a new span ID.
+    fn new_span_id(&self) -> SpanId;
+}
+
+impl PreSampledTracer for noop::NoopTracer {
+    fn sampled_context(&self, data: &mut OtelData) -> Context {
+        data.parent_cx.clone()
+    }
+
+    fn new_trace_id(&self) -> TraceId {
+        TraceId::INVALID
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        SpanId::INVALID
+    }
+}
+
+impl PreSampledTracer for SdkTracer {
+    fn sampled_context(&self, data: &mut OtelData) -> Context {
+        let OtelData {
+            parent_cx, builder, ..
+        } = data;
+
+        let parent_sc = parent_cx
+            .has_active_span()
+            .then(|| parent_cx.span().span_context().clone())
+            .filter(|sc| sc.is_valid());
+
+        let trace_id = parent_sc
+            .as_ref()
+            .map(|sc| sc.trace_id())
+            .or(builder.trace_id)
+            .unwrap_or_else(|| self.id_generator().new_trace_id());
+        builder.trace_id = Some(trace_id);
+
+        let span_id = *builder
+            .span_id
+            .get_or_insert_with(|| self.id_generator().new_span_id());
+
+        if builder.sampling_result.is_none() {
+            builder.sampling_result = Some(self.should_sample().should_sample(
+                Some(parent_cx),
+                trace_id,
+                &builder.name,
+                builder.span_kind.as_ref().unwrap_or(&SpanKind::Internal),
+                builder.attributes.as_deref().unwrap_or(&[]),
+                builder.links.as_deref().unwrap_or(&[]),
+            ));
+        }
+        let sampling_result = builder
+            .sampling_result
+            .as_ref()
+            .expect("sampling result was just set");
+
+        let trace_flags = if sampling_result.decision == SamplingDecision::RecordAndSample {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+        let trace_state = sampling_result.trace_state.clone();
+
+        let span_context = SpanContext::new(trace_id, span_id, trace_flags, false, trace_state);
+        parent_cx.with_span(SynthesizedSpan(span_context))
+    }
+
+    fn new_trace_id(&self) -> TraceId {
+        self.id_generator().new_trace_id()
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        self.id_generator().new_span_id()
+    }
+}
+
+/// A span placeholder carrying only a [`SpanContext`], used to parent child
+/// spans on a span that has not been built yet.
+#[derive(Debug)]
+pub(crate) struct SynthesizedSpan(pub(crate) SpanContext);
+
+impl opentelemetry::trace::Span for SynthesizedSpan {
+    fn add_event_with_timestamp<T>(
+        &mut self,
+        _name: T,
+        _timestamp: std::time::SystemTime,
+        _attributes: Vec<opentelemetry::KeyValue>,
+    ) where
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+    }
+
+    fn span_context(&self) -> &SpanContext {
+        &self.0
+    }
+
+    fn is_recording(&self) -> bool {
+        false
+    }
+
+    fn set_attribute(&mut self, _attribute: opentelemetry::KeyValue) {}
+
+    fn set_status(&mut self, _status: opentelemetry::trace::Status) {}
+
+    fn update_name<T>(&mut self, _new_name: T)
+    where
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+    }
+
+    fn add_link(&mut self, _span_context: SpanContext, _attributes: Vec<opentelemetry::KeyValue>) {}
+
+    fn end_with_timestamp(&mut self, _timestamp: std::time::SystemTime) {}
+}
+
+/// Build a tracer whose [`InstrumentationScope`] carries the given
+/// attributes, so every span it exports is tagged with scope-level metadata
+/// (library version, subsystem, deployment ring) without repeating it per
+/// span.
+///
+/// Combine with
+/// [`OpenTelemetryLayer::with_scoped_tracer`](crate::OpenTelemetryLayer::with_scoped_tracer)
+/// to give different module trees differently-attributed scopes.
+///
+/// [`InstrumentationScope`]: opentelemetry::InstrumentationScope
+pub fn scoped_tracer(
+    provider: &opentelemetry_sdk::trace::SdkTracerProvider,
+    name: impl Into<std::borrow::Cow<'static, str>>,
+    attributes: impl IntoIterator<Item = opentelemetry::KeyValue>,
+) -> SdkTracer {
+    use opentelemetry::trace::TracerProvider as _;
+    let scope = opentelemetry::InstrumentationScope::builder(name)
+        .with_attributes(attributes)
+        .build();
+    provider.tracer_with_scope(scope)
+}
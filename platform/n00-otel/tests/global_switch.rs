@@ -0,0 +1,54 @@
+//! The global enable/disable switch affects every OTel layer in the
+//! process, so this test lives in its own binary rather than racing the
+//! rest of the suite.
+
+use n00_otel::testing::TestHarness;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+#[test]
+fn global_switch_stops_span_export_without_touching_other_layers() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+    use tracing_subscriber::registry::LookupSpan;
+
+    #[derive(Default)]
+    struct FmtStandIn(std::sync::Arc<AtomicUsize>);
+    impl<S> Layer<S> for FmtStandIn
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, _event: &tracing::Event<'_>, _ctx: LayerContext<'_, S>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let fmt_stand_in = FmtStandIn::default();
+    let fmt_events = fmt_stand_in.0.clone();
+    let harness = TestHarness::new();
+    let subscriber = Registry::default().with(fmt_stand_in).with(harness.layer());
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("while_on").in_scope(|| {});
+
+        n00_otel::set_enabled(false);
+        assert!(!n00_otel::is_enabled());
+        tracing::info_span!("while_off").in_scope(|| tracing::info!("still logged"));
+
+        n00_otel::set_enabled(true);
+        tracing::info_span!("on_again").in_scope(|| {});
+    });
+
+    // Other layers kept observing while span export was off.
+    assert_eq!(fmt_events.load(Ordering::SeqCst), 1);
+
+    let names: Vec<String> = harness
+        .finished_spans()
+        .iter()
+        .map(|s| s.name.to_string())
+        .collect();
+    assert!(names.contains(&"while_on".to_string()));
+    assert!(!names.contains(&"while_off".to_string()));
+    assert!(names.contains(&"on_again".to_string()));
+}
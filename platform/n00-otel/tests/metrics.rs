@@ -0,0 +1,235 @@
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+fn metric_names(exporter: &InMemoryMetricExporter) -> Vec<String> {
+    exporter
+        .get_finished_metrics()
+        .unwrap()
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .map(|m| m.name().to_string())
+        .collect()
+}
+
+#[test]
+fn field_conventions_drive_all_four_instrument_kinds() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let subscriber =
+        Registry::default().with(n00_otel::MetricsLayer::new(provider.meter("metrics-test")));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(monotonic_counter.requests_total = 2);
+        tracing::info!(counter.active_connections = -1);
+        tracing::info!(histogram.request_duration_ms = 23.7);
+        tracing::info!(gauge.queue_depth = 42);
+        tracing::info!(plain_field = 1, "not a metric");
+    });
+    provider.force_flush().unwrap();
+
+    let names = metric_names(&exporter);
+    for expected in [
+        "requests_total",
+        "active_connections",
+        "request_duration_ms",
+        "queue_depth",
+    ] {
+        assert!(names.contains(&expected.to_string()), "{names:?}");
+    }
+    assert!(!names.iter().any(|n| n.contains("plain_field")));
+}
+
+#[test]
+fn metric_schema_validates_kinds_and_names() {
+    use n00_otel::{InstrumentKind, MetricSchema};
+
+    let schema = MetricSchema::new()
+        .declare(
+            InstrumentKind::Counter,
+            "requests_total",
+            Some("{request}"),
+            Some("Total requests handled"),
+        )
+        .declare(InstrumentKind::Histogram, "latency_ms", Some("ms"), None);
+
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let subscriber = Registry::default().with(
+        n00_otel::MetricsLayer::new(provider.meter("schema-test")).with_schema(schema.clone()),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(monotonic_counter.requests_total = 1); // declared
+        tracing::info!(histogram.latency_ms = 5.0); // declared
+        tracing::info!(monotonic_counter.latency_ms = 1.0); // wrong kind
+        tracing::info!(gauge.undeclared_depth = 9); // undeclared
+    });
+    provider.force_flush().unwrap();
+
+    let names = metric_names(&exporter);
+    assert!(names.contains(&"requests_total".to_string()));
+    assert!(names.contains(&"latency_ms".to_string()));
+    assert!(!names.contains(&"undeclared_depth".to_string()));
+    assert_eq!(schema.violation_count(), 2);
+}
+
+#[test]
+fn cardinality_guard_collapses_excess_attribute_sets() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let subscriber = Registry::default().with(
+        n00_otel::MetricsLayer::new(provider.meter("cardinality-test"))
+            .with_cardinality_limit(2),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        for user in ["a", "b", "c", "d"] {
+            tracing::info!(monotonic_counter.lookups = 1, user_id = user);
+        }
+    });
+    provider.force_flush().unwrap();
+
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let lookups = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .find(|m| m.name() == "lookups")
+        .expect("lookups metric");
+    let opentelemetry_sdk::metrics::data::AggregatedMetrics::F64(
+        opentelemetry_sdk::metrics::data::MetricData::Sum(sum),
+    ) = lookups.data()
+    else {
+        panic!("expected f64 sum");
+    };
+    let mut overflowed = 0.0;
+    let mut distinct_user_sets = 0;
+    for point in sum.data_points() {
+        let attrs: Vec<_> = point.attributes().collect();
+        if attrs
+            .iter()
+            .any(|kv| kv.key.as_str() == "otel.metric.overflow")
+        {
+            overflowed = point.value();
+        } else if attrs.iter().any(|kv| kv.key.as_str() == "user_id") {
+            distinct_user_sets += 1;
+        }
+    }
+    assert_eq!(distinct_user_sets, 2);
+    assert_eq!(overflowed, 2.0);
+}
+
+#[test]
+fn red_metrics_are_derived_from_finished_spans() {
+    use n00_otel::testing::TestHarness;
+
+    let exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+
+    let harness = TestHarness::new();
+    let subscriber = Registry::default().with(
+        harness
+            .layer()
+            .with_span_metrics(&meter_provider.meter("red-test")),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("handler").in_scope(|| {});
+        tracing::info_span!("handler", otel.status_code = "error").in_scope(|| {});
+    });
+    meter_provider.force_flush().unwrap();
+
+    let names = metric_names(&exporter);
+    assert!(names.contains(&"traces.span.calls".to_string()), "{names:?}");
+    assert!(names.contains(&"traces.span.duration".to_string()));
+}
+
+#[test]
+fn span_metric_histogram_buckets_are_configurable() {
+    use n00_otel::testing::TestHarness;
+
+    let exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+
+    let harness = TestHarness::new();
+    let boundaries = vec![1.0, 5.0, 25.0, 125.0];
+    let subscriber = Registry::default().with(harness.layer().with_span_metrics_buckets(
+        &meter_provider.meter("buckets-test"),
+        boundaries.clone(),
+    ));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("timed").in_scope(|| {});
+    });
+    meter_provider.force_flush().unwrap();
+
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let duration = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .find(|m| m.name() == "traces.span.duration")
+        .expect("duration histogram");
+    let opentelemetry_sdk::metrics::data::AggregatedMetrics::F64(
+        opentelemetry_sdk::metrics::data::MetricData::Histogram(histogram),
+    ) = duration.data()
+    else {
+        panic!("expected f64 histogram");
+    };
+    let point = histogram.data_points().next().expect("data point");
+    let bounds: Vec<f64> = point.bounds().collect();
+    assert_eq!(bounds, boundaries);
+}
+
+#[test]
+fn closure_backed_observable_gauges_are_polled_on_collection() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let layer = n00_otel::MetricsLayer::new(provider.meter("gauge-test"));
+
+    let depth = std::sync::Arc::new(AtomicU64::new(7));
+    let sampled = depth.clone();
+    layer.register_observable_gauge(
+        "work.queue_depth",
+        Some("{item}"),
+        Some("Items waiting in the work queue"),
+        move || sampled.load(Ordering::Relaxed) as f64,
+    );
+    let _subscriber = Registry::default().with(layer);
+
+    provider.force_flush().unwrap();
+    depth.store(11, Ordering::Relaxed);
+    provider.force_flush().unwrap();
+
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let values: Vec<f64> = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .filter(|m| m.name() == "work.queue_depth")
+        .filter_map(|m| match m.data() {
+            opentelemetry_sdk::metrics::data::AggregatedMetrics::F64(
+                opentelemetry_sdk::metrics::data::MetricData::Gauge(gauge),
+            ) => gauge.data_points().next().map(|p| p.value()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(values, vec![7.0, 11.0]);
+}
@@ -0,0 +1,27 @@
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tokio_runtime_gauges_are_collected() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+
+    let _gauges = n00_otel::observe_tokio_runtime(
+        &provider.meter("n00-otel-test"),
+        tokio::runtime::Handle::current(),
+    );
+    provider.force_flush().expect("collect metrics");
+
+    let metrics = exporter.get_finished_metrics().expect("exported metrics");
+    let names: Vec<String> = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .map(|m| m.name().to_string())
+        .collect();
+    assert!(names.contains(&"tokio.runtime.workers".to_string()), "{names:?}");
+    assert!(names.contains(&"tokio.runtime.alive_tasks".to_string()));
+    assert!(names.contains(&"tokio.runtime.global_queue_depth".to_string()));
+}
@@ -0,0 +1,41 @@
+use opentelemetry_sdk::logs::{InMemoryLogExporter, SdkLoggerProvider};
+
+#[test]
+fn spans_mirror_onto_the_logs_signal() {
+    use n00_otel::testing::TestHarness;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+
+    let log_exporter = InMemoryLogExporter::default();
+    let logger_provider = SdkLoggerProvider::builder()
+        .with_simple_exporter(log_exporter.clone())
+        .build();
+
+    let harness = TestHarness::new();
+    let subscriber = Registry::default().with(
+        harness
+            .layer()
+            .with_span_log_mirror(&logger_provider),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("mirrored", tier = "gold", otel.status_code = "error")
+            .in_scope(|| {});
+    });
+
+    let span = harness.span("mirrored");
+    let logs = log_exporter.get_emitted_logs().expect("emitted logs");
+    assert_eq!(logs.len(), 1);
+    let record = &logs[0].record;
+    assert_eq!(
+        record.trace_context().map(|cx| cx.trace_id),
+        Some(span.span_context.trace_id())
+    );
+    assert_eq!(
+        record.severity_number(),
+        Some(opentelemetry::logs::Severity::Error)
+    );
+    assert!(record
+        .attributes_iter()
+        .any(|(k, _)| k.as_str() == "span.duration_ms"));
+}
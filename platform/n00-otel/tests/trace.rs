@@ -0,0 +1,2706 @@
+use n00_otel::testing::TestHarness;
+use n00_otel::{EventOverflowPolicy, OpenTelemetryLayer, OpenTelemetrySpanExt};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+fn test_tracer<F>(configure: F) -> (impl Subscriber + for<'a> LookupSpan<'a>, TestHarness)
+where
+    F: FnOnce(
+        OpenTelemetryLayer<Registry, opentelemetry_sdk::trace::SdkTracer>,
+    ) -> OpenTelemetryLayer<Registry, opentelemetry_sdk::trace::SdkTracer>,
+{
+    let harness = TestHarness::new();
+    let layer = configure(harness.layer());
+    (Registry::default().with(layer), harness)
+}
+
+fn exported_spans(harness: &TestHarness) -> Vec<SpanData> {
+    harness.finished_spans()
+}
+
+#[test]
+fn exports_span_with_events() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("request", answer = 42);
+        let _enter = root.enter();
+        tracing::info!(step = 1, "first");
+        tracing::info!(step = 2, "second");
+    });
+
+    let spans = exported_spans(&harness);
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.name, "request");
+    assert_eq!(span.events.len(), 2);
+    assert!(span
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "answer" && kv.value == 42.into()));
+}
+
+#[test]
+fn parents_nested_spans() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("parent");
+        let _enter = root.enter();
+        tracing::info_span!("child").in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    assert_eq!(spans.len(), 2);
+    let child = spans.iter().find(|s| s.name == "child").unwrap();
+    let parent = spans.iter().find(|s| s.name == "parent").unwrap();
+    assert_eq!(
+        child.span_context.trace_id(),
+        parent.span_context.trace_id()
+    );
+    assert_eq!(child.parent_span_id, parent.span_context.span_id());
+}
+
+#[test]
+fn max_events_drop_newest_keeps_head() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_max_events_per_span(2)
+            .with_event_overflow_policy(EventOverflowPolicy::DropNewest)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("loop");
+        let _enter = span.enter();
+        for i in 0..5 {
+            tracing::info!(i, "tick");
+        }
+    });
+
+    let spans = exported_spans(&harness);
+    let span = &spans[0];
+    assert_eq!(span.events.len(), 2);
+    let dropped = span
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "otel.dropped_event_count")
+        .expect("dropped count attribute");
+    assert_eq!(dropped.value, 3.into());
+}
+
+#[test]
+fn max_events_drop_oldest_keeps_tail() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_max_events_per_span(2)
+            .with_event_overflow_policy(EventOverflowPolicy::DropOldest)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("loop");
+        let _enter = span.enter();
+        for i in 0..5 {
+            tracing::info!(i, "tick");
+        }
+    });
+
+    let spans = exported_spans(&harness);
+    let span = &spans[0];
+    assert_eq!(span.events.len(), 2);
+    let last = span.events.last().unwrap();
+    assert!(last
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "i" && kv.value == 4.into()));
+}
+
+#[test]
+fn max_events_summarize_only_counts() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_max_events_per_span(0)
+            .with_event_overflow_policy(EventOverflowPolicy::Summarize)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("loop");
+        let _enter = span.enter();
+        for _ in 0..3 {
+            tracing::info!("tick");
+        }
+    });
+
+    let spans = exported_spans(&harness);
+    let span = &spans[0];
+    assert!(span.events.is_empty());
+    let dropped = span
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "otel.dropped_event_count")
+        .expect("dropped count attribute");
+    assert_eq!(dropped.value, 3.into());
+}
+
+#[test]
+fn tail_events_keep_ring_of_most_recent() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_tail_events(3));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("work");
+        let _enter = span.enter();
+        for i in 0..10 {
+            tracing::info!(i, "step");
+        }
+        tracing::error!("boom");
+    });
+
+    let spans = exported_spans(&harness);
+    let span = &spans[0];
+    assert_eq!(span.events.len(), 3);
+    // The final events leading up to the failure survive; the head does not.
+    assert_eq!(span.events.last().unwrap().name, "boom");
+    assert!(span.events.iter().all(|e| {
+        e.attributes
+            .iter()
+            .all(|kv| !(kv.key.as_str() == "i" && kv.value == 0.into()))
+    }));
+}
+
+#[test]
+fn events_on_error_only_suppresses_happy_path() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_events_on_error_only(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("ok_span").in_scope(|| {
+            tracing::info!("noise");
+            tracing::info!("more noise");
+        });
+        let failed = tracing::info_span!("failed_span", otel.status_code = "error");
+        failed.in_scope(|| tracing::error!("boom"));
+    });
+
+    let spans = exported_spans(&harness);
+    let ok_span = spans.iter().find(|s| s.name == "ok_span").unwrap();
+    assert!(ok_span.events.is_empty());
+    let count = ok_span
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "otel.tracing_event_count")
+        .expect("event count attribute");
+    assert_eq!(count.value, 2.into());
+
+    let failed_span = spans.iter().find(|s| s.name == "failed_span").unwrap();
+    assert_eq!(failed_span.events.len(), 1);
+    assert_eq!(failed_span.events[0].name, "boom");
+}
+
+#[test]
+fn events_export_predicate_controls_attachment() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.with_events_export_predicate(|builder| builder.name.contains("keep"))
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("keep_me").in_scope(|| tracing::info!("kept"));
+        tracing::info_span!("drop_me").in_scope(|| tracing::info!("dropped"));
+    });
+
+    let spans = exported_spans(&harness);
+    let kept = spans.iter().find(|s| s.name == "keep_me").unwrap();
+    assert_eq!(kept.events.len(), 1);
+    let dropped = spans.iter().find(|s| s.name == "drop_me").unwrap();
+    assert!(dropped.events.is_empty());
+}
+
+#[test]
+fn capture_events_field_overrides_export_filter() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_events_on_error_only(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        // Opted in via the reserved field: events export despite a clean end.
+        let verbose = tracing::info_span!("verbose", otel.capture_events = "debug");
+        let _enter = verbose.enter();
+        tracing::info!("kept");
+        // Children inherit the override from the subtree root.
+        tracing::info_span!("verbose_child").in_scope(|| tracing::info!("kept too"));
+    });
+
+    let spans = exported_spans(&harness);
+    let verbose = spans.iter().find(|s| s.name == "verbose").unwrap();
+    assert_eq!(verbose.events.len(), 1);
+    let child = spans.iter().find(|s| s.name == "verbose_child").unwrap();
+    assert_eq!(child.events.len(), 1);
+}
+
+#[test]
+fn capture_events_off_suppresses_events() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let quiet = tracing::info_span!("quiet", otel.capture_events = "off");
+        quiet.in_scope(|| tracing::info!("unwanted"));
+    });
+
+    let spans = exported_spans(&harness);
+    let quiet = spans.iter().find(|s| s.name == "quiet").unwrap();
+    assert!(quiet.events.is_empty());
+    assert!(quiet
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "otel.tracing_event_count" && kv.value == 1.into()));
+}
+
+#[test]
+fn tracestate_debug_flag_enables_events_for_trace() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_events_on_error_only(true)
+            .with_tracestate_debug_flag("myvendor", "debug:1")
+    });
+
+    let remote = |trace_state: TraceState| {
+        opentelemetry::Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_bytes(0xface_u128.to_be_bytes()),
+            SpanId::from_bytes(0xbeef_u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            true,
+            trace_state,
+        ))
+    };
+
+    tracing::subscriber::with_default(subscriber, || {
+        let flagged = tracing::info_span!("flagged");
+        flagged.set_parent(remote(
+            TraceState::from_key_value([("myvendor", "debug:1")]).unwrap(),
+        ));
+        flagged.in_scope(|| tracing::info!("debug detail"));
+
+        let unflagged = tracing::info_span!("unflagged");
+        unflagged.set_parent(remote(TraceState::default()));
+        unflagged.in_scope(|| tracing::info!("suppressed"));
+    });
+
+    let spans = exported_spans(&harness);
+    let flagged = spans.iter().find(|s| s.name == "flagged").unwrap();
+    assert_eq!(flagged.events.len(), 1);
+    let unflagged = spans.iter().find(|s| s.name == "unflagged").unwrap();
+    assert!(unflagged.events.is_empty());
+}
+
+#[test]
+fn tail_sampling_keeps_only_failed_traces() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_tail_sampling(n00_otel::tail_sampling::errors_only(), 128));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let ok_root = tracing::info_span!("ok_root");
+        ok_root.in_scope(|| {
+            tracing::info_span!("ok_child").in_scope(|| {});
+        });
+
+        let bad_root = tracing::info_span!("bad_root");
+        bad_root.in_scope(|| {
+            tracing::info_span!("bad_child", otel.status_code = "error").in_scope(|| {});
+        });
+    });
+
+    let spans = exported_spans(&harness);
+    let names: Vec<_> = spans.iter().map(|s| s.name.as_ref()).collect();
+    assert!(names.contains(&"bad_root"));
+    assert!(names.contains(&"bad_child"));
+    assert!(!names.contains(&"ok_root"));
+    assert!(!names.contains(&"ok_child"));
+}
+
+#[test]
+fn tail_sampling_summary_sees_whole_trace() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen_in_policy = seen.clone();
+    let (subscriber, harness) = test_tracer(move |layer| {
+        layer.with_tail_sampling(
+            move |summary: &n00_otel::TraceSummary| {
+                seen_in_policy
+                    .lock()
+                    .unwrap()
+                    .push((summary.root_name.clone(), summary.span_count));
+                true
+            },
+            128,
+        )
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("root");
+        root.in_scope(|| {
+            tracing::info_span!("a").in_scope(|| {});
+            tracing::info_span!("b").in_scope(|| {});
+        });
+    });
+
+    assert_eq!(exported_spans(&harness).len(), 3);
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.as_slice(), &[("root".to_string(), 3)]);
+}
+
+#[test]
+fn span_rate_limit_caps_exports_per_callsite() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_span_rate_limit(0.0, 2));
+
+    tracing::subscriber::with_default(subscriber, || {
+        for _ in 0..10 {
+            tracing::info_span!("hot").in_scope(|| {});
+        }
+        // A different callsite has its own bucket.
+        tracing::info_span!("cold").in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    assert_eq!(spans.iter().filter(|s| s.name == "hot").count(), 2);
+    assert_eq!(spans.iter().filter(|s| s.name == "cold").count(), 1);
+}
+
+#[test]
+fn deterministic_ids_make_traces_reproducible() {
+    let run = || {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_id_generator(n00_otel::DeterministicIdGenerator::new())
+            .build();
+        let layer = n00_otel::layer().with_tracer(provider.tracer("test"));
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("root").in_scope(|| {
+                tracing::info_span!("child").in_scope(|| {});
+            });
+        });
+        exporter
+            .get_finished_spans()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                (
+                    s.name.to_string(),
+                    s.span_context.trace_id(),
+                    s.span_context.span_id(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = run();
+    let second = run();
+    assert_eq!(first, second);
+    assert!(!first.is_empty());
+}
+
+#[test]
+fn snapshot_json_is_stable_across_runs() {
+    let run = || {
+        let (subscriber, harness) = test_tracer(|layer| layer.with_tracked_inactivity(false));
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("root", tier = "gold");
+            root.in_scope(|| {
+                tracing::info_span!("child").in_scope(|| tracing::info!("step done"));
+            });
+        });
+        harness.snapshot()
+    };
+
+    let snapshot = run();
+    assert_eq!(snapshot, run());
+    // Placeholders, not raw IDs.
+    assert!(snapshot.contains("\"trace-1\""));
+    assert!(snapshot.contains("\"parent_span_id\": \"none\""));
+    assert!(snapshot.contains("\"step done\""));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn context_works_across_await_without_entering() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("async_root");
+    let before = span.context();
+
+    // Cross an await point (and likely a worker thread) without entering.
+    tokio::task::yield_now().await;
+
+    let after = span.context();
+    let sc = |cx: &opentelemetry::Context| TraceContextExt::span(cx).span_context().clone();
+    assert!(sc(&before).is_valid());
+    assert_eq!(sc(&before), sc(&after));
+
+    // The context parents children entered on another task. (The child span
+    // is created here because `set_default` only covers this thread.)
+    let child_cx = span.context();
+    let child = tracing::info_span!("async_child");
+    tokio::spawn(async move {
+        child.set_parent(child_cx);
+        child.in_scope(|| {});
+    })
+    .await
+    .unwrap();
+    drop(span);
+
+    let spans = exported_spans(&harness);
+    let root = spans.iter().find(|s| s.name == "async_root").unwrap();
+    let child = spans.iter().find(|s| s.name == "async_child").unwrap();
+    assert_eq!(child.span_context.trace_id(), root.span_context.trace_id());
+    assert_eq!(child.parent_span_id, root.span_context.span_id());
+}
+
+#[test]
+fn third_party_layer_can_read_otel_data() {
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+    #[derive(Default)]
+    struct TraceIdSpy {
+        seen: std::sync::Arc<std::sync::Mutex<Vec<Option<TraceId>>>>,
+    }
+
+    impl<S> Layer<S> for TraceIdSpy
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, event: &tracing::Event<'_>, ctx: LayerContext<'_, S>) {
+            if let Some(span) = ctx.event_span(event) {
+                let trace_id = n00_otel::with_otel_data(&span, |data| data.builder.trace_id);
+                self.seen.lock().unwrap().push(trace_id.flatten());
+            }
+        }
+    }
+
+    let spy = TraceIdSpy::default();
+    let seen = spy.seen.clone();
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    // The spy sits on top of the OTel layer in the same registry.
+    let subscriber = subscriber.with(spy);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("spied");
+        // Force ID allocation the way a child or context access would.
+        let _ = root.context();
+        root.in_scope(|| tracing::info!("observed"));
+    });
+
+    let spans = exported_spans(&harness);
+    let root = spans.iter().find(|s| s.name == "spied").unwrap();
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.as_slice(), &[Some(root.span_context.trace_id())]);
+}
+
+#[test]
+fn third_party_layer_can_append_attributes() {
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+    struct TenantTagger;
+
+    impl<S> Layer<S> for TenantTagger
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            _attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: LayerContext<'_, S>,
+        ) {
+            let span = ctx.span(id).expect("span exists");
+            assert!(n00_otel::append_otel_attributes(
+                &span,
+                [opentelemetry::KeyValue::new("tenant.id", "acme")],
+            ));
+        }
+    }
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    // Ordered above the OTel layer so its on_new_span runs after OtelData
+    // exists.
+    let subscriber = subscriber.with(TenantTagger);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("tagged").in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    let span = spans.iter().find(|s| s.name == "tagged").unwrap();
+    assert!(span
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "tenant.id" && kv.value == "acme".into()));
+}
+
+#[test]
+fn otel_data_access_trait_reads_pending_state() {
+    use n00_otel::OtelDataAccess;
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+    #[derive(Default)]
+    struct Snapshot {
+        seen: std::sync::Arc<std::sync::Mutex<Option<(String, usize, bool)>>>,
+    }
+
+    impl<S> Layer<S> for Snapshot
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_close(&self, id: tracing::span::Id, ctx: LayerContext<'_, S>) {
+            let span = ctx.span(&id).expect("span exists");
+            if let Some(seen) = n00_otel::with_otel_data(&span, |data| {
+                (
+                    data.name().to_string(),
+                    data.event_count(),
+                    data.attributes().iter().any(|kv| kv.key.as_str() == "tier"),
+                )
+            }) {
+                *self.seen.lock().unwrap() = Some(seen);
+            }
+        }
+    }
+
+    let snapshot = Snapshot::default();
+    let seen = snapshot.seen.clone();
+    // on_close notifications run inner layers first and the OTel layer
+    // removes OtelData in its on_close, so an observer that wants close-time
+    // access must sit below it in the stack.
+    let harness = TestHarness::new();
+    let subscriber = Registry::default().with(snapshot).with(harness.layer());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("renamed", tier = "gold", otel.name = "final_name");
+        span.in_scope(|| tracing::info!("one event"));
+    });
+
+    assert_eq!(
+        seen.lock().unwrap().take(),
+        Some(("final_name".to_string(), 1, true))
+    );
+}
+
+#[test]
+fn tracestate_entries_propagate_to_children_and_export() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("ts_root");
+        assert!(root.set_tracestate_entry("myvendor", "debug:1"));
+        assert!(!root.set_tracestate_entry("INVALID KEY", "x"));
+        assert_eq!(root.tracestate().get("myvendor"), Some("debug:1"));
+
+        root.in_scope(|| {
+            let child = tracing::info_span!("ts_child");
+            assert_eq!(child.tracestate().get("myvendor"), Some("debug:1"));
+            child.in_scope(|| {});
+        });
+    });
+
+    let spans = exported_spans(&harness);
+    let root = spans.iter().find(|s| s.name == "ts_root").unwrap();
+    assert_eq!(
+        root.span_context.trace_state().get("myvendor"),
+        Some("debug:1")
+    );
+}
+
+#[test]
+fn start_time_and_duration_overrides_shape_exported_timing() {
+    use std::time::{Duration, SystemTime};
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("replayed");
+        span.set_start_time(start);
+        span.set_duration(Duration::from_millis(250));
+        span.in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    let span = spans.iter().find(|s| s.name == "replayed").unwrap();
+    assert_eq!(span.start_time, start);
+    assert_eq!(span.end_time, start + Duration::from_millis(250));
+}
+
+#[test]
+fn replayed_records_export_as_parented_spans() {
+    use n00_otel::replay::SpanRecord;
+    use std::time::{Duration, SystemTime};
+
+    let harness = TestHarness::new();
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    let record = SpanRecord::new("job", start, start + Duration::from_secs(3))
+        .with_attribute(opentelemetry::KeyValue::new("items", 2))
+        .with_event("checkpoint", start + Duration::from_secs(1), vec![])
+        .with_child(SpanRecord::new(
+            "item",
+            start,
+            start + Duration::from_millis(40),
+        ));
+    record.replay(&harness.tracer(), &opentelemetry::Context::new());
+
+    let spans = harness.finished_spans();
+    assert_eq!(spans.len(), 2);
+    let job = spans.iter().find(|s| s.name == "job").unwrap();
+    let item = spans.iter().find(|s| s.name == "item").unwrap();
+    assert_eq!(job.start_time, start);
+    assert_eq!(job.end_time, start + Duration::from_secs(3));
+    assert_eq!(job.events.len(), 1);
+    assert_eq!(item.parent_span_id, job.span_context.span_id());
+    assert_eq!(item.span_context.trace_id(), job.span_context.trace_id());
+}
+
+#[test]
+fn scoped_tracers_map_targets_to_instrumentation_scopes() {
+    let harness = TestHarness::new();
+    let layer = harness
+        .layer()
+        .with_scoped_tracer("db", harness.provider_tracer("db-client"));
+    let subscriber = Registry::default().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!(target: "db::pool", "query").in_scope(|| {});
+        tracing::info_span!("handler").in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    let query = spans.iter().find(|s| s.name == "query").unwrap();
+    let handler = spans.iter().find(|s| s.name == "handler").unwrap();
+    assert_eq!(query.instrumentation_scope.name(), "db-client");
+    assert_eq!(handler.instrumentation_scope.name(), "n00-otel-test");
+}
+
+#[test]
+fn late_recorded_fields_export_like_creation_time_ones() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        // The parent computes values after the child span already exists.
+        let child = tracing::info_span!(
+            "work_item",
+            result = tracing::field::Empty,
+            otel.name = tracing::field::Empty
+        );
+        child.record("result", "deduplicated");
+        child.record("otel.name", "work_item_final");
+        child.in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    let span = spans.iter().find(|s| s.name == "work_item_final").unwrap();
+    assert!(span
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "result" && kv.value == "deduplicated".into()));
+}
+
+#[test]
+fn panic_hook_records_panic_on_current_span() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    n00_otel::install_panic_hook();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("panicky");
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            span.in_scope(|| panic!("exploded: {}", 42));
+        }));
+    });
+
+    let spans = exported_spans(&harness);
+    let span = spans.iter().find(|s| s.name == "panicky").unwrap();
+    assert!(matches!(
+        &span.status,
+        opentelemetry::trace::Status::Error { description } if description.contains("exploded: 42")
+    ));
+    let event = span.events.iter().find(|e| e.name == "panic").unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "panic.location"));
+}
+
+#[test]
+fn pre_init_spans_are_buffered_and_flushed_on_initialize() {
+    let lazy = n00_otel::LazyTracer::buffering(16);
+    let subscriber = Registry::default().with(n00_otel::layer().with_tracer(lazy.clone()));
+
+    let harness = TestHarness::new();
+    tracing::subscriber::with_default(subscriber, || {
+        // Telemetry is not initialized yet; both spans close pre-init.
+        {
+            let startup = tracing::info_span!("startup");
+            startup.in_scope(|| {
+                tracing::info_span!("load_config").in_scope(|| tracing::info!("loaded"));
+            });
+        }
+        assert!(harness.finished_spans().is_empty());
+        lazy.initialize(harness.tracer());
+
+        // Post-init spans flow straight through.
+        tracing::info_span!("after_init").in_scope(|| {});
+    });
+
+    let spans = exported_spans(&harness);
+    let startup = spans.iter().find(|s| s.name == "startup").unwrap();
+    let config = spans.iter().find(|s| s.name == "load_config").unwrap();
+    assert_eq!(config.parent_span_id, startup.span_context.span_id());
+    assert_eq!(config.span_context.trace_id(), startup.span_context.trace_id());
+    assert_eq!(config.events.len(), 1);
+    assert!(spans.iter().any(|s| s.name == "after_init"));
+}
+
+#[test]
+fn two_otel_layers_coexist_with_different_filters() {
+    let harness_a = TestHarness::new();
+    let harness_b = TestHarness::new();
+    let subscriber = Registry::default()
+        .with(harness_a.layer())
+        .with(harness_b.layer().with_events_on_error_only(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("shared_root");
+        root.in_scope(|| {
+            tracing::info_span!("shared_child").in_scope(|| tracing::info!("detail"));
+        });
+    });
+
+    // Both pipelines saw the full span tree, each with its own IDs.
+    for harness in [&harness_a, &harness_b] {
+        let spans = exported_spans(harness);
+        let root = spans.iter().find(|s| s.name == "shared_root").unwrap();
+        let child = spans.iter().find(|s| s.name == "shared_child").unwrap();
+        assert_eq!(child.parent_span_id, root.span_context.span_id());
+    }
+
+    // ...but each honored its own event filter.
+    let child_a = harness_a.span("shared_child");
+    let child_b = harness_b.span("shared_child");
+    assert_eq!(child_a.events.len(), 1);
+    assert!(child_b.events.is_empty());
+}
+
+#[test]
+fn per_subscriber_propagator_overrides_global() {
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use std::collections::HashMap;
+
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.with_propagator(TraceContextPropagator::new())
+    });
+
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    carrier.insert(
+        "traceparent".into(),
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".into(),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("inbound");
+        // Extraction goes through the subscriber's propagator even though
+        // the global propagator is the default no-op one.
+        span.set_parent_from(&carrier);
+
+        let mut outgoing: HashMap<String, String> = HashMap::new();
+        span.inject_context(&mut outgoing);
+        let header = outgoing.get("traceparent").expect("injected traceparent");
+        assert!(header.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+
+        span.in_scope(|| {});
+    });
+
+    let span = harness.span("inbound");
+    assert_eq!(
+        span.span_context.trace_id().to_string(),
+        "0af7651916cd43dd8448eb211c80319c"
+    );
+    assert_eq!(span.parent_span_id.to_string(), "b7ad6b7169203331");
+}
+
+#[test]
+fn datadog_conventions_add_mapped_attributes() {
+    use n00_otel::conventions::ConventionsMode;
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_conventions(ConventionsMode::Datadog));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!(
+            target: "my_app::http",
+            "GET /users/:id",
+            otel.kind = "server",
+            otel.status_code = "error",
+            otel.status_description = "upstream timed out"
+        )
+        .in_scope(|| {});
+    });
+
+    let span = harness.span("GET /users/:id");
+    assert!(span.has_attribute("operation.name", "my_app::http"));
+    assert!(span.has_attribute("resource.name", "GET /users/:id"));
+    assert!(span.has_attribute("span.type", "web"));
+    assert!(span.has_attribute("error.msg", "upstream timed out"));
+}
+
+#[test]
+fn json_attributes_flatten_onto_spans() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("api_call");
+        span.set_json_attribute(
+            "request",
+            &serde_json::json!({"method": "POST", "retries": 2}),
+        );
+        span.in_scope(|| {});
+    });
+
+    let span = harness.span("api_call");
+    assert!(span.has_attribute("request.method", "POST"));
+    assert!(span.has_attribute("request.retries", 2));
+}
+
+#[test]
+fn db_statements_are_sanitized_before_export() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_db_statement_sanitizer(n00_otel::sanitize_sql));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!(
+            "query",
+            db.query.text = "SELECT * FROM users WHERE email = 'leak@example.com'"
+        )
+        .in_scope(|| {
+            tracing::info!(db.statement = "DELETE FROM t WHERE id = 99", "exec");
+        });
+    });
+
+    let span = harness.span("query");
+    assert!(span.has_attribute("db.query.text", "SELECT * FROM users WHERE email = ?"));
+    let event_attr = &span.events[0]
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "db.statement")
+        .unwrap()
+        .value;
+    assert_eq!(*event_attr, "DELETE FROM t WHERE id = ?".into());
+}
+
+#[test]
+fn redaction_policy_scrubs_spans_and_events() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_redaction(n00_otel::RedactionPolicy::common_pii()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("login", auth.token = "abc123", user = "bob@example.com")
+            .in_scope(|| tracing::info!(email = "carol@example.com", "sent"));
+    });
+
+    let span = harness.span("login");
+    assert!(span.has_attribute("auth.token", "[REDACTED]"));
+    assert!(span.has_attribute("user", "[REDACTED]"));
+    let email = span.events[0]
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "email")
+        .unwrap();
+    assert_eq!(email.value, "[REDACTED]".into());
+}
+
+#[test]
+fn profiling_correlation_exposes_thread_ids() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_profiling_correlation(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(n00_otel::profiling::current_thread_trace_ids().is_none());
+        let span = tracing::info_span!("profiled");
+        span.in_scope(|| {
+            let (trace_id, span_id) =
+                n00_otel::profiling::current_thread_trace_ids().expect("ids while entered");
+            let cx = tracing::Span::current().context();
+            let sc = TraceContextExt::span(&cx).span_context().clone();
+            assert_eq!(trace_id, sc.trace_id());
+            assert_eq!(span_id, sc.span_id());
+        });
+        assert!(n00_otel::profiling::current_thread_trace_ids().is_none());
+    });
+
+    let _ = harness;
+}
+
+#[test]
+fn log_crate_events_keep_their_own_callsites() {
+    // LogTracer forwards `log` records as tracing events through one shared
+    // shim callsite; the layer must export the record's own metadata.
+    let _ = tracing_log::LogTracer::init();
+
+    let (subscriber, harness) = test_tracer(|layer| layer.with_level(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("legacy").in_scope(|| {
+            log::warn!(target: "legacy_lib", "old-style warning");
+        });
+    });
+
+    let span = harness.span("legacy");
+    let event = span.events.iter().find(|e| e.name == "old-style warning").unwrap();
+    let attr = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.clone())
+    };
+    assert_eq!(attr("target"), Some("legacy_lib".into()));
+    assert_eq!(attr("level"), Some("WARN".into()));
+}
+
+#[test]
+fn sampled_field_skips_evaluation_when_dropped() {
+    use n00_otel::testing::SpanDataExt;
+    use opentelemetry_sdk::trace::Sampler;
+
+    let evaluations = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let expensive = {
+        let evaluations = evaluations.clone();
+        move || {
+            evaluations.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            "costly".to_string()
+        }
+    };
+
+    // Sampled-out pipeline: the value expression must never run.
+    let off = TestHarness::with_provider(|builder| builder.with_sampler(Sampler::AlwaysOff));
+    tracing::subscriber::with_default(
+        Registry::default().with(off.layer()),
+        || {
+            let span = tracing::info_span!("dropped");
+            n00_otel::sampled_field!(span, "dump", expensive());
+            span.in_scope(|| {});
+        },
+    );
+    assert_eq!(evaluations.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    // Sampled pipeline: evaluated once and recorded.
+    let on = TestHarness::new();
+    tracing::subscriber::with_default(Registry::default().with(on.layer()), || {
+        let span = tracing::info_span!("kept");
+        n00_otel::sampled_field!(span, "dump", expensive());
+        span.in_scope(|| {});
+    });
+    assert_eq!(evaluations.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert!(on.span("kept").has_attribute("dump", "costly"));
+}
+
+#[test]
+fn explicit_end_time_wins_over_measured_close() {
+    use std::time::{Duration, SystemTime};
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let end = start + Duration::from_millis(75);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("written_early");
+        span.set_start_time(start);
+        span.set_end_time(end);
+        span.in_scope(|| std::thread::sleep(Duration::from_millis(5)));
+    });
+
+    let span = harness.span("written_early");
+    assert_eq!(span.end_time, end);
+}
+
+#[test]
+fn per_enter_timings_survive_concurrent_enters() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer.with_per_enter_timings(true));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("parallel");
+    let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+    let threads: Vec<_> = (0..2)
+        .map(|_| {
+            let span = span.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                let _entered = span.enter();
+                // Both threads are inside the span at the same time.
+                barrier.wait();
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    drop(span);
+
+    let span = harness.span("parallel");
+    assert!(span.has_attribute("enter_count", 2));
+    assert!(span.has_attribute("max_concurrency", 2));
+    let busy = span
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "busy_ns")
+        .unwrap();
+    // Two overlapping ~5ms segments sum to at least ~10ms.
+    assert!(matches!(busy.value, opentelemetry::Value::I64(ns) if ns >= 9_000_000));
+}
+
+#[test]
+fn follows_from_links_can_snapshot_attributes() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_follows_from_attribute_snapshot(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let producer = tracing::info_span!("producer", batch.id = 17);
+        let consumer = tracing::info_span!("consumer");
+        consumer.follows_from(&producer);
+        consumer.in_scope(|| {});
+        producer.in_scope(|| {});
+    });
+
+    let consumer = harness.span("consumer");
+    assert_eq!(consumer.links.links.len(), 1);
+    let link = &consumer.links.links[0];
+    assert!(link
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "batch.id" && kv.value == 17.into()));
+}
+
+#[test]
+fn late_follows_from_resolves_via_closed_span_lru() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_closed_span_lru(64));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let producer = tracing::info_span!("early_producer");
+        producer.in_scope(|| {});
+        let producer_id = producer.id().expect("span id");
+        drop(producer); // closes before the consumer links to it
+
+        let consumer = tracing::info_span!("late_consumer");
+        consumer.follows_from(producer_id);
+        consumer.in_scope(|| {});
+    });
+
+    let producer = harness.span("early_producer");
+    let consumer = harness.span("late_consumer");
+    assert_eq!(consumer.links.links.len(), 1);
+    assert_eq!(
+        consumer.links.links[0].span_context.span_id(),
+        producer.span_context.span_id()
+    );
+}
+
+#[test]
+fn span_name_fn_derives_exported_names() {
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.with_span_name_fn(|meta| Some(format!("renamed::{}", meta.name())))
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("operation").in_scope(|| {});
+        // otel.name still wins over the naming function.
+        tracing::info_span!("other", otel.name = "explicit").in_scope(|| {});
+    });
+
+    assert_eq!(harness.span("renamed::operation").name, "renamed::operation");
+    assert_eq!(harness.span("explicit").name, "explicit");
+}
+
+#[test]
+fn scope_attributes_from_configuration_reach_exported_spans() {
+    let harness = TestHarness::new();
+    let tracer = n00_otel::scoped_tracer(
+        harness.provider(),
+        "payments",
+        [opentelemetry::KeyValue::new("deployment.ring", "canary")],
+    );
+    let subscriber = Registry::default().with(n00_otel::layer().with_tracer(tracer));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("charge").in_scope(|| {});
+    });
+
+    let span = harness.span("charge");
+    assert_eq!(span.instrumentation_scope.name(), "payments");
+    assert!(span
+        .instrumentation_scope
+        .attributes()
+        .any(|kv| kv.key.as_str() == "deployment.ring" && kv.value == "canary".into()));
+}
+
+#[test]
+fn dynamic_targets_do_not_silence_other_layers() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tracing::level_filters::LevelFilter;
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+    #[derive(Default)]
+    struct FmtStandIn {
+        spans: std::sync::Arc<AtomicUsize>,
+        events: std::sync::Arc<AtomicUsize>,
+    }
+    impl<S> Layer<S> for FmtStandIn
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            _attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: LayerContext<'_, S>,
+        ) {
+            self.spans.fetch_add(1, Ordering::SeqCst);
+        }
+        fn on_event(&self, _event: &tracing::Event<'_>, _ctx: LayerContext<'_, S>) {
+            self.events.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let fmt_stand_in = FmtStandIn::default();
+    let (spans_seen, events_seen) = (fmt_stand_in.spans.clone(), fmt_stand_in.events.clone());
+    let targets = n00_otel::DynamicTargets::new(LevelFilter::OFF);
+    let harness = TestHarness::new();
+    let subscriber = Registry::default()
+        .with(fmt_stand_in)
+        .with(harness.layer().with_dynamic_targets(targets.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        // Everything is off for the OTel layer...
+        tracing::info_span!(target: "hyper::client", "request").in_scope(|| {
+            tracing::info!(target: "hyper::client", "connected");
+        });
+    });
+
+    // ...but the fmt-like layer saw both the span and the event.
+    assert!(harness.finished_spans().is_empty());
+    assert_eq!(spans_seen.load(Ordering::SeqCst), 1);
+    assert_eq!(events_seen.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn filtered_spans_do_not_orphan_descendants() {
+    use tracing::level_filters::LevelFilter;
+
+    let targets = n00_otel::DynamicTargets::new(LevelFilter::INFO);
+    targets.set_target("scaffolding", LevelFilter::OFF);
+    let (subscriber, harness) = test_tracer(|layer| layer.with_dynamic_targets(targets));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("kept_root");
+        root.in_scope(|| {
+            tracing::info_span!(target: "scaffolding", "filtered_middle").in_scope(|| {
+                tracing::info_span!("kept_leaf").in_scope(|| tracing::info!("leaf event"));
+            });
+        });
+    });
+
+    let spans = exported_spans(&harness);
+    assert!(spans.iter().all(|s| s.name != "filtered_middle"));
+    let root = spans.iter().find(|s| s.name == "kept_root").unwrap();
+    let leaf = spans.iter().find(|s| s.name == "kept_leaf").unwrap();
+    // The leaf re-parents onto the nearest exported ancestor.
+    assert_eq!(leaf.span_context.trace_id(), root.span_context.trace_id());
+    assert_eq!(leaf.parent_span_id, root.span_context.span_id());
+    assert_eq!(leaf.events.len(), 1);
+}
+
+#[test]
+fn dynamic_targets_reload_at_runtime() {
+    use tracing::level_filters::LevelFilter;
+
+    let targets = n00_otel::DynamicTargets::new(LevelFilter::OFF);
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_dynamic_targets(targets.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!(target: "noisy::module", "before").in_scope(|| {});
+        targets.set_target("noisy", LevelFilter::INFO);
+        tracing::info_span!(target: "noisy::module", "after").in_scope(|| {});
+        targets.clear_target("noisy");
+        tracing::info_span!(target: "noisy::module", "cleared").in_scope(|| {});
+    });
+
+    let names: Vec<String> = exported_spans(&harness)
+        .iter()
+        .map(|s| s.name.to_string())
+        .collect();
+    assert_eq!(names, vec!["after".to_string()]);
+}
+
+#[test]
+fn remote_config_endpoint_drives_dynamic_filters() {
+    use std::io::{Read, Write};
+    use tracing::level_filters::LevelFilter;
+
+    let targets = n00_otel::DynamicTargets::new(LevelFilter::OFF);
+    let server = n00_otel::serve_filter_config("127.0.0.1:0", targets.clone()).unwrap();
+    let addr = server.local_addr();
+
+    let request = |method: &str, body: &str| {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{method} /v1/filters HTTP/1.1\r\nHost: x\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let _ = stream.shutdown(std::net::Shutdown::Write);
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    };
+
+    let response = request(
+        "PUT",
+        r#"{"default":"warn","targets":{"noisy":"trace"}}"#,
+    );
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    let (default, entries) = targets.snapshot();
+    assert_eq!(default, LevelFilter::WARN);
+    assert_eq!(entries, vec![("noisy".to_string(), LevelFilter::TRACE)]);
+
+    let response = request("GET", "");
+    assert!(response.contains(r#""noisy":"trace""#), "{response}");
+
+    let response = request("PUT", r#"{"default":"loud"}"#);
+    assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+}
+
+#[test]
+fn otel_drop_suppresses_export_but_keeps_children_parented() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("visible_root");
+        root.in_scope(|| {
+            let scaffold = tracing::info_span!("scaffold", otel.drop = true);
+            scaffold.in_scope(|| {
+                tracing::info_span!("grandchild").in_scope(|| {});
+            });
+        });
+    });
+
+    let spans = exported_spans(&harness);
+    assert!(spans.iter().all(|s| s.name != "scaffold"));
+    let root = spans.iter().find(|s| s.name == "visible_root").unwrap();
+    let grandchild = spans.iter().find(|s| s.name == "grandchild").unwrap();
+    // The grandchild stays in the same trace, parented to the dropped
+    // span's ID (trace continuity over orphan re-parenting).
+    assert_eq!(
+        grandchild.span_context.trace_id(),
+        root.span_context.trace_id()
+    );
+    assert_ne!(grandchild.parent_span_id, root.span_context.span_id());
+    assert_ne!(
+        grandchild.parent_span_id,
+        opentelemetry::trace::SpanId::INVALID
+    );
+}
+
+#[test]
+fn repetitive_children_collapse_into_parent_aggregate() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_child_span_aggregation(2));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let parent = tracing::info_span!("batch");
+        parent.in_scope(|| {
+            for _ in 0..10 {
+                tracing::info_span!("item").in_scope(|| {});
+            }
+            tracing::info_span!("unique").in_scope(|| {});
+        });
+    });
+
+    let spans = exported_spans(&harness);
+    // The first two representatives export; the rest fold into the parent.
+    assert_eq!(spans.iter().filter(|s| s.name == "item").count(), 2);
+    assert_eq!(spans.iter().filter(|s| s.name == "unique").count(), 1);
+
+    let parent = harness.span("batch");
+    let aggregate = parent
+        .events
+        .iter()
+        .find(|e| e.name == "aggregated_children")
+        .expect("aggregate event");
+    assert!(aggregate
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "span.name" && kv.value == "item".into()));
+    assert!(aggregate
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "folded_count" && kv.value == 8.into()));
+}
+
+#[test]
+fn long_running_spans_emit_partial_heartbeats() {
+    use std::time::Duration;
+
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.with_heartbeat(Duration::from_millis(20), Duration::from_millis(0))
+    });
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let span = tracing::info_span!("long_job");
+    let _entered = span.enter();
+
+    // Wait for at least one heartbeat while the span is still open.
+    let mut partials = Vec::new();
+    for _ in 0..100 {
+        partials = exported_spans(&harness);
+        if !partials.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!partials.is_empty(), "no heartbeat export arrived");
+    let partial = &partials[0];
+    assert_eq!(partial.name, "long_job");
+    assert!(partial
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "n00.partial" && kv.value == true.into()));
+
+    drop(_entered);
+    drop(span);
+
+    // The final export reuses the same IDs, superseding the partials.
+    let spans = exported_spans(&harness);
+    let finals: Vec<_> = spans
+        .iter()
+        .filter(|s| !s.attributes.iter().any(|kv| kv.key.as_str() == "n00.partial"))
+        .collect();
+    assert_eq!(finals.len(), 1);
+    assert_eq!(finals[0].span_context.span_id(), partial.span_context.span_id());
+    assert_eq!(finals[0].span_context.trace_id(), partial.span_context.trace_id());
+}
+
+#[test]
+fn spans_over_budget_are_flagged_and_hooked() {
+    use n00_otel::testing::SpanDataExt;
+    use std::time::Duration;
+
+    let blown = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let blown_in_hook = blown.clone();
+    let (subscriber, harness) = test_tracer(move |layer| {
+        layer
+            .with_duration_budget(Duration::from_millis(10))
+            .with_budget_exceeded_hook(move |name, duration| {
+                blown_in_hook
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), duration));
+            })
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("fast").in_scope(|| {});
+        tracing::info_span!("slow")
+            .in_scope(|| std::thread::sleep(Duration::from_millis(25)));
+    });
+
+    assert!(harness.span("fast").attribute("n00.budget_exceeded").is_none());
+    let slow = harness.span("slow");
+    assert!(slow.has_attribute("n00.budget_exceeded", true));
+    assert!(slow.attribute("n00.budget_overrun_ms").is_some());
+    let blown = blown.lock().unwrap();
+    assert_eq!(blown.len(), 1);
+    assert_eq!(blown[0].0, "slow");
+}
+
+#[test]
+fn live_spans_lists_open_spans() {
+    let live = n00_otel::LiveSpans::new();
+    let (subscriber, _harness) = test_tracer(|layer| layer.with_live_spans(live.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        assert_eq!(live.open_count(), 0);
+        let outer = tracing::info_span!("outer_work");
+        let _enter = outer.enter();
+        let inner = tracing::info_span!("inner_work");
+        {
+            let open = live.open_spans();
+            assert_eq!(open.len(), 2);
+            let names: Vec<&str> = open.iter().map(|s| s.name.as_str()).collect();
+            assert!(names.contains(&"outer_work") && names.contains(&"inner_work"));
+            assert!(open.iter().all(|s| s.span_id != SpanId::INVALID));
+        }
+        drop(inner);
+        assert_eq!(live.open_count(), 1);
+    });
+    assert_eq!(live.open_count(), 0);
+}
+
+#[test]
+fn span_feed_publishes_lifecycle_events() {
+    use n00_otel::SpanLifecycle;
+
+    let feed = n00_otel::SpanFeed::new();
+    let events = feed.subscribe(16);
+    let (subscriber, _harness) = test_tracer(|layer| layer.with_span_feed(feed.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("watched", otel.status_code = "error").in_scope(|| {});
+    });
+
+    let received: Vec<SpanLifecycle> = events.try_iter().collect();
+    assert_eq!(received.len(), 2);
+    assert!(
+        matches!(&received[0], SpanLifecycle::Started { name, .. } if name == "watched")
+    );
+    assert!(matches!(
+        &received[1],
+        SpanLifecycle::Closed { name, is_error: true, .. } if name == "watched"
+    ));
+}
+
+#[test]
+fn layer_stats_count_spans_and_events() {
+    let stats = n00_otel::LayerStats::new();
+    let (subscriber, _harness) = test_tracer(|layer| {
+        layer
+            .with_stats(stats.clone())
+            .with_max_events_per_span(1)
+            .with_span_rate_limit(0.0, 2)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        for _ in 0..3 {
+            tracing::info_span!("counted").in_scope(|| {
+                tracing::info!("one");
+                tracing::info!("two, dropped");
+            });
+        }
+    });
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.spans_started, 3);
+    assert_eq!(snapshot.spans_exported, 2); // burst of 2, third suppressed
+    assert_eq!(snapshot.spans_suppressed, 1);
+    assert_eq!(snapshot.events_recorded, 6);
+    assert_eq!(snapshot.events_dropped, 3);
+}
+
+#[test]
+fn backpressure_escalates_to_shedding_non_error_spans() {
+    let signal = n00_otel::BackpressureSignal::with_thresholds(10, 50);
+    let (subscriber, harness) = test_tracer(|layer| layer.with_backpressure(signal.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        // Stage one: events shed, spans still exported.
+        signal.add(20);
+        assert!(signal.is_overloaded() && !signal.should_shed_spans());
+        tracing::info_span!("stage_one").in_scope(|| tracing::info!("shed"));
+
+        // Stage two: non-error spans shed too; errors still get through.
+        signal.add(40);
+        assert!(signal.should_shed_spans());
+        tracing::info_span!("stage_two_ok").in_scope(|| {});
+        tracing::info_span!("stage_two_failed", otel.status_code = "error").in_scope(|| {});
+        signal.sub(60);
+    });
+
+    let names: Vec<String> = exported_spans(&harness)
+        .iter()
+        .map(|s| s.name.to_string())
+        .collect();
+    assert!(names.contains(&"stage_one".to_string()));
+    assert!(harness.span("stage_one").events.is_empty());
+    assert!(!names.contains(&"stage_two_ok".to_string()));
+    assert!(names.contains(&"stage_two_failed".to_string()));
+}
+
+#[test]
+fn backpressure_sheds_events_while_overloaded() {
+    use n00_otel::testing::SpanDataExt;
+
+    let signal = n00_otel::BackpressureSignal::with_threshold(10);
+    let (subscriber, harness) = test_tracer(|layer| layer.with_backpressure(signal.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("normal").in_scope(|| tracing::info!("kept"));
+
+        // Simulate a stalled exporter holding spans in flight.
+        signal.add(100);
+        assert!(signal.is_overloaded());
+        tracing::info_span!("overloaded").in_scope(|| tracing::info!("shed"));
+        signal.sub(100);
+    });
+
+    assert_eq!(harness.span("normal").events.len(), 1);
+    let overloaded = harness.span("overloaded");
+    assert!(overloaded.events.is_empty());
+    assert!(overloaded.has_attribute("otel.dropped_event_count", 1));
+}
+
+#[test]
+fn adaptive_sampler_boosts_failing_span_names() {
+    let sampler = n00_otel::AdaptiveSampler::builder()
+        .with_base_ratio(0.0)
+        .with_boost_ratio(1.0)
+        .with_error_rate_threshold(0.5)
+        .with_min_samples(3)
+        .build();
+
+    let harness = TestHarness::with_provider({
+        let sampler = sampler.clone();
+        move |builder| builder.with_sampler(sampler)
+    });
+    let layer = harness.layer().with_adaptive_feedback(sampler);
+    let subscriber = Registry::default().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        // While the name is unknown/below min samples, the base ratio (0)
+        // drops everything. Outcomes are recorded at close, so by the third
+        // error the window crosses the threshold and sampling flips on.
+        tracing::info_span!("flaky", otel.status_code = "error").in_scope(|| {});
+        tracing::info_span!("flaky", otel.status_code = "error").in_scope(|| {});
+        assert!(harness.finished_spans().is_empty());
+
+        tracing::info_span!("flaky", otel.status_code = "error").in_scope(|| {});
+        tracing::info_span!("flaky").in_scope(|| {});
+        assert_eq!(harness.finished_spans().len(), 2);
+    });
+}
+
+#[test]
+fn configured_error_fields_map_to_span_status() {
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_error_fields_to_status(["error", "err"]));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("failed", error = "connection refused").in_scope(|| {});
+        tracing::info_span!("fine", error = tracing::field::Empty).in_scope(|| {});
+        // Explicit status wins over the derived one.
+        tracing::info_span!("overridden", error = "ignored", otel.status_code = "ok")
+            .in_scope(|| {});
+    });
+
+    assert!(matches!(
+        &harness.span("failed").status,
+        opentelemetry::trace::Status::Error { description } if description.as_ref() == "connection refused"
+    ));
+    assert!(matches!(
+        harness.span("fine").status,
+        opentelemetry::trace::Status::Unset
+    ));
+    assert!(matches!(
+        harness.span("overridden").status,
+        opentelemetry::trace::Status::Ok
+    ));
+}
+
+#[test]
+fn error_events_set_status_message() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_error_events_to_status(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("failing").in_scope(|| {
+            tracing::error!("first failure");
+            tracing::error!("final failure");
+        });
+        tracing::info_span!("healthy").in_scope(|| tracing::info!("all good"));
+        tracing::info_span!("explicit", otel.status_code = "ok")
+            .in_scope(|| tracing::error!("noise"));
+    });
+
+    assert!(matches!(
+        &harness.span("failing").status,
+        opentelemetry::trace::Status::Error { description } if description.as_ref() == "final failure"
+    ));
+    assert!(matches!(
+        harness.span("healthy").status,
+        opentelemetry::trace::Status::Unset
+    ));
+    assert!(matches!(
+        harness.span("explicit").status,
+        opentelemetry::trace::Status::Ok
+    ));
+}
+
+#[test]
+fn nested_subscribers_keep_independent_pipelines() {
+    let host = TestHarness::new();
+    let embedded = TestHarness::new();
+    let host_subscriber = Registry::default().with(host.layer());
+
+    tracing::subscriber::with_default(host_subscriber, || {
+        let app_span = tracing::info_span!("app_request");
+        let _enter = app_span.enter();
+
+        // An embedded library brings its own registry and OTel pipeline.
+        let embedded_subscriber = Registry::default().with(embedded.layer());
+        tracing::subscriber::with_default(embedded_subscriber, || {
+            let lib_span = tracing::info_span!("library_op");
+            // The extension trait resolves through the span's own
+            // subscriber, not the host's.
+            assert!(TraceContextExt::span(&lib_span.context())
+                .span_context()
+                .is_valid());
+            lib_span.in_scope(|| tracing::info!("library detail"));
+        });
+
+        // Back on the host dispatch.
+        tracing::info_span!("app_child").in_scope(|| {});
+    });
+
+    let host_names: Vec<String> = host
+        .finished_spans()
+        .iter()
+        .map(|s| s.name.to_string())
+        .collect();
+    assert!(host_names.contains(&"app_request".to_string()));
+    assert!(host_names.contains(&"app_child".to_string()));
+    assert!(!host_names.contains(&"library_op".to_string()));
+
+    let lib = embedded.span("library_op");
+    assert_eq!(lib.events.len(), 1);
+}
+
+#[test]
+fn attribute_cap_bounds_buffering_and_counts_drops() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_max_attributes_per_span(2)
+            .with_tracked_inactivity(false)
+            .with_threads(false)
+            .with_target(false)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("tight", a = 1, b = 2, c = 3, d = 4);
+        span.record("a", 99); // recorded late, also over the cap
+        span.in_scope(|| {});
+    });
+
+    let span = harness.span("tight");
+    let user_attrs = span
+        .attributes
+        .iter()
+        .filter(|kv| !kv.key.as_str().starts_with("otel."))
+        .count();
+    assert_eq!(user_attrs, 2);
+    assert!(span.has_attribute("otel.dropped_attributes_count", 3));
+}
+
+#[test]
+fn manual_clock_makes_exported_timing_deterministic() {
+    use std::time::{Duration, SystemTime};
+
+    let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = n00_otel::ManualClock::new(epoch);
+    let (subscriber, harness) = test_tracer(|layer| layer.with_clock(clock.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("simulated");
+        span.in_scope(|| {
+            clock.advance(Duration::from_millis(125));
+            tracing::info!("halfway");
+            clock.advance(Duration::from_millis(125));
+        });
+    });
+
+    let span = harness.span("simulated");
+    assert_eq!(span.start_time, epoch);
+    assert_eq!(span.end_time, epoch + Duration::from_millis(250));
+    assert_eq!(span.events[0].timestamp, epoch + Duration::from_millis(125));
+}
+
+#[test]
+fn event_time_field_overrides_event_timestamp() {
+    use std::time::{Duration, SystemTime};
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let reading_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    let nanos = reading_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("sensor").in_scope(|| {
+            tracing::info!(otel.event_time = nanos, reading = 42, "sample");
+            tracing::info!("live event");
+        });
+    });
+
+    let span = harness.span("sensor");
+    let sample = span.events.iter().find(|e| e.name == "sample").unwrap();
+    assert_eq!(sample.timestamp, reading_time);
+    // The override is consumed, not exported as an attribute.
+    assert!(!sample
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "otel.event_time"));
+    let live = span.events.iter().find(|e| e.name == "live event").unwrap();
+    assert!(live.timestamp > reading_time);
+}
+
+#[test]
+fn duplicate_field_policies_shape_repeated_recordings() {
+    use n00_otel::testing::SpanDataExt;
+    use n00_otel::DuplicateFieldPolicy;
+
+    let record_thrice = |policy| {
+        let (subscriber, harness) =
+            test_tracer(|layer: OpenTelemetryLayer<_, _>| layer.with_duplicate_field_policy(policy));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("dup", retry = 1);
+            span.record("retry", 2);
+            span.record("retry", 3);
+            span.in_scope(|| {});
+        });
+        harness.span("dup")
+    };
+
+    let appended = record_thrice(DuplicateFieldPolicy::Append);
+    assert_eq!(
+        appended
+            .attributes
+            .iter()
+            .filter(|kv| kv.key.as_str() == "retry")
+            .count(),
+        3
+    );
+
+    let last = record_thrice(DuplicateFieldPolicy::LastWins);
+    assert!(last.has_attribute("retry", 3));
+    assert_eq!(
+        last.attributes
+            .iter()
+            .filter(|kv| kv.key.as_str() == "retry")
+            .count(),
+        1
+    );
+
+    let multi = record_thrice(DuplicateFieldPolicy::MultiValue);
+    assert!(multi.has_attribute(
+        "retry",
+        opentelemetry::Value::Array(opentelemetry::Array::I64(vec![1, 2, 3]))
+    ));
+}
+
+#[test]
+fn event_fields_are_visited_exactly_once() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_level(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("single_pass").in_scope(|| {
+            tracing::info!(a = 1, b = "two", c = 3.0, d = true, "message text");
+        });
+    });
+
+    let span = harness.span("single_pass");
+    let event = &span.events[0];
+    assert_eq!(event.name, "message text");
+    // Each declared field appears exactly once; a double field walk would
+    // duplicate them.
+    for key in ["a", "b", "c", "d"] {
+        assert_eq!(
+            event
+                .attributes
+                .iter()
+                .filter(|kv| kv.key.as_str() == key)
+                .count(),
+            1,
+            "field {key} duplicated or missing"
+        );
+    }
+    // 4 fields + level/target/code.file.path/code.line.number metadata.
+    assert_eq!(event.attributes.len(), 8);
+}
+
+#[test]
+fn deferred_export_builds_spans_on_worker_thread() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_deferred_export(128));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("offloaded").in_scope(|| tracing::info!("detail"));
+    });
+
+    // Export happens asynchronously on the worker.
+    for _ in 0..200 {
+        if !harness.finished_spans().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let span = harness.span("offloaded");
+    assert_eq!(span.events.len(), 1);
+}
+
+#[test]
+fn memory_budget_bounds_buffered_events_globally() {
+    use n00_otel::testing::SpanDataExt;
+
+    // Tiny budget: roughly two small events' worth.
+    let (subscriber, harness) = test_tracer(|layer| layer.with_memory_budget(200));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("greedy").in_scope(|| {
+            for i in 0..50 {
+                tracing::info!(i, "filler");
+            }
+        });
+        // The budget was refunded at close; a later span gets events again.
+        tracing::info_span!("after").in_scope(|| tracing::info!("works"));
+    });
+
+    let greedy = harness.span("greedy");
+    assert!(greedy.events.len() < 50);
+    assert!(greedy.attribute("otel.dropped_event_count").is_some());
+    assert_eq!(harness.span("after").events.len(), 1);
+}
+
+#[test]
+fn otlp_json_writer_emits_standard_encoding() {
+    #[derive(Clone, Debug, Default)]
+    struct SharedBuf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = SharedBuf::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(n00_otel::OtlpJsonExporter::new(buffer.clone()))
+        .build();
+    let layer = n00_otel::layer().with_tracer(provider.tracer("debug-writer"));
+    let subscriber = Registry::default().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("json_span", count = 3, otel.kind = "server")
+            .in_scope(|| tracing::info!("hello"));
+    });
+
+    let bytes = buffer.0.lock().unwrap().clone();
+    let line = String::from_utf8(bytes).unwrap();
+    let document: serde_json::Value = serde_json::from_str(line.lines().next().unwrap()).unwrap();
+    let span = &document["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["name"], "json_span");
+    assert_eq!(span["kind"], 2);
+    assert_eq!(span["traceId"].as_str().unwrap().len(), 32);
+    assert_eq!(span["events"][0]["name"], "hello");
+    assert!(span["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|a| a["key"] == "count" && a["value"]["intValue"] == "3"));
+    assert_eq!(
+        document["resourceSpans"][0]["scopeSpans"][0]["scope"]["name"],
+        "debug-writer"
+    );
+}
+
+#[test]
+fn chrome_trace_exporter_writes_complete_events() {
+    #[derive(Clone, Debug, Default)]
+    struct SharedBuf2(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+    impl std::io::Write for SharedBuf2 {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = SharedBuf2::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(n00_otel::ChromeTraceExporter::new(buffer.clone()))
+        .build();
+    let layer = n00_otel::layer().with_tracer(provider.tracer("chrome"));
+    let subscriber = Registry::default().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("rendered").in_scope(|| {});
+        tracing::info_span!("also_rendered").in_scope(|| {});
+    });
+    provider.shutdown().unwrap();
+
+    let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let events: serde_json::Value = serde_json::from_str(&text).expect("valid JSON array");
+    let events = events.as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["ph"], "X");
+    assert_eq!(events[0]["name"], "rendered");
+    assert!(events[0]["args"]["trace_id"].as_str().unwrap().len() == 32);
+    assert!(events[0]["dur"].is_number());
+}
+
+#[test]
+fn otlp_harness_delivers_spans_to_an_http_endpoint() {
+    use std::io::{Read, Write};
+
+    // A single-shot OTLP/HTTP "collector".
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+    let received_in_server = received.clone();
+    std::thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            while let Ok(n) = stream.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let expected: usize = headers
+                        .lines()
+                        .find_map(|l| {
+                            let (k, v) = l.split_once(':')?;
+                            k.eq_ignore_ascii_case("content-length")
+                                .then(|| v.trim().parse().ok())
+                                .flatten()
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= expected {
+                        *received_in_server.lock().unwrap() = body.to_string();
+                        let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}");
+                        let _ = stream.shutdown(std::net::Shutdown::Write);
+                        while matches!(stream.read(&mut chunk), Ok(n) if n > 0) {}
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let harness = TestHarness::against_otlp(&format!("http://{addr}/v1/traces"));
+    let subscriber = Registry::default().with(harness.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("delivered").in_scope(|| {});
+    });
+
+    // The in-memory view works as usual...
+    assert_eq!(harness.span("delivered").name, "delivered");
+    // ...and the endpoint received the same span as OTLP/JSON.
+    for _ in 0..100 {
+        if !received.lock().unwrap().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let body = received.lock().unwrap().clone();
+    let document: serde_json::Value = serde_json::from_str(&body).expect("OTLP JSON body");
+    assert_eq!(
+        document["resourceSpans"][0]["scopeSpans"][0]["spans"][0]["name"],
+        "delivered"
+    );
+}
+
+#[test]
+fn messaging_span_macros_follow_semconv() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        n00_otel::producer_span!("kafka", "orders").in_scope(|| {});
+        n00_otel::consumer_span!("kafka", "orders", "process").in_scope(|| {});
+    });
+
+    let publish = harness.span("orders publish");
+    assert_eq!(publish.span_kind, opentelemetry::trace::SpanKind::Producer);
+    assert!(publish.has_attribute("messaging.system", "kafka"));
+    assert!(publish.has_attribute("messaging.destination.name", "orders"));
+    assert!(publish.has_attribute("messaging.operation.type", "publish"));
+
+    let process = harness.span("orders process");
+    assert_eq!(process.span_kind, opentelemetry::trace::SpanKind::Consumer);
+    assert!(process.has_attribute("messaging.operation.type", "process"));
+
+    let extra = n00_otel::messaging::MessagingAttributes::operation("kafka", "orders", "publish")
+        .with_batch_size(10)
+        .into_attributes();
+    assert!(extra
+        .iter()
+        .any(|kv| kv.key.as_str() == "messaging.batch.message_count"));
+}
+
+#[test]
+fn db_span_helper_builds_client_spans_with_conventions() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        n00_otel::db::DbSpan::new("postgresql", "SELECT")
+            .with_namespace("orders")
+            .with_statement("SELECT * FROM orders WHERE id = $1")
+            .span()
+            .in_scope(|| {});
+    });
+
+    let span = harness.span("SELECT orders");
+    assert_eq!(span.span_kind, opentelemetry::trace::SpanKind::Client);
+    assert!(span.has_attribute("db.system", "postgresql"));
+    assert!(span.has_attribute("db.operation.name", "SELECT"));
+    assert!(span.has_attribute("db.namespace", "orders"));
+    assert!(span.has_attribute("db.query.text", "SELECT * FROM orders WHERE id = $1"));
+}
+
+#[test]
+fn grpc_helpers_map_status_codes_per_semconv() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    use n00_otel::grpc::{MessageDirection, RpcRole};
+
+    tracing::subscriber::with_default(subscriber, || {
+        let ok = n00_otel::grpc_client_span!("checkout.CartService", "AddItem");
+        n00_otel::grpc::record_grpc_status(&ok, RpcRole::Client, 0, None);
+        ok.in_scope(|| {});
+
+        let failed = n00_otel::grpc_server_span!("checkout.CartService", "Purchase");
+        n00_otel::grpc::record_grpc_message(&failed, MessageDirection::Received, 1);
+        n00_otel::grpc::record_grpc_message(&failed, MessageDirection::Sent, 1);
+        n00_otel::grpc::record_grpc_status(&failed, RpcRole::Server, 4, Some("upstream slow"));
+        failed.in_scope(|| {});
+
+        // Caller-caused codes are not server errors per semconv...
+        let not_found = n00_otel::grpc_server_span!("checkout.CartService", "GetCart");
+        n00_otel::grpc::record_grpc_status(&not_found, RpcRole::Server, 5, None);
+        not_found.in_scope(|| {});
+
+        // ...but clients treat every non-OK code as a failure.
+        let client_not_found = n00_otel::grpc_client_span!("checkout.CartService", "GetCart");
+        n00_otel::grpc::record_grpc_status(&client_not_found, RpcRole::Client, 5, None);
+        client_not_found.in_scope(|| {});
+    });
+
+    let ok = harness.span("checkout.CartService/AddItem");
+    assert_eq!(ok.span_kind, opentelemetry::trace::SpanKind::Client);
+    assert!(ok.has_attribute("rpc.system", "grpc"));
+    assert!(ok.has_attribute("rpc.grpc.status_code", 0));
+    assert!(matches!(ok.status, opentelemetry::trace::Status::Ok));
+
+    let failed = harness.span("checkout.CartService/Purchase");
+    assert_eq!(failed.span_kind, opentelemetry::trace::SpanKind::Server);
+    assert!(failed.has_attribute("rpc.grpc.status_code", 4));
+    assert!(matches!(
+        &failed.status,
+        opentelemetry::trace::Status::Error { description }
+            if description.as_ref() == "DEADLINE_EXCEEDED: upstream slow"
+    ));
+    // Streaming messages appear as conventional `message` events.
+    let message_events: Vec<_> = failed.events.iter().filter(|e| e.name == "message").collect();
+    assert_eq!(message_events.len(), 2);
+    assert!(message_events[0]
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "rpc.message.type" && kv.value == "RECEIVED".into()));
+    assert!(message_events[0]
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == "rpc.message.id" && kv.value == 1.into()));
+
+    let spans = exported_spans(&harness);
+    let server_not_found = spans
+        .iter()
+        .filter(|s| s.name == "checkout.CartService/GetCart")
+        .find(|s| s.span_kind == opentelemetry::trace::SpanKind::Server)
+        .unwrap();
+    assert!(matches!(
+        server_not_found.status,
+        opentelemetry::trace::Status::Unset
+    ));
+    let client_not_found = spans
+        .iter()
+        .filter(|s| s.name == "checkout.CartService/GetCart")
+        .find(|s| s.span_kind == opentelemetry::trace::SpanKind::Client)
+        .unwrap();
+    assert!(matches!(
+        client_not_found.status,
+        opentelemetry::trace::Status::Error { .. }
+    ));
+}
+
+#[test]
+fn context_values_propagate_to_children_without_exporting() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("cv_root");
+        root.set_context_value("priority", "high");
+        root.in_scope(|| {
+            let child = tracing::info_span!("cv_child");
+            assert_eq!(child.context_value("priority"), Some("high".into()));
+            // Child-local updates don't leak back to the parent.
+            child.set_context_value("priority", "low");
+            assert_eq!(child.context_value("priority"), Some("low".into()));
+            child.in_scope(|| {});
+        });
+        assert_eq!(root.context_value("priority"), Some("high".into()));
+    });
+
+    // Context values stay in-process; nothing is exported.
+    let root = harness.span("cv_root");
+    assert!(!root.attributes.iter().any(|kv| kv.key.as_str() == "priority"));
+}
+
+#[test]
+fn attached_context_parents_root_spans_on_other_threads() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let root = tracing::info_span!("dispatcher");
+    let cx = root.context();
+
+    // Non-tracing worker thread: no entered span, only the attached context.
+    let worker_span = tracing::info_span!("preallocated"); // created under subscriber
+    drop(worker_span);
+    std::thread::scope(|scope| {
+        let handle = tracing::dispatcher::get_default(|d| d.clone());
+        scope.spawn(move || {
+            let _dispatch = tracing::dispatcher::set_default(&handle);
+            let _attached = n00_otel::attach_otel_context(cx);
+            tracing::info_span!("worker_root").in_scope(|| {});
+        });
+    });
+    drop(root);
+
+    let spans = exported_spans(&harness);
+    let dispatcher = spans.iter().find(|s| s.name == "dispatcher").unwrap();
+    let worker = spans.iter().find(|s| s.name == "worker_root").unwrap();
+    assert_eq!(
+        worker.span_context.trace_id(),
+        dispatcher.span_context.trace_id()
+    );
+    assert_eq!(worker.parent_span_id, dispatcher.span_context.span_id());
+}
+
+#[test]
+fn otel_api_created_spans_become_tracing_parents() {
+    use opentelemetry::trace::Tracer as _;
+
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // A span created directly through the OpenTelemetry API...
+    let tracer = harness.tracer();
+    let api_span = tracer.start("api_parent");
+    let api_cx = opentelemetry::Context::new().with_span(api_span);
+    {
+        let _attached = n00_otel::attach_otel_context(api_cx.clone());
+        // ...is adopted as the parent of tracing spans created under it.
+        tracing::info_span!("bridged_child").in_scope(|| {});
+    }
+    drop(api_cx); // ends and exports the API span
+
+    let spans = exported_spans(&harness);
+    let parent = spans.iter().find(|s| s.name == "api_parent").unwrap();
+    let child = spans.iter().find(|s| s.name == "bridged_child").unwrap();
+    assert_eq!(child.span_context.trace_id(), parent.span_context.trace_id());
+    assert_eq!(child.parent_span_id, parent.span_context.span_id());
+}
+
+#[test]
+fn trace_sampled_filter_keeps_sampled_trace_events() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+    #[derive(Default)]
+    struct CountingLayer(std::sync::Arc<AtomicUsize>);
+    impl<S> Layer<S> for CountingLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, _event: &tracing::Event<'_>, _ctx: LayerContext<'_, S>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let seen = std::sync::Arc::new(AtomicUsize::new(0));
+    let harness = TestHarness::new();
+    let subscriber = Registry::default()
+        .with(harness.layer())
+        .with(CountingLayer(seen.clone()).with_filter(n00_otel::TraceSampledFilter::new(0)));
+
+    tracing::subscriber::with_default(subscriber, || {
+        // Outside any trace: dropped by the filter (keep_one_in = 0).
+        tracing::info!("orphan event");
+        assert_eq!(seen.load(Ordering::SeqCst), 0);
+
+        let root = tracing::info_span!("sampled_root");
+        // Force the sampling decision the way any context access would.
+        let _ = root.context();
+        root.in_scope(|| {
+            tracing::info!("kept one");
+            tracing::info!("kept two");
+        });
+    });
+
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn current_trace_ids_match_exported_span() {
+    // The per-thread cache behind current_trace_ids is maintained by the
+    // enter/exit hooks of profiling correlation.
+    let (subscriber, harness) = test_tracer(|layer| layer.with_profiling_correlation(true));
+
+    let mut observed = None;
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(n00_otel::current_trace_ids().is_none());
+        tracing::info_span!("formatted").in_scope(|| {
+            observed = n00_otel::current_trace_ids();
+        });
+    });
+
+    let span = harness.span("formatted");
+    assert_eq!(
+        observed,
+        Some((span.span_context.trace_id(), span.span_context.span_id()))
+    );
+}
+
+#[test]
+fn env_style_directives_configure_the_layer() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.apply_directives(
+            "tail_events=2, events_on_error_only=off, tracked_inactivity=off, bogus=1,, redact=common_pii",
+        )
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("configured", password = "hunter2").in_scope(|| {
+            for i in 0..5 {
+                tracing::info!(i, "tick");
+            }
+        });
+    });
+
+    let span = harness.span("configured");
+    assert_eq!(span.events.len(), 2); // tail_events=2
+    assert!(span.has_attribute("password", "[REDACTED]")); // redact directive
+    assert!(span.attribute("busy_ns").is_none()); // tracked_inactivity=off
+}
+
+#[test]
+fn standard_otel_env_vars_map_onto_layer_options() {
+    use n00_otel::testing::SpanDataExt;
+    use std::collections::HashMap;
+
+    let vars: HashMap<String, String> = [
+        ("OTEL_SPAN_EVENT_COUNT_LIMIT".to_string(), "1".to_string()),
+        ("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT".to_string(), "2".to_string()),
+    ]
+    .into();
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer
+            .with_tracked_inactivity(false)
+            .with_threads(false)
+            .with_target(false)
+            .apply_standard_otel_vars(&vars)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("limited", a = 1, b = 2, c = 3).in_scope(|| {
+            tracing::info!("one");
+            tracing::info!("two");
+        });
+    });
+
+    let span = harness.span("limited");
+    assert_eq!(span.events.len(), 1);
+    assert!(span.has_attribute("otel.dropped_attributes_count", 1));
+
+    // OTEL_SDK_DISABLED silences the layer completely.
+    let disabled: HashMap<String, String> =
+        [("OTEL_SDK_DISABLED".to_string(), "true".to_string())].into();
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.apply_standard_otel_vars(&disabled));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("invisible").in_scope(|| {});
+    });
+    assert!(harness.finished_spans().is_empty());
+}
+
+#[test]
+fn suppression_guard_hides_instrumentation_from_the_layer() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("app_work").in_scope(|| {});
+
+        {
+            let _guard = n00_otel::suppress_tracing();
+            assert!(n00_otel::is_suppressed());
+            // Nested guards keep suppression until the outermost drops.
+            let inner = n00_otel::suppress_tracing();
+            drop(inner);
+            assert!(n00_otel::is_suppressed());
+            tracing::info_span!("exporter_internals").in_scope(|| {
+                tracing::info!("self-instrumentation");
+            });
+        }
+        assert!(!n00_otel::is_suppressed());
+
+        tracing::info_span!("after_export").in_scope(|| {});
+    });
+
+    let names: Vec<String> = exported_spans(&harness)
+        .iter()
+        .map(|s| s.name.to_string())
+        .collect();
+    assert_eq!(names, vec!["app_work".to_string(), "after_export".to_string()]);
+}
+
+#[test]
+fn lifecycle_events_record_each_enter_and_exit() {
+    let (subscriber, harness) = test_tracer(|layer| layer.with_lifecycle_events(true));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("reentrant");
+        span.in_scope(|| tracing::info!("working"));
+        span.in_scope(|| {});
+        drop(span);
+
+        tracing::info_span!("quiet_by_default").in_scope(|| {});
+    });
+
+    let span = harness.span("reentrant");
+    let names: Vec<&str> = span.events.iter().map(|e| e.name.as_ref()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "span.entered",
+            "working",
+            "span.exited",
+            "span.entered",
+            "span.exited",
+        ]
+    );
+}
+
+#[test]
+fn lifecycle_events_are_off_by_default() {
+    let (subscriber, harness) = test_tracer(|layer| layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("plain").in_scope(|| {});
+    });
+    assert!(harness.span("plain").events.is_empty());
+}
+
+#[test]
+fn batch_dictionary_shrinks_the_wire_and_round_trips() {
+    let plain = {
+        let harness = TestHarness::new();
+        tracing::subscriber::with_default(
+            Registry::default().with(harness.layer().with_tracked_inactivity(false)),
+            || {
+                // The attribute-heavy shape dictionary encoding targets:
+                // many spans sharing the same keys and enum-like values.
+                for i in 0..20 {
+                    tracing::info_span!(
+                        "repeated_operation_name",
+                        customer.account.tier = "enterprise",
+                        deployment.environment.name = "production-eu-west-1",
+                        service.namespace = "checkout-platform",
+                        messaging.destination.name = "orders.priority.high",
+                        http.request.method = "POST",
+                        i
+                    )
+                    .in_scope(|| {});
+                }
+            },
+        );
+        harness.finished_spans()
+    };
+
+    // Build the same batch document both ways.
+    let resource = opentelemetry_sdk::Resource::builder_empty().build();
+    let _ = resource;
+    let document = {
+        // Serialize through the public exporter to a buffer.
+        #[derive(Clone, Debug, Default)]
+        struct Buf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+        impl std::io::Write for Buf {
+            fn write(&mut self, b: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(b);
+                Ok(b.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        use opentelemetry_sdk::trace::SpanExporter as _;
+        let buf = Buf::default();
+        let exporter = n00_otel::OtlpJsonExporter::new(buf.clone());
+        futures_executor_block_on(exporter.export(plain.clone())).unwrap();
+        let bytes = buf.0.lock().unwrap().clone();
+        serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+    };
+
+    let plain_len = document.to_string().len();
+    let encoded = n00_otel::dictionary_encode(document.clone());
+    let encoded_len = encoded.to_string().len();
+    // IDs and timestamps are incompressible; the attribute portion is what
+    // the dictionary removes. Require a solid overall reduction.
+    assert!(
+        (encoded_len as f64) < plain_len as f64 * 0.6,
+        "expected >40% reduction, got {plain_len} -> {encoded_len}"
+    );
+    assert_eq!(n00_otel::dictionary_decode(encoded), Some(document));
+}
+
+/// Minimal executor for the exporter future in tests.
+fn futures_executor_block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            noop_raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        RawWaker::new(std::ptr::null(), &RawWakerVTable::new(clone, noop, noop, noop))
+    }
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn tail_ring_refunds_budget_on_rotation() {
+    // Budget sized for a handful of events; the ring holds 2.
+    let (subscriber, harness) = test_tracer(|layer| {
+        layer.with_tail_events(2).with_memory_budget(100_000)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let chatty = tracing::info_span!("chatty");
+        chatty.in_scope(|| {
+            // Far more event bytes than the budget if evictions leaked.
+            for i in 0..10_000 {
+                tracing::info!(i, "tick with a reasonably long message body");
+            }
+        });
+        // A later span still gets full event capture: the rotations above
+        // must not have consumed the global budget.
+        tracing::info_span!("later").in_scope(|| tracing::info!("still room"));
+    });
+
+    assert_eq!(harness.span("chatty").events.len(), 2);
+    assert_eq!(harness.span("later").events.len(), 1);
+}
+
+#[test]
+fn span_ext_add_event_respects_layer_policies() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) = test_tracer(|layer| layer.with_max_events_per_span(1));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("capped");
+        span.add_event("first", vec![]);
+        span.add_event("second, over the cap", vec![]);
+        span.in_scope(|| {});
+    });
+
+    let span = harness.span("capped");
+    assert_eq!(span.events.len(), 1);
+    assert_eq!(span.events[0].name, "first");
+    assert!(span.has_attribute("otel.dropped_event_count", 1));
+}
+
+#[test]
+fn attribute_value_length_limit_truncates_at_record_time() {
+    use n00_otel::testing::SpanDataExt;
+    use std::collections::HashMap;
+
+    let vars: HashMap<String, String> =
+        [("OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT".to_string(), "8".to_string())].into();
+    let (subscriber, harness) = test_tracer(|layer| layer.apply_standard_otel_vars(&vars));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("clipped", note = "a very long attribute value").in_scope(|| {
+            tracing::info!(detail = "another long event value", "evt");
+        });
+    });
+
+    let span = harness.span("clipped");
+    assert!(span.has_attribute("note", "a very l"));
+    let event_attr = span.events[0]
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == "detail")
+        .unwrap();
+    assert_eq!(event_attr.value, "another ".into());
+}
+
+#[test]
+fn stall_watchdog_flags_spans_that_never_close() {
+    use std::time::Duration;
+
+    let flagged = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let flagged_in_hook = flagged.clone();
+    let (subscriber, _harness) = test_tracer(move |layer| {
+        layer
+            .with_duration_budget(Duration::from_millis(10))
+            .with_budget_exceeded_hook(move |name, age| {
+                flagged_in_hook.lock().unwrap().push((name.to_string(), age));
+            })
+            .with_stall_watchdog(Duration::from_millis(10))
+    });
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let stalled = tracing::info_span!("stalled_job");
+    let _entered = stalled.enter();
+
+    // The span is still open; the watchdog must flag it anyway.
+    for _ in 0..200 {
+        if !flagged.lock().unwrap().is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let seen = flagged.lock().unwrap().clone();
+    assert_eq!(seen.len(), 1, "expected exactly one flag, got {seen:?}");
+    assert_eq!(seen[0].0, "stalled_job");
+    assert!(seen[0].1 >= Duration::from_millis(10));
+
+    // One report per span: more sweeps don't re-flag it.
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(flagged.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn configured_fields_are_inherited_by_descendants() {
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_inherited_fields(["tenant.id", "request.id"]));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("request_root", tenant.id = "acme", internal = 1);
+        root.in_scope(|| {
+            tracing::info_span!("middle").in_scope(|| {
+                tracing::info_span!("leaf").in_scope(|| {});
+            });
+            // A child's own value wins over the inherited one.
+            tracing::info_span!("override_child", tenant.id = "self-recorded").in_scope(|| {});
+        });
+    });
+
+    // The value propagates transitively to every descendant...
+    for name in ["middle", "leaf"] {
+        assert!(
+            harness.span(name).has_attribute("tenant.id", "acme"),
+            "{name} missing inherited tenant.id"
+        );
+    }
+    // ...but unconfigured fields do not.
+    assert!(harness.span("leaf").attribute("internal").is_none());
+    assert!(harness
+        .span("override_child")
+        .has_attribute("tenant.id", "self-recorded"));
+}
+
+#[test]
+fn profiling_stack_survives_spans_the_layer_is_not_tracking() {
+    use tracing::level_filters::LevelFilter;
+
+    let targets = n00_otel::DynamicTargets::new(LevelFilter::INFO);
+    targets.set_target("untracked", LevelFilter::OFF);
+    let (subscriber, _harness) = test_tracer(|layer| {
+        layer
+            .with_profiling_correlation(true)
+            .with_dynamic_targets(targets)
+    });
+
+    tracing::subscriber::with_default(subscriber, || {
+        let outer = tracing::info_span!("tracked_outer");
+        let _outer = outer.enter();
+        let (outer_trace, outer_span) =
+            n00_otel::profiling::current_thread_trace_ids().expect("outer ids");
+
+        {
+            // This span has no OtelData for the layer; entering and exiting
+            // it must not touch the profiling stack.
+            let untracked = tracing::info_span!(target: "untracked", "scaffolding");
+            let _untracked = untracked.enter();
+            assert_eq!(
+                n00_otel::profiling::current_thread_trace_ids(),
+                Some((outer_trace, outer_span))
+            );
+        }
+
+        // After the unbalanced-looking exit, the outer entry must survive.
+        assert_eq!(
+            n00_otel::profiling::current_thread_trace_ids(),
+            Some((outer_trace, outer_span))
+        );
+    });
+    assert!(n00_otel::profiling::current_thread_trace_ids().is_none());
+}
+
+#[test]
+fn xray_conventions_promote_annotation_fields() {
+    use n00_otel::conventions::ConventionsMode;
+    use n00_otel::testing::SpanDataExt;
+
+    let (subscriber, harness) =
+        test_tracer(|layer| layer.with_conventions(ConventionsMode::Xray));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!(
+            "segment",
+            xray.annotation.customer_id = "acme-7",
+            plain.metadata = "untouched"
+        )
+        .in_scope(|| {});
+    });
+
+    let span = harness.span("segment");
+    assert!(span.has_attribute("customer_id", "acme-7"));
+    assert!(!span
+        .attributes
+        .iter()
+        .any(|kv| kv.key.as_str().starts_with("xray.annotation.")));
+    assert!(span.has_attribute(
+        "aws.xray.annotations",
+        opentelemetry::Value::Array(opentelemetry::Array::String(vec!["customer_id".into()]))
+    ));
+    assert!(span.has_attribute("plain.metadata", "untouched"));
+}